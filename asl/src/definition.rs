@@ -0,0 +1,302 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::error::AslError;
+
+/// A state machine definition, restricted to the subset of ASL used in this repository
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct StateMachine {
+    pub comment: Option<String>,
+    pub start_at: String,
+    pub states: HashMap<String, State>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "Type")]
+pub enum State {
+    Pass(PassState),
+    Task(TaskState),
+    Parallel(ParallelState),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct PassState {
+    pub comment: Option<String>,
+    pub next: Option<String>,
+    #[serde(default)]
+    pub end: bool,
+    pub input_path: Option<String>,
+    pub parameters: Option<Value>,
+    pub result: Option<Value>,
+    #[serde(default = "default_result_path")]
+    pub result_path: Option<String>,
+    pub output_path: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct TaskState {
+    pub comment: Option<String>,
+    pub resource: String,
+    pub next: Option<String>,
+    #[serde(default)]
+    pub end: bool,
+    pub input_path: Option<String>,
+    pub parameters: Option<Value>,
+    pub result_selector: Option<Value>,
+    #[serde(default = "default_result_path")]
+    pub result_path: Option<String>,
+    pub output_path: Option<String>,
+    #[serde(default)]
+    pub retry: Vec<Retrier>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct ParallelState {
+    pub comment: Option<String>,
+    pub branches: Vec<Branch>,
+    pub next: Option<String>,
+    #[serde(default)]
+    pub end: bool,
+    pub input_path: Option<String>,
+    pub parameters: Option<Value>,
+    pub result_selector: Option<Value>,
+    #[serde(default = "default_result_path")]
+    pub result_path: Option<String>,
+    pub output_path: Option<String>,
+    #[serde(default)]
+    pub retry: Vec<Retrier>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct Branch {
+    pub comment: Option<String>,
+    pub start_at: String,
+    pub states: HashMap<String, State>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct Retrier {
+    pub error_equals: Vec<String>,
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_backoff_rate")]
+    pub backoff_rate: f64,
+}
+
+// `ResultPath: null` discards the result, while a missing `ResultPath` means `$`
+fn default_result_path() -> Option<String> {
+    Some("$".to_string())
+}
+
+fn default_interval_seconds() -> u64 {
+    1
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_rate() -> f64 {
+    2.0
+}
+
+impl State {
+    pub fn next(&self) -> Option<&str> {
+        match self {
+            State::Pass(state) => state.next.as_deref(),
+            State::Task(state) => state.next.as_deref(),
+            State::Parallel(state) => state.next.as_deref(),
+        }
+    }
+
+    pub fn is_end(&self) -> bool {
+        match self {
+            State::Pass(state) => state.end,
+            State::Task(state) => state.end,
+            State::Parallel(state) => state.end,
+        }
+    }
+}
+
+impl Retrier {
+    /// Whether this retrier applies to the given error name
+    pub fn matches(&self, error: &str) -> bool {
+        self.error_equals.iter().any(|candidate| {
+            candidate == error
+                || candidate == "States.ALL"
+                || (candidate == "States.TaskFailed" && error != "States.Timeout")
+        })
+    }
+}
+
+impl StateMachine {
+    /// Parses a definition written in YAML (or JSON, which is a subset of YAML)
+    pub fn from_yaml(definition: &str) -> Result<Self, AslError> {
+        let machine: StateMachine = serde_yaml::from_str(definition)?;
+        validate_transitions(&machine.start_at, &machine.states)?;
+        Ok(machine)
+    }
+
+    /// Replaces the `${Name}` placeholders the same way SAM's `DefinitionSubstitutions` does,
+    /// then parses the resulting definition
+    pub fn from_yaml_with_substitutions(
+        definition: &str,
+        substitutions: &HashMap<&str, &str>,
+    ) -> Result<Self, AslError> {
+        let mut definition = definition.to_string();
+        for (name, value) in substitutions {
+            definition = definition.replace(&format!("${{{}}}", name), value);
+        }
+        Self::from_yaml(&definition)
+    }
+}
+
+fn validate_transitions(start_at: &str, states: &HashMap<String, State>) -> Result<(), AslError> {
+    if !states.contains_key(start_at) {
+        return Err(AslError::UnknownState(start_at.to_string()));
+    }
+
+    for (name, state) in states {
+        match (state.next(), state.is_end()) {
+            (Some(next), false) if states.contains_key(next) => {}
+            (Some(next), false) => return Err(AslError::UnknownState(next.to_string())),
+            (None, true) => {}
+            _ => return Err(AslError::InvalidTransition(name.clone())),
+        }
+
+        if let State::Parallel(parallel) = state {
+            for branch in &parallel.branches {
+                validate_transitions(&branch.start_at, &branch.states)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pass_state_defaults() {
+        let machine = StateMachine::from_yaml(
+            r#"
+StartAt: First
+States:
+  First:
+    Type: Pass
+    End: true
+"#,
+        )
+        .unwrap();
+
+        match &machine.states["First"] {
+            State::Pass(pass) => assert_eq!(pass.result_path.as_deref(), Some("$")),
+            other => panic!("Unexpected state {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_null_result_path() {
+        let machine = StateMachine::from_yaml(
+            r#"
+StartAt: First
+States:
+  First:
+    Type: Pass
+    ResultPath: null
+    End: true
+"#,
+        )
+        .unwrap();
+
+        match &machine.states["First"] {
+            State::Pass(pass) => assert_eq!(pass.result_path, None),
+            other => panic!("Unexpected state {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_retrier_defaults() {
+        let retrier: Retrier = serde_yaml::from_str("ErrorEquals: [States.ALL]").unwrap();
+        assert_eq!(retrier.interval_seconds, 1);
+        assert_eq!(retrier.max_attempts, 3);
+        assert_eq!(retrier.backoff_rate, 2.0);
+        assert!(retrier.matches("Lambda.ServiceException"));
+    }
+
+    #[test]
+    fn test_rejects_unsupported_state_type() {
+        let result = StateMachine::from_yaml(
+            r#"
+StartAt: Choose
+States:
+  Choose:
+    Type: Choice
+    Choices: []
+"#,
+        );
+        assert!(matches!(result, Err(AslError::Parse(_))));
+    }
+
+    #[test]
+    fn test_rejects_unknown_fields() {
+        let result = StateMachine::from_yaml(
+            r#"
+StartAt: First
+States:
+  First:
+    Type: Pass
+    ResultPth: $.typo
+    End: true
+"#,
+        );
+        assert!(matches!(result, Err(AslError::Parse(_))));
+    }
+
+    #[test]
+    fn test_rejects_dangling_next() {
+        let result = StateMachine::from_yaml(
+            r#"
+StartAt: First
+States:
+  First:
+    Type: Pass
+    Next: Second
+"#,
+        );
+        assert!(matches!(result, Err(AslError::UnknownState(name)) if name == "Second"));
+    }
+
+    #[test]
+    fn test_substitutions() {
+        let substitutions = HashMap::from([("FunctionArn", "arn:local:function")]);
+        let machine = StateMachine::from_yaml_with_substitutions(
+            r#"
+StartAt: Invoke
+States:
+  Invoke:
+    Type: Task
+    Resource: ${FunctionArn}
+    End: true
+"#,
+            &substitutions,
+        )
+        .unwrap();
+
+        match &machine.states["Invoke"] {
+            State::Task(task) => assert_eq!(task.resource, "arn:local:function"),
+            other => panic!("Unexpected state {:?}", other),
+        }
+    }
+}
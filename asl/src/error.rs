@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AslError {
+    #[error("Failed to parse the state machine definition: {0}")]
+    Parse(#[from] serde_yaml::Error),
+    #[error("State `{0}` does not exist")]
+    UnknownState(String),
+    #[error("State `{0}` must have either `Next` or `End: true`")]
+    InvalidTransition(String),
+    #[error("Invalid path `{0}`")]
+    InvalidPath(String),
+    #[error("Path `{0}` does not match any value in the input")]
+    PathNotFound(String),
+    #[error("Invalid intrinsic function call `{0}`")]
+    InvalidIntrinsic(String),
+    #[error("Unsupported intrinsic function `{0}`")]
+    UnsupportedIntrinsic(String),
+    #[error("No task implementation registered for resource `{0}`")]
+    UnknownResource(String),
+    #[error("State `{state}` failed with {error}: {cause}")]
+    TaskFailed {
+        state: String,
+        error: String,
+        cause: String,
+    },
+}
@@ -0,0 +1,586 @@
+use futures::future::{join_all, BoxFuture};
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, future::Future, sync::Mutex, time::Duration};
+
+use crate::{
+    definition::{Retrier, State, StateMachine},
+    error::AslError,
+    intrinsics, path,
+};
+
+/// The error reported by a task, equivalent to the `Error` and `Cause` fields
+/// that Step Functions records when a Lambda invocation fails
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskError {
+    pub error: String,
+    pub cause: String,
+}
+
+impl TaskError {
+    pub fn new(error: impl Into<String>, cause: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            cause: cause.into(),
+        }
+    }
+}
+
+/// An implementation for the `Resource` of a `Task` state
+pub trait Task: Send + Sync {
+    fn invoke(&self, input: Value) -> BoxFuture<'_, Result<Value, TaskError>>;
+}
+
+impl<F, Fut> Task for F
+where
+    F: Fn(Value) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Value, TaskError>> + Send + 'static,
+{
+    fn invoke(&self, input: Value) -> BoxFuture<'_, Result<Value, TaskError>> {
+        Box::pin(self(input))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryEvent {
+    StateEntered {
+        state: String,
+        input: Value,
+    },
+    StateExited {
+        state: String,
+        output: Value,
+    },
+    TaskFailed {
+        state: String,
+        error: String,
+    },
+    RetryScheduled {
+        state: String,
+        attempt: u32,
+        delay: Duration,
+    },
+}
+
+/// The result of a successful execution
+#[derive(Debug)]
+pub struct Execution {
+    pub output: Value,
+    pub history: Vec<HistoryEvent>,
+}
+
+struct Run {
+    input: Value,
+    history: Mutex<Vec<HistoryEvent>>,
+}
+
+impl Run {
+    fn record(&self, event: HistoryEvent) {
+        self.history.lock().unwrap().push(event);
+    }
+
+    fn context(&self, state: &str) -> Value {
+        json!({
+            "Execution": { "Input": self.input, "Name": "local" },
+            "State": { "Name": state },
+        })
+    }
+}
+
+/// Runs a [`StateMachine`] against pluggable task implementations
+pub struct Executor {
+    machine: StateMachine,
+    tasks: HashMap<String, Box<dyn Task>>,
+    delays: bool,
+}
+
+impl Executor {
+    pub fn new(machine: StateMachine) -> Self {
+        Self {
+            machine,
+            tasks: HashMap::new(),
+            delays: true,
+        }
+    }
+
+    /// Registers the implementation invoked by every `Task` state with the given `Resource`
+    pub fn with_task(mut self, resource: impl Into<String>, task: impl Task + 'static) -> Self {
+        self.tasks.insert(resource.into(), Box::new(task));
+        self
+    }
+
+    /// Records the retry delays in the history without actually waiting for them
+    pub fn without_delays(mut self) -> Self {
+        self.delays = false;
+        self
+    }
+
+    pub async fn execute(&self, input: Value) -> Result<Execution, AslError> {
+        let run = Run {
+            input: input.clone(),
+            history: Mutex::new(vec![]),
+        };
+        let output = self
+            .run_states(&self.machine.start_at, &self.machine.states, input, &run)
+            .await?;
+
+        Ok(Execution {
+            output,
+            history: run.history.into_inner().unwrap(),
+        })
+    }
+
+    fn run_states<'a>(
+        &'a self,
+        start_at: &'a str,
+        states: &'a HashMap<String, State>,
+        input: Value,
+        run: &'a Run,
+    ) -> BoxFuture<'a, Result<Value, AslError>> {
+        Box::pin(async move {
+            let mut current = start_at;
+            let mut data = input;
+
+            loop {
+                let state = states
+                    .get(current)
+                    .ok_or_else(|| AslError::UnknownState(current.to_string()))?;
+                run.record(HistoryEvent::StateEntered {
+                    state: current.to_string(),
+                    input: data.clone(),
+                });
+
+                data = self.run_state(current, state, data, run).await?;
+                run.record(HistoryEvent::StateExited {
+                    state: current.to_string(),
+                    output: data.clone(),
+                });
+
+                match state.next() {
+                    Some(next) if !state.is_end() => current = next,
+                    _ => return Ok(data),
+                }
+            }
+        })
+    }
+
+    async fn run_state(
+        &self,
+        name: &str,
+        state: &State,
+        input: Value,
+        run: &Run,
+    ) -> Result<Value, AslError> {
+        let context = run.context(name);
+
+        let output = match state {
+            State::Pass(pass) => {
+                let effective_input = effective_input(
+                    &input,
+                    pass.input_path.as_deref(),
+                    pass.parameters.as_ref(),
+                    &context,
+                )?;
+                let result = pass.result.clone().unwrap_or(effective_input);
+                apply_result_path(input, pass.result_path.as_deref(), result)?
+            }
+            State::Task(task) => {
+                let effective_input = effective_input(
+                    &input,
+                    task.input_path.as_deref(),
+                    task.parameters.as_ref(),
+                    &context,
+                )?;
+                let implementation = self
+                    .tasks
+                    .get(&task.resource)
+                    .ok_or_else(|| AslError::UnknownResource(task.resource.clone()))?;
+                let result = self
+                    .with_retry(name, &task.retry, run, || {
+                        let input = effective_input.clone();
+                        Box::pin(async move {
+                            implementation
+                                .invoke(input)
+                                .await
+                                .map_err(|e| AslError::TaskFailed {
+                                    state: name.to_string(),
+                                    error: e.error,
+                                    cause: e.cause,
+                                })
+                        })
+                    })
+                    .await?;
+                let result = match &task.result_selector {
+                    Some(selector) => apply_template(selector, &result, &context)?,
+                    None => result,
+                };
+                apply_result_path(input, task.result_path.as_deref(), result)?
+            }
+            State::Parallel(parallel) => {
+                let effective_input = effective_input(
+                    &input,
+                    parallel.input_path.as_deref(),
+                    parallel.parameters.as_ref(),
+                    &context,
+                )?;
+                let result = self
+                    .with_retry(name, &parallel.retry, run, || {
+                        let branches = parallel.branches.iter().map(|branch| {
+                            self.run_states(
+                                &branch.start_at,
+                                &branch.states,
+                                effective_input.clone(),
+                                run,
+                            )
+                        });
+                        Box::pin(async move {
+                            join_all(branches)
+                                .await
+                                .into_iter()
+                                .collect::<Result<Vec<_>, _>>()
+                                .map(Value::Array)
+                        })
+                    })
+                    .await?;
+                let result = match &parallel.result_selector {
+                    Some(selector) => apply_template(selector, &result, &context)?,
+                    None => result,
+                };
+                apply_result_path(input, parallel.result_path.as_deref(), result)?
+            }
+        };
+
+        let output_path = match state {
+            State::Pass(pass) => pass.output_path.as_deref(),
+            State::Task(task) => task.output_path.as_deref(),
+            State::Parallel(parallel) => parallel.output_path.as_deref(),
+        };
+        match output_path {
+            Some(output_path) => path::select(&output, output_path).cloned(),
+            None => Ok(output),
+        }
+    }
+
+    /// Runs `attempt` until it succeeds, following the first retrier that matches
+    /// each failure, or until the matching retrier runs out of attempts
+    async fn with_retry<'a, F>(
+        &self,
+        name: &str,
+        retriers: &[Retrier],
+        run: &Run,
+        attempt: F,
+    ) -> Result<Value, AslError>
+    where
+        F: Fn() -> BoxFuture<'a, Result<Value, AslError>>,
+    {
+        let mut attempts = vec![0u32; retriers.len()];
+
+        loop {
+            let error = match attempt().await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            let AslError::TaskFailed {
+                error: error_name, ..
+            } = &error
+            else {
+                return Err(error);
+            };
+            run.record(HistoryEvent::TaskFailed {
+                state: name.to_string(),
+                error: error_name.clone(),
+            });
+
+            let Some(index) = retriers.iter().position(|r| r.matches(error_name)) else {
+                return Err(error);
+            };
+            let retrier = &retriers[index];
+            if attempts[index] >= retrier.max_attempts {
+                return Err(error);
+            }
+
+            let delay = Duration::from_secs_f64(
+                retrier.interval_seconds as f64 * retrier.backoff_rate.powi(attempts[index] as i32),
+            );
+            attempts[index] += 1;
+            run.record(HistoryEvent::RetryScheduled {
+                state: name.to_string(),
+                attempt: attempts[index],
+                delay,
+            });
+            if self.delays {
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+fn effective_input(
+    input: &Value,
+    input_path: Option<&str>,
+    parameters: Option<&Value>,
+    context: &Value,
+) -> Result<Value, AslError> {
+    let selected = match input_path {
+        Some(input_path) => path::select(input, input_path)?.clone(),
+        None => input.clone(),
+    };
+    match parameters {
+        Some(parameters) => apply_template(parameters, &selected, context),
+        None => Ok(selected),
+    }
+}
+
+fn apply_result_path(
+    input: Value,
+    result_path: Option<&str>,
+    result: Value,
+) -> Result<Value, AslError> {
+    match result_path {
+        Some(result_path) => path::insert(input, result_path, result),
+        None => Ok(input),
+    }
+}
+
+/// Builds a payload from a `Parameters` or `ResultSelector` template, where the
+/// keys ending in `.$` are resolved as paths or intrinsic function calls
+fn apply_template(template: &Value, input: &Value, context: &Value) -> Result<Value, AslError> {
+    match template {
+        Value::Object(fields) => {
+            let mut payload = Map::new();
+            for (key, value) in fields {
+                match key.strip_suffix(".$") {
+                    Some(key) => {
+                        let expression = value
+                            .as_str()
+                            .ok_or_else(|| AslError::InvalidPath(value.to_string()))?;
+                        let resolved = if expression.starts_with('$') {
+                            path::resolve(expression, input, context)?
+                        } else {
+                            intrinsics::evaluate(expression, input, context)?
+                        };
+                        payload.insert(key.to_string(), resolved);
+                    }
+                    None => {
+                        payload.insert(key.clone(), apply_template(value, input, context)?);
+                    }
+                }
+            }
+            Ok(Value::Object(payload))
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| apply_template(item, input, context))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        other => Ok(other.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn machine(definition: &str) -> StateMachine {
+        StateMachine::from_yaml(definition).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pass_with_parameters_and_result_path() {
+        let executor = Executor::new(machine(
+            r#"
+StartAt: Defaults
+States:
+  Defaults:
+    Type: Pass
+    ResultPath: $.defaults
+    Parameters:
+      dryRun: false
+    Next: Apply
+  Apply:
+    Type: Pass
+    Parameters:
+      config.$: States.JsonMerge($.defaults, $$.Execution.Input, false)
+    End: true
+"#,
+        ));
+
+        let execution = executor.execute(json!({ "time": "now" })).await.unwrap();
+
+        assert_eq!(
+            execution.output,
+            json!({ "config": { "dryRun": false, "time": "now" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_result_path_and_output_path() {
+        let executor = Executor::new(machine(
+            r#"
+StartAt: Invoke
+States:
+  Invoke:
+    Type: Task
+    Resource: echo
+    ResultPath: $.result
+    OutputPath: $.result
+    End: true
+"#,
+        ))
+        .with_task("echo", |input: Value| async move {
+            Ok(json!({ "echo": input }))
+        });
+
+        let execution = executor.execute(json!({ "a": 1 })).await.unwrap();
+
+        assert_eq!(execution.output, json!({ "echo": { "a": 1 } }));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_result_selector() {
+        let executor = Executor::new(machine(
+            r#"
+StartAt: Fan Out
+States:
+  Fan Out:
+    Type: Parallel
+    ResultSelector:
+      First.$: $[0].first
+      Second.$: $[1].second
+    ResultPath: $.data
+    End: true
+    Branches:
+      - StartAt: One
+        States:
+          One:
+            Type: Pass
+            Result: 1
+            ResultPath: $.first
+            End: true
+      - StartAt: Two
+        States:
+          Two:
+            Type: Pass
+            Result: 2
+            ResultPath: $.second
+            End: true
+"#,
+        ));
+
+        let execution = executor.execute(json!({ "a": 1 })).await.unwrap();
+
+        assert_eq!(
+            execution.output,
+            json!({ "a": 1, "data": { "First": 1, "Second": 2 } })
+        );
+    }
+
+    const FLAKY_TASK: &str = r#"
+StartAt: Invoke
+States:
+  Invoke:
+    Type: Task
+    Resource: flaky
+    Retry:
+      - ErrorEquals: [Lambda.ServiceException]
+        IntervalSeconds: 2
+        MaxAttempts: 2
+        BackoffRate: 3
+    End: true
+"#;
+
+    fn failing_times(failures: usize, calls: Arc<AtomicUsize>) -> impl Task {
+        move |_| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if call < failures {
+                    Err(TaskError::new("Lambda.ServiceException", "boom"))
+                } else {
+                    Ok(json!("done"))
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_backoff() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let executor = Executor::new(machine(FLAKY_TASK))
+            .with_task("flaky", failing_times(2, calls.clone()))
+            .without_delays();
+
+        let execution = executor.execute(json!({})).await.unwrap();
+
+        assert_eq!(execution.output, json!("done"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        let delays: Vec<Duration> = execution
+            .history
+            .iter()
+            .filter_map(|event| match event {
+                HistoryEvent::RetryScheduled { delay, .. } => Some(*delay),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(delays, vec![Duration::from_secs(2), Duration::from_secs(6)]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let executor = Executor::new(machine(FLAKY_TASK))
+            .with_task("flaky", failing_times(3, calls.clone()))
+            .without_delays();
+
+        let result = executor.execute(json!({})).await;
+
+        assert!(matches!(
+            result,
+            Err(AslError::TaskFailed { error, .. }) if error == "Lambda.ServiceException"
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_matching_error_is_not_retried() {
+        let executor = Executor::new(machine(
+            r#"
+StartAt: Invoke
+States:
+  Invoke:
+    Type: Task
+    Resource: failing
+    Retry:
+      - ErrorEquals: [Lambda.ServiceException]
+    End: true
+"#,
+        ))
+        .with_task("failing", |_| async {
+            Err(TaskError::new("Handled", "nope"))
+        })
+        .without_delays();
+
+        assert!(executor.execute(json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_resource() {
+        let executor = Executor::new(machine(
+            r#"
+StartAt: Invoke
+States:
+  Invoke:
+    Type: Task
+    Resource: missing
+    End: true
+"#,
+        ));
+
+        assert!(matches!(
+            executor.execute(json!({})).await,
+            Err(AslError::UnknownResource(resource)) if resource == "missing"
+        ));
+    }
+}
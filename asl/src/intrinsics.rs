@@ -0,0 +1,175 @@
+use serde_json::Value;
+
+use crate::{error::AslError, path};
+
+/// Evaluates an intrinsic function call such as
+/// `States.JsonMerge($.defaults, $$.Execution.Input, false)`
+pub fn evaluate(expression: &str, input: &Value, context: &Value) -> Result<Value, AslError> {
+    let invalid = || AslError::InvalidIntrinsic(expression.to_string());
+    let expression = expression.trim();
+    let open = expression.find('(').ok_or_else(invalid)?;
+    let arguments = expression[open + 1..]
+        .strip_suffix(')')
+        .ok_or_else(invalid)?;
+    let name = &expression[..open];
+
+    let arguments = split_arguments(arguments)
+        .ok_or_else(invalid)?
+        .into_iter()
+        .map(|argument| evaluate_argument(argument, input, context))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match name {
+        "States.JsonMerge" => match arguments.as_slice() {
+            [left @ Value::Object(_), right @ Value::Object(_), Value::Bool(deep)] => {
+                Ok(json_merge(left.clone(), right.clone(), *deep))
+            }
+            _ => Err(invalid()),
+        },
+        _ => Err(AslError::UnsupportedIntrinsic(name.to_string())),
+    }
+}
+
+/// Splits the arguments of a call on the commas that are not nested in a call or a string
+fn split_arguments(arguments: &str) -> Option<Vec<&str>> {
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut start = 0;
+
+    for (position, c) in arguments.char_indices() {
+        match c {
+            '\'' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                result.push(arguments[start..position].trim());
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if in_string || depth != 0 {
+        return None;
+    }
+    let last = arguments[start..].trim();
+    if !last.is_empty() || !result.is_empty() {
+        result.push(last);
+    }
+    Some(result)
+}
+
+fn evaluate_argument(argument: &str, input: &Value, context: &Value) -> Result<Value, AslError> {
+    if argument.starts_with('$') {
+        return path::resolve(argument, input, context);
+    }
+    if argument.starts_with("States.") {
+        return evaluate(argument, input, context);
+    }
+    if let Some(text) = argument
+        .strip_prefix('\'')
+        .and_then(|a| a.strip_suffix('\''))
+    {
+        return Ok(Value::String(text.to_string()));
+    }
+    serde_json::from_str(argument).map_err(|_| AslError::InvalidIntrinsic(argument.to_string()))
+}
+
+fn json_merge(left: Value, right: Value, deep: bool) -> Value {
+    match (left, right) {
+        (Value::Object(mut left), Value::Object(right)) => {
+            for (key, value) in right {
+                let merged = match left.remove(&key) {
+                    Some(existing) if deep => json_merge(existing, value, deep),
+                    _ => value,
+                };
+                left.insert(key, merged);
+            }
+            Value::Object(left)
+        }
+        (_, right) => right,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_merge_is_shallow() {
+        let input = json!({ "defaults": { "dryRun": false, "nested": { "a": 1, "b": 2 } } });
+        let context = json!({ "Execution": { "Input": { "time": "now", "nested": { "a": 3 } } } });
+
+        let result = evaluate(
+            "States.JsonMerge($.defaults, $$.Execution.Input, false)",
+            &input,
+            &context,
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            json!({ "dryRun": false, "time": "now", "nested": { "a": 3 } })
+        );
+    }
+
+    #[test]
+    fn test_json_merge_deep() {
+        let input =
+            json!({ "left": { "nested": { "a": 1, "b": 2 } }, "right": { "nested": { "a": 3 } } });
+
+        let result = evaluate(
+            "States.JsonMerge($.left, $.right, true)",
+            &input,
+            &json!({}),
+        )
+        .unwrap();
+
+        assert_eq!(result, json!({ "nested": { "a": 3, "b": 2 } }));
+    }
+
+    #[test]
+    fn test_nested_calls() {
+        let input = json!({ "a": { "x": 1 }, "b": { "y": 2 }, "c": { "z": 3 } });
+
+        let result = evaluate(
+            "States.JsonMerge(States.JsonMerge($.a, $.b, false), $.c, false)",
+            &input,
+            &json!({}),
+        )
+        .unwrap();
+
+        assert_eq!(result, json!({ "x": 1, "y": 2, "z": 3 }));
+    }
+
+    #[test]
+    fn test_json_merge_requires_objects() {
+        let input = json!({ "a": { "x": 1 }, "b": [1, 2] });
+        assert!(matches!(
+            evaluate("States.JsonMerge($.a, $.b, false)", &input, &json!({})),
+            Err(AslError::InvalidIntrinsic(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_function() {
+        assert!(matches!(
+            evaluate("States.Format('{}', 'a')", &json!({}), &json!({})),
+            Err(AslError::UnsupportedIntrinsic(name)) if name == "States.Format"
+        ));
+    }
+
+    #[test]
+    fn test_missing_argument_path() {
+        assert!(matches!(
+            evaluate(
+                "States.JsonMerge($.missing, $.other, false)",
+                &json!({}),
+                &json!({})
+            ),
+            Err(AslError::PathNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+//! A small interpreter for the subset of the Amazon States Language used by
+//! `statemachine/create_issue.asl.yaml`: `Pass`, `Task` and `Parallel` states,
+//! `InputPath`/`Parameters`/`ResultSelector`/`ResultPath`/`OutputPath`,
+//! the `States.JsonMerge` intrinsic and `Retry` policies.
+//!
+//! Tasks are pluggable, so a definition can be run locally against stubs or
+//! in-process implementations of the Lambda functions.
+
+mod definition;
+mod error;
+mod executor;
+mod intrinsics;
+mod path;
+
+pub use definition::{Branch, ParallelState, PassState, Retrier, State, StateMachine, TaskState};
+pub use error::AslError;
+pub use executor::{Execution, Executor, HistoryEvent, Task, TaskError};
@@ -0,0 +1,173 @@
+use serde_json::{Map, Value};
+
+use crate::error::AslError;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+/// Parses the subset of JSONPath used by ASL reference paths: `$`, `$.field`, `$[0]`
+/// and `$['field']`, in any combination
+fn parse(path: &str) -> Result<Vec<Segment>, AslError> {
+    let invalid = || AslError::InvalidPath(path.to_string());
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments = vec![];
+
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(Segment::Field(after_dot[..end].to_string()));
+            rest = &after_dot[end..];
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let end = after_bracket.find(']').ok_or_else(invalid)?;
+            let inner = &after_bracket[..end];
+            let segment =
+                if let Some(quoted) = inner.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+                    Segment::Field(quoted.to_string())
+                } else {
+                    Segment::Index(inner.parse().map_err(|_| invalid())?)
+                };
+            segments.push(segment);
+            rest = &after_bracket[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Selects the value at `path` inside `value`
+pub fn select<'a>(value: &'a Value, path: &str) -> Result<&'a Value, AslError> {
+    parse(path)?
+        .iter()
+        .try_fold(value, |current, segment| match segment {
+            Segment::Field(name) => current.get(name.as_str()),
+            Segment::Index(index) => current.get(*index),
+        })
+        .ok_or_else(|| AslError::PathNotFound(path.to_string()))
+}
+
+/// Resolves a path that points either to the state input (`$`) or to the context object (`$$`)
+pub fn resolve(path: &str, input: &Value, context: &Value) -> Result<Value, AslError> {
+    match path.strip_prefix('$') {
+        Some(context_path) if context_path.starts_with('$') => {
+            select(context, context_path).cloned()
+        }
+        _ => select(input, path).cloned(),
+    }
+}
+
+/// Stores `result` at `path` inside `target`, creating intermediate objects as needed,
+/// which is how `ResultPath` combines the state input with the state result
+pub fn insert(target: Value, path: &str, result: Value) -> Result<Value, AslError> {
+    let segments = parse(path)?;
+    if segments.is_empty() {
+        return Ok(result);
+    }
+
+    let mut target = target;
+    let mut current = &mut target;
+    for (position, segment) in segments.iter().enumerate() {
+        let Segment::Field(name) = segment else {
+            return Err(AslError::InvalidPath(path.to_string()));
+        };
+        let object = current
+            .as_object_mut()
+            .ok_or_else(|| AslError::PathNotFound(path.to_string()))?;
+
+        if position == segments.len() - 1 {
+            object.insert(name.clone(), result);
+            break;
+        }
+        current = object
+            .entry(name.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_root() {
+        let value = json!({ "a": 1 });
+        assert_eq!(select(&value, "$").unwrap(), &value);
+    }
+
+    #[test]
+    fn test_select_nested_fields_and_indexes() {
+        let value = json!([{ "Quote": { "id": 1 } }, { "Book": { "id": "b" } }]);
+        assert_eq!(select(&value, "$[0].Quote.id").unwrap(), &json!(1));
+        assert_eq!(select(&value, "$[1]['Book'].id").unwrap(), &json!("b"));
+    }
+
+    #[test]
+    fn test_select_missing_path() {
+        let value = json!([{ "Links": [] }]);
+        assert!(matches!(
+            select(&value, "$[0].Linkz"),
+            Err(AslError::PathNotFound(path)) if path == "$[0].Linkz"
+        ));
+        assert!(matches!(
+            select(&value, "$[3].Links"),
+            Err(AslError::PathNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        assert!(matches!(
+            select(&json!({}), "a.b"),
+            Err(AslError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            select(&json!({}), "$..a"),
+            Err(AslError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            select(&json!({}), "$[x]"),
+            Err(AslError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_context_path() {
+        let context = json!({ "Execution": { "Input": { "time": "now" } } });
+        assert_eq!(
+            resolve("$$.Execution.Input.time", &json!({}), &context).unwrap(),
+            json!("now")
+        );
+        assert_eq!(
+            resolve("$.a", &json!({ "a": 1 }), &context).unwrap(),
+            json!(1)
+        );
+    }
+
+    #[test]
+    fn test_insert_at_root_replaces_input() {
+        let result = insert(json!({ "a": 1 }), "$", json!({ "b": 2 })).unwrap();
+        assert_eq!(result, json!({ "b": 2 }));
+    }
+
+    #[test]
+    fn test_insert_creates_intermediate_objects() {
+        let result = insert(json!({ "a": 1 }), "$.data.Quote", json!({ "id": 3 })).unwrap();
+        assert_eq!(result, json!({ "a": 1, "data": { "Quote": { "id": 3 } } }));
+    }
+
+    #[test]
+    fn test_insert_into_non_object_fails() {
+        assert!(insert(json!("text"), "$.a", json!(1)).is_err());
+        assert!(insert(json!({}), "$[0]", json!(1)).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use asl::{AslError, Executor, HistoryEvent, StateMachine, Task, TaskError};
+use create_issue::model::Event;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+const DEFINITION: &str = include_str!("../../statemachine/create_issue.asl.yaml");
+const ISSUE_435: &str = include_str!("../../functions/create-issue/events/issue-435.json");
+
+fn state_machine() -> StateMachine {
+    let substitutions = HashMap::from([
+        ("FetchIssueNumberFunctionArn", "fetch-issue-number"),
+        ("FetchQuoteFunctionArn", "fetch-quote"),
+        ("FetchBookFunctionArn", "fetch-book"),
+        ("FetchSponsorFunctionArn", "fetch-sponsor"),
+        ("FetchLinksFunctionArn", "fetch-links"),
+        ("CreateIssueFunctionArn", "create-issue"),
+    ]);
+    StateMachine::from_yaml_with_substitutions(DEFINITION, &substitutions).unwrap()
+}
+
+fn fixture() -> Value {
+    serde_json::from_str(ISSUE_435).unwrap()
+}
+
+/// The input that EventBridge sends on the weekly schedule
+fn scheduled_event() -> Value {
+    let mut input = fixture()["config"].clone();
+    input.as_object_mut().unwrap().remove("dryRun");
+    input
+}
+
+fn returning(value: Value) -> impl Task {
+    move |_| {
+        let value = value.clone();
+        async move { Ok(value) }
+    }
+}
+
+/// An executor where every function returns the data of the issue #435 fixture
+/// and create-issue records the payload it receives
+fn executor(create_issue_input: Arc<Mutex<Option<Value>>>) -> Executor {
+    let data = fixture()["data"].clone();
+    Executor::new(state_machine())
+        .with_task("fetch-issue-number", returning(json!({ "number": 435 })))
+        .with_task("fetch-quote", returning(data["Quote"].clone()))
+        .with_task("fetch-book", returning(data["Book"].clone()))
+        .with_task("fetch-sponsor", returning(data["Sponsor"].clone()))
+        .with_task("fetch-links", returning(data["Links"].clone()))
+        .with_task("create-issue", move |input: Value| {
+            *create_issue_input.lock().unwrap() = Some(input);
+            async { Ok(json!({ "dryRun": true })) }
+        })
+        .without_delays()
+}
+
+#[tokio::test]
+async fn test_create_issue_receives_a_valid_event() {
+    let create_issue_input = Arc::new(Mutex::new(None));
+
+    executor(create_issue_input.clone())
+        .execute(scheduled_event())
+        .await
+        .unwrap();
+
+    let input = create_issue_input.lock().unwrap().take().unwrap();
+    assert_eq!(input, fixture());
+
+    let event: Event = serde_json::from_value(input).unwrap();
+    assert_eq!(event.next_issue.number, 435);
+    assert!(!event.config.dry_run);
+    assert_eq!(event.config.time, "2025-08-22T17:00:00Z");
+    assert_eq!(event.data.quote.author, "Pablo Picasso");
+    assert_eq!(
+        event.data.links.len(),
+        fixture()["data"]["Links"].as_array().unwrap().len()
+    );
+}
+
+#[tokio::test]
+async fn test_execution_input_overrides_defaults() {
+    let create_issue_input = Arc::new(Mutex::new(None));
+    let mut input = scheduled_event();
+    input["dryRun"] = json!(true);
+
+    executor(create_issue_input.clone())
+        .execute(input)
+        .await
+        .unwrap();
+
+    let event: Event =
+        serde_json::from_value(create_issue_input.lock().unwrap().take().unwrap()).unwrap();
+    assert!(event.config.dry_run);
+}
+
+#[tokio::test]
+async fn test_fetchers_receive_config_and_next_issue() {
+    let fetch_book_input = Arc::new(Mutex::new(None));
+    let recorded = fetch_book_input.clone();
+
+    executor(Arc::new(Mutex::new(None)))
+        .with_task("fetch-book", move |input: Value| {
+            *recorded.lock().unwrap() = Some(input);
+            async { Ok(json!({})) }
+        })
+        .execute(scheduled_event())
+        .await
+        .unwrap();
+
+    let input = fetch_book_input.lock().unwrap().take().unwrap();
+    assert_eq!(input["NextIssue"], json!({ "number": 435 }));
+    assert_eq!(input["config"]["dryRun"], json!(false));
+    assert!(input.get("defaults").is_none());
+}
+
+#[tokio::test]
+async fn test_fetch_sponsor_retries_any_error() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let sponsor = fixture()["data"]["Sponsor"].clone();
+
+    let execution = executor(Arc::new(Mutex::new(None)))
+        .with_task("fetch-sponsor", move |_| {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            let sponsor = sponsor.clone();
+            async move {
+                match call {
+                    0 | 1 => Err(TaskError::new("Error", "Airtable is down")),
+                    _ => Ok(sponsor),
+                }
+            }
+        })
+        .execute(scheduled_event())
+        .await
+        .unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    let delays: Vec<Duration> = execution
+        .history
+        .iter()
+        .filter_map(|event| match event {
+            HistoryEvent::RetryScheduled { state, delay, .. } if state == "Fetch Sponsor" => {
+                Some(*delay)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(delays, vec![Duration::from_secs(3), Duration::from_secs(6)]);
+}
+
+#[tokio::test]
+async fn test_lambda_errors_are_retried_six_times() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let result = executor(Arc::new(Mutex::new(None)))
+        .with_task("fetch-issue-number", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async {
+                Err(TaskError::new(
+                    "Lambda.TooManyRequestsException",
+                    "throttled",
+                ))
+            }
+        })
+        .execute(scheduled_event())
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(AslError::TaskFailed { state, .. }) if state == "Fetch Issue Number"
+    ));
+    assert_eq!(calls.load(Ordering::SeqCst), 7);
+}
+
+#[tokio::test]
+async fn test_function_errors_are_not_retried() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let result = executor(Arc::new(Mutex::new(None)))
+        .with_task("fetch-quote", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(TaskError::new("FetchQuoteError", "bad quote")) }
+        })
+        .execute(scheduled_event())
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_transient_buttondown_errors_are_retried() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let execution = executor(Arc::new(Mutex::new(None)))
+        .with_task("create-issue", move |_| {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match call {
+                    0 => Err(TaskError::new("ButtonDown.RateLimited", "slow down")),
+                    _ => Ok(json!({ "emailAction": "created" })),
+                }
+            }
+        })
+        .execute(scheduled_event())
+        .await
+        .unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(execution.output["emailAction"], json!("created"));
+    assert!(execution.history.iter().any(|event| matches!(
+        event,
+        HistoryEvent::RetryScheduled { state, delay, .. }
+            if state == "Create Issue" && *delay == Duration::from_secs(30)
+    )));
+}
+
+#[tokio::test]
+async fn test_permanent_buttondown_errors_are_not_retried() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let result = executor(Arc::new(Mutex::new(None)))
+        .with_task("create-issue", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(TaskError::new("ButtonDown.Validation", "subject: required")) }
+        })
+        .execute(scheduled_event())
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -1,6 +1,163 @@
-use anyhow::{Context, Result};
-use reqwest::Client;
+use anyhow::Result;
+use reqwest::{header::HeaderMap, Client, Method, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::{fmt, time::Duration};
+use thiserror::Error;
+
+use crate::publisher::{Delivery, NewsletterPublisher, PublishedEmail};
+
+#[derive(Debug, Error)]
+pub enum ButtonDownError {
+    #[error("ButtonDown rejected the API key ({status}): {message}")]
+    Auth { status: u16, message: String },
+    #[error("ButtonDown rate limit exceeded{}", retry_after_suffix(.retry_after))]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("ButtonDown rejected the request: {}", FieldErrors(.fields))]
+    Validation { fields: Vec<FieldError> },
+    #[error("ButtonDown email not found: {0}")]
+    NotFound(String),
+    #[error("ButtonDown reported a conflict: {0}")]
+    Conflict(String),
+    #[error("ButtonDown API returned error {status}: {message}")]
+    Server { status: u16, message: String },
+    #[error("ButtonDown API returned unexpected status {status}: {message}")]
+    Unexpected { status: u16, message: String },
+    #[error("Request to ButtonDown failed: {0}")]
+    Transport(#[from] reqwest::Error),
+}
+
+/// A validation error reported by ButtonDown for a single field of the request
+/// (`None` for errors about the request as a whole)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: Option<String>,
+    pub message: String,
+}
+
+struct FieldErrors<'a>(&'a [FieldError]);
+
+impl fmt::Display for FieldErrors<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, error) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            match &error.field {
+                Some(field) => write!(f, "{}: {}", field, error.message)?,
+                None => write!(f, "{}", error.message)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn retry_after_suffix(retry_after: &Option<Duration>) -> String {
+    retry_after
+        .map(|delay| format!(", retry after {}s", delay.as_secs()))
+        .unwrap_or_default()
+}
+
+impl ButtonDownError {
+    /// Classify a non-successful response
+    fn from_response(status: StatusCode, headers: &HeaderMap, body: String) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ButtonDownError::Auth {
+                status: status.as_u16(),
+                message: body,
+            },
+            StatusCode::TOO_MANY_REQUESTS => ButtonDownError::RateLimited {
+                retry_after: parse_retry_after(headers),
+            },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                ButtonDownError::Validation {
+                    fields: parse_field_errors(&body),
+                }
+            }
+            StatusCode::NOT_FOUND => ButtonDownError::NotFound(body),
+            StatusCode::CONFLICT => ButtonDownError::Conflict(body),
+            status if status.is_server_error() => ButtonDownError::Server {
+                status: status.as_u16(),
+                message: body,
+            },
+            status => ButtonDownError::Unexpected {
+                status: status.as_u16(),
+                message: body,
+            },
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ButtonDownError::RateLimited { .. } | ButtonDownError::Server { .. } => true,
+            ButtonDownError::Transport(error) => !error.is_decode() && !error.is_builder(),
+            ButtonDownError::Auth { .. }
+            | ButtonDownError::Validation { .. }
+            | ButtonDownError::NotFound(_)
+            | ButtonDownError::Conflict(_)
+            | ButtonDownError::Unexpected { .. } => false,
+        }
+    }
+
+    /// The error name reported to Step Functions, which the `Retry` blocks of the state machine match on
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            ButtonDownError::Auth { .. } => "ButtonDown.Auth",
+            ButtonDownError::RateLimited { .. } => "ButtonDown.RateLimited",
+            ButtonDownError::Validation { .. } => "ButtonDown.Validation",
+            ButtonDownError::NotFound(_) => "ButtonDown.NotFound",
+            ButtonDownError::Conflict(_) => "ButtonDown.Conflict",
+            ButtonDownError::Server { .. } => "ButtonDown.Server",
+            ButtonDownError::Unexpected { .. } => "ButtonDown.Unexpected",
+            ButtonDownError::Transport(_) if self.is_retryable() => "ButtonDown.Transport",
+            ButtonDownError::Transport(_) => "ButtonDown.InvalidResponse",
+        }
+    }
+}
+
+/// `Retry-After` in seconds (ButtonDown doesn't send HTTP dates)
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Parse a validation error body such as `{"subject": ["This field is required."]}`
+/// or `{"detail": "Invalid publish date"}`
+fn parse_field_errors(body: &str) -> Vec<FieldError> {
+    let Ok(serde_json::Value::Object(errors)) = serde_json::from_str(body) else {
+        return vec![FieldError {
+            field: None,
+            message: body.to_string(),
+        }];
+    };
+
+    errors
+        .into_iter()
+        .flat_map(|(key, value)| {
+            let field = match key.as_str() {
+                "detail" | "non_field_errors" | "code" => None,
+                _ => Some(key),
+            };
+            let messages = match value {
+                serde_json::Value::Array(messages) => messages,
+                message => vec![message],
+            };
+            messages.into_iter().map(move |message| FieldError {
+                field: field.clone(),
+                message: match message {
+                    serde_json::Value::String(message) => message,
+                    message => message.to_string(),
+                },
+            })
+        })
+        .collect()
+}
 
 #[derive(Debug, Serialize)]
 pub struct CreateEmailRequest {
@@ -20,10 +177,64 @@ pub struct SendDraftRequest {
     pub recipients: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A partial update of an email: only the fields that are set are changed
+#[derive(Debug, Default, Serialize)]
+pub struct UpdateEmailRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// Filters of the email list: no status means every status, no page means the first one,
+/// and no ordering means the default one of ButtonDown
+#[derive(Debug, Default)]
+pub struct ListEmailsRequest {
+    pub status: Vec<String>,
+    pub page: Option<u32>,
+    /// A field to sort on, e.g. `-creation_date` for the newest emails first
+    pub ordering: Option<String>,
+}
+
+impl ListEmailsRequest {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        self.status
+            .iter()
+            .map(|status| ("status", status.clone()))
+            .chain(self.page.map(|page| ("page", page.to_string())))
+            .chain(self.ordering.clone().map(|ordering| ("ordering", ordering)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct EmailResponse {
     pub id: String,
     pub status: String,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub publish_date: Option<String>,
+    #[serde(default)]
+    pub creation_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmailListResponse {
+    pub results: Vec<EmailResponse>,
+    #[serde(default)]
+    pub count: u32,
+    pub next: Option<String>,
+    #[serde(default)]
+    pub previous: Option<String>,
 }
 
 pub struct ButtonDownClient {
@@ -55,99 +266,343 @@ impl ButtonDownClient {
             .join("-")
     }
 
-    /// Create a scheduled email with the given subject, markdown body, publish date, issue number, and first link title
-    pub async fn create_scheduled_email(
+    /// The deterministic slug of an issue: `{issue_number}-{first_link_slug}`
+    fn issue_slug(issue_number: u32, first_link_title: &str) -> String {
+        format!("{}-{}", issue_number, Self::generate_slug(first_link_title))
+    }
+
+    /// Whether an email belongs to the given issue, either because it has the expected slug
+    /// or because its slug or subject carry the issue number (e.g. the first link changed)
+    fn is_issue_email(email: &EmailResponse, issue_number: u32, expected_slug: &str) -> bool {
+        let slug = email.slug.as_deref().unwrap_or_default();
+        slug == expected_slug
+            || slug.starts_with(&format!("{}-", issue_number))
+            || email.subject.ends_with(&format!("#{}", issue_number))
+    }
+
+    /// The issue an email was created for, from its slug or its subject
+    fn issue_number_of(email: &EmailResponse) -> Option<u32> {
+        let from_slug = email
+            .slug
+            .as_deref()
+            .and_then(|slug| slug.split_once('-'))
+            .and_then(|(number, _)| number.parse().ok());
+        from_slug.or_else(|| {
+            email
+                .subject
+                .rsplit_once('#')
+                .and_then(|(_, number)| number.trim().parse().ok())
+        })
+    }
+
+    /// An authenticated request to the given path of the API
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.base_url, path))
+            .header("Authorization", format!("Token {}", self.api_key))
+    }
+
+    /// Turn a non-successful response into a classified error
+    async fn check(response: Response) -> Result<Response, ButtonDownError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        Err(ButtonDownError::from_response(status, &headers, body))
+    }
+
+    async fn send_json<T: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        request: &T,
+    ) -> Result<EmailResponse, ButtonDownError> {
+        let response = self
+            .request(method, path)
+            .header("Content-Type", "application/json")
+            .json(request)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Look for an email that was already created for the given issue, in any status.
+    /// The emails are listed from the newest one, and the listing stops at an exact slug
+    /// match or at an email of an earlier issue, so only the latest page or two are fetched
+    /// rather than the whole archive.
+    pub async fn find_issue_email(
         &self,
-        subject: String,
-        body: String,
-        publish_date: String,
         issue_number: u32,
         first_link_title: &str,
-    ) -> Result<EmailResponse> {
-        let first_link_slug = Self::generate_slug(first_link_title);
-        let slug = format!("{}-{}", issue_number, first_link_slug);
+    ) -> Result<Option<EmailResponse>, ButtonDownError> {
+        let expected_slug = Self::issue_slug(issue_number, first_link_title);
+        let mut candidates = vec![];
+        let mut page = 1;
 
-        let request = CreateEmailRequest {
-            subject,
-            body,
-            publish_date,
-            status: "scheduled".to_string(),
-            slug,
-            commenting_mode: "enabled".to_string(),
-        };
+        loop {
+            let emails = self
+                .list_emails(&ListEmailsRequest {
+                    status: vec![],
+                    page: Some(page),
+                    ordering: Some("-creation_date".to_string()),
+                })
+                .await?;
+            let reached_earlier_issue = emails.results.iter().any(|email| {
+                Self::issue_number_of(email).is_some_and(|number| number < issue_number)
+            });
+            candidates.extend(
+                emails
+                    .results
+                    .into_iter()
+                    .filter(|email| Self::is_issue_email(email, issue_number, &expected_slug)),
+            );
+            let found = candidates
+                .iter()
+                .any(|email| email.slug.as_deref() == Some(expected_slug.as_str()));
+            if found || reached_earlier_issue || emails.next.is_none() {
+                break;
+            }
+            page += 1;
+        }
+
+        // Prefer an exact slug match over a match on the issue number alone
+        let position = candidates
+            .iter()
+            .position(|email| email.slug.as_deref() == Some(expected_slug.as_str()))
+            .unwrap_or(0);
+        Ok((!candidates.is_empty()).then(|| candidates.swap_remove(position)))
+    }
 
-        self.create_email(request).await
+    /// The review copies with the given slug, among the drafts
+    async fn find_review_copies(&self, slug: &str) -> Result<Vec<EmailResponse>, ButtonDownError> {
+        let mut copies = vec![];
+        let mut page = 1;
+        loop {
+            let emails = self
+                .list_emails(&ListEmailsRequest {
+                    status: vec!["draft".to_string()],
+                    page: Some(page),
+                    ordering: Some("-creation_date".to_string()),
+                })
+                .await?;
+            copies.extend(
+                emails
+                    .results
+                    .into_iter()
+                    .filter(|email| email.slug.as_deref() == Some(slug)),
+            );
+            if emails.next.is_none() {
+                return Ok(copies);
+            }
+            page += 1;
+        }
     }
 
     /// Create an email with full control over all parameters
-    pub async fn create_email(&self, request: CreateEmailRequest) -> Result<EmailResponse> {
-        let url = format!("{}/emails", self.base_url);
+    pub async fn create_email(
+        &self,
+        request: CreateEmailRequest,
+    ) -> Result<EmailResponse, ButtonDownError> {
+        self.send_json(Method::POST, "/emails", &request).await
+    }
 
+    /// Send a draft email with full control over recipients
+    pub async fn send_draft(
+        &self,
+        email_id: &str,
+        request: SendDraftRequest,
+    ) -> Result<(), ButtonDownError> {
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Token {}", self.api_key))
+            .request(Method::POST, &format!("/emails/{}/send-draft", email_id))
             .header("Content-Type", "application/json")
             .json(&request)
             .send()
-            .await
-            .context("Failed to send request to ButtonDown API")?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow::anyhow!(
-                "ButtonDown API returned error {}: {}",
-                status,
-                error_text
-            ));
-        }
-
-        let email_response: EmailResponse = response
-            .json()
-            .await
-            .context("Failed to parse ButtonDown API response")?;
+            .await?;
 
-        Ok(email_response)
+        Self::check(response).await?;
+        Ok(())
     }
 
-    /// Send a draft email with full control over recipients
-    pub async fn send_draft(&self, email_id: &str, request: SendDraftRequest) -> Result<()> {
-        let url = format!("{}/emails/{}/send-draft", self.base_url, email_id);
+    /// Retrieve an existing email by ID
+    pub async fn get_email(&self, email_id: &str) -> Result<EmailResponse, ButtonDownError> {
+        let response = self
+            .request(Method::GET, &format!("/emails/{}", email_id))
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
 
+    /// Retrieve one page of emails, optionally filtered by status
+    pub async fn list_emails(
+        &self,
+        request: &ListEmailsRequest,
+    ) -> Result<EmailListResponse, ButtonDownError> {
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Token {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
+            .request(Method::GET, "/emails")
+            .query(&request.query())
             .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Change the subject, body, publish date or status of an existing email
+    pub async fn update_email(
+        &self,
+        email_id: &str,
+        request: &UpdateEmailRequest,
+    ) -> Result<EmailResponse, ButtonDownError> {
+        self.send_json(Method::PATCH, &format!("/emails/{}", email_id), request)
             .await
-            .context("Failed to send draft request to ButtonDown API")?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow::anyhow!(
-                "ButtonDown API returned error {}: {}",
-                status,
-                error_text
-            ));
-        }
+    }
+
+    /// Move a scheduled email back to draft, so that it doesn't go out
+    pub async fn unschedule_email(&self, email_id: &str) -> Result<EmailResponse, ButtonDownError> {
+        let request = UpdateEmailRequest {
+            status: Some("draft".to_string()),
+            ..Default::default()
+        };
+        self.update_email(email_id, &request).await
+    }
+
+    /// Delete an email
+    pub async fn delete_email(&self, email_id: &str) -> Result<(), ButtonDownError> {
+        let response = self
+            .request(Method::DELETE, &format!("/emails/{}", email_id))
+            .send()
+            .await?;
 
+        Self::check(response).await?;
         Ok(())
     }
 }
 
+/// The body of the review copy of an email: the notes, then the email
+fn review_body(notes: &[String], body: &str) -> String {
+    let mut review = String::from("> **Review notes** (only in this test draft)\n>\n");
+    for note in notes {
+        review.push_str(&format!("> - {}\n", note));
+    }
+    review.push_str("\n---\n\n");
+    review.push_str(body);
+    review
+}
+
+impl From<EmailResponse> for PublishedEmail {
+    fn from(email: EmailResponse) -> Self {
+        PublishedEmail {
+            id: email.id,
+            status: email.status,
+        }
+    }
+}
+
+impl NewsletterPublisher for ButtonDownClient {
+    async fn create_issue_email(
+        &self,
+        subject: String,
+        body: String,
+        publish_date: String,
+        issue_number: u32,
+        first_link_title: &str,
+        delivery: Delivery,
+    ) -> Result<PublishedEmail> {
+        let request = CreateEmailRequest {
+            subject,
+            body,
+            publish_date,
+            status: delivery.status().to_string(),
+            slug: Self::issue_slug(issue_number, first_link_title),
+            commenting_mode: "enabled".to_string(),
+        };
+        Ok(self.create_email(request).await?.into())
+    }
+
+    async fn find_existing_email(
+        &self,
+        issue_number: u32,
+        first_link_title: &str,
+    ) -> Result<Option<PublishedEmail>> {
+        Ok(self
+            .find_issue_email(issue_number, first_link_title)
+            .await?
+            .map(PublishedEmail::from))
+    }
+
+    async fn update_issue_email(
+        &self,
+        email_id: &str,
+        subject: String,
+        body: String,
+        publish_date: String,
+        delivery: Delivery,
+    ) -> Result<PublishedEmail> {
+        let request = UpdateEmailRequest {
+            subject: Some(subject),
+            body: Some(body),
+            publish_date: Some(publish_date),
+            status: Some(delivery.status().to_string()),
+        };
+        Ok(self.update_email(email_id, &request).await?.into())
+    }
+
+    async fn unschedule_issue_email(&self, email_id: &str) -> Result<PublishedEmail> {
+        Ok(self.unschedule_email(email_id).await?.into())
+    }
+
+    async fn send_test_draft(
+        &self,
+        email_id: &str,
+        subscriber_ids: &[String],
+        recipient_emails: &[String],
+        review_notes: &[String],
+    ) -> Result<()> {
+        let request = SendDraftRequest {
+            subscribers: Some(subscriber_ids.to_vec()),
+            recipients: Some(recipient_emails.to_vec()),
+        };
+        if review_notes.is_empty() {
+            return Ok(self.send_draft(email_id, request).await?);
+        }
+
+        // A test draft is the email as it is: the notes go in a draft copy of it, deleted
+        // once sent, or once the sending failed. A copy left by an earlier attempt whose
+        // deletion failed is removed first, so that retries don't pile them up.
+        let email = self.get_email(email_id).await?;
+        let slug = format!("review-{}", email.slug.unwrap_or(email.id));
+        for leftover in self.find_review_copies(&slug).await? {
+            self.delete_email(&leftover.id).await?;
+        }
+        let copy = self
+            .create_email(CreateEmailRequest {
+                subject: format!("{} (review)", email.subject),
+                body: review_body(review_notes, &email.body),
+                publish_date: email.publish_date.unwrap_or_default(),
+                status: "draft".to_string(),
+                slug,
+                commenting_mode: "enabled".to_string(),
+            })
+            .await?;
+        let sent = self.send_draft(&copy.id, request).await;
+        let deleted = self.delete_email(&copy.id).await;
+        sent?;
+        Ok(deleted?)
+    }
+
+    async fn fetch_status(&self, email_id: &str) -> Result<String> {
+        Ok(self.get_email(email_id).await?.status)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
 
     #[test]
     fn test_create_email_request_serialization() {
@@ -189,6 +644,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_is_issue_email() {
+        let email = |slug: Option<&str>, subject: &str| EmailResponse {
+            id: "email-1".to_string(),
+            status: "scheduled".to_string(),
+            subject: subject.to_string(),
+            body: String::new(),
+            slug: slug.map(str::to_string),
+            publish_date: None,
+            creation_date: None,
+        };
+        let expected_slug = ButtonDownClient::issue_slug(435, "An Interactive Guide to SVG Paths");
+        assert_eq!(expected_slug, "435-an-interactive-guide-to-svg-paths");
+
+        let is_issue_email =
+            |email: &EmailResponse| ButtonDownClient::is_issue_email(email, 435, &expected_slug);
+
+        assert!(is_issue_email(&email(Some(&expected_slug), "")));
+        assert!(is_issue_email(&email(Some("435-another-first-link"), "")));
+        assert!(is_issue_email(&email(
+            None,
+            "📫 Something — FullStack Bulletin #435"
+        )));
+        assert!(!is_issue_email(&email(
+            Some("434-an-interactive-guide-to-svg-paths"),
+            "📫 Something — FullStack Bulletin #434"
+        )));
+        assert!(!is_issue_email(&email(None, "FullStack Bulletin #4350")));
+
+        let issue_number_of = |slug: Option<&str>, subject: &str| {
+            ButtonDownClient::issue_number_of(&email(slug, subject))
+        };
+        assert_eq!(issue_number_of(Some("434-something"), ""), Some(434));
+        assert_eq!(
+            issue_number_of(None, "Something — FullStack Bulletin #433"),
+            Some(433)
+        );
+        assert_eq!(issue_number_of(Some("welcome-email"), "Welcome!"), None);
+    }
+
     #[test]
     fn test_send_draft_request_serialization() {
         // Test empty request (send to all subscribers)
@@ -223,6 +718,570 @@ mod tests {
         assert!(!json.contains("recipients"));
     }
 
-    // Note: Integration tests with actual API calls would require a test API key
-    // and should be run separately from unit tests
+    #[test]
+    fn test_update_email_request_serialization() {
+        let request = UpdateEmailRequest {
+            subject: Some("Fixed subject".to_string()),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"subject":"Fixed subject"}"#);
+    }
+
+    fn client(server: &MockServer) -> ButtonDownClient {
+        ButtonDownClient::new("test-key".to_string(), Client::new(), server.url("/v1"))
+    }
+
+    fn email_json(id: &str, status: &str, slug: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "status": status,
+            "subject": "📫 An Interactive Guide to SVG Paths — FullStack Bulletin #435",
+            "body": "Hello",
+            "slug": slug,
+            "publish_date": "2025-08-25T17:00:00Z",
+            "creation_date": "2025-08-22T17:00:00Z"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_email() {
+        let server = MockServer::start();
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/v1/emails/email-1")
+                    .header("Authorization", "Token test-key");
+                then.status(200)
+                    .json_body(email_json("email-1", "scheduled", "435-svg"));
+            })
+            .await;
+
+        let email = client(&server).get_email("email-1").await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(email.status, "scheduled");
+        assert_eq!(email.slug.as_deref(), Some("435-svg"));
+        assert_eq!(email.publish_date.as_deref(), Some("2025-08-25T17:00:00Z"));
+    }
+
+    #[tokio::test]
+    async fn test_get_missing_email() {
+        let server = MockServer::start();
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails/missing");
+                then.status(404).body(r#"{"detail": "Not found."}"#);
+            })
+            .await;
+
+        let error = client(&server).get_email("missing").await.unwrap_err();
+        assert!(matches!(error, ButtonDownError::NotFound(_)));
+        assert!(!error.is_retryable());
+    }
+
+    async fn error_for(status: u16, headers: &[(&str, &str)], body: &str) -> ButtonDownError {
+        let server = MockServer::start();
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/emails");
+                let then = headers
+                    .iter()
+                    .fold(then.status(status), |then, (name, value)| {
+                        then.header(*name, *value)
+                    });
+                then.body(body);
+            })
+            .await;
+
+        client(&server)
+            .create_issue_email(
+                "Subject".to_string(),
+                "Body".to_string(),
+                "2025-08-25T17:00:00Z".to_string(),
+                435,
+                "First link",
+                Delivery::Scheduled,
+            )
+            .await
+            .unwrap_err()
+            .downcast()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_auth_error() {
+        let error = error_for(401, &[], r#"{"detail": "Invalid token."}"#).await;
+
+        assert!(matches!(error, ButtonDownError::Auth { status: 401, .. }));
+        assert!(!error.is_retryable());
+        assert_eq!(error.error_type(), "ButtonDown.Auth");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_error() {
+        let error = error_for(429, &[("Retry-After", "30")], "").await;
+
+        assert!(matches!(
+            error,
+            ButtonDownError::RateLimited { retry_after: Some(delay) } if delay == Duration::from_secs(30)
+        ));
+        assert!(error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "ButtonDown rate limit exceeded, retry after 30s"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validation_error() {
+        let error = error_for(
+            400,
+            &[],
+            r#"{"subject": ["This field is required."], "non_field_errors": ["Invalid email."]}"#,
+        )
+        .await;
+
+        match &error {
+            ButtonDownError::Validation { fields } => assert_eq!(
+                fields,
+                &vec![
+                    FieldError {
+                        field: None,
+                        message: "Invalid email.".to_string()
+                    },
+                    FieldError {
+                        field: Some("subject".to_string()),
+                        message: "This field is required.".to_string()
+                    },
+                ]
+            ),
+            error => panic!("Unexpected error: {:?}", error),
+        }
+        assert!(!error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "ButtonDown rejected the request: Invalid email.; subject: This field is required."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflict_and_server_errors() {
+        let conflict = error_for(409, &[], "Slug already in use").await;
+        assert!(
+            matches!(conflict, ButtonDownError::Conflict(ref body) if body == "Slug already in use")
+        );
+        assert!(!conflict.is_retryable());
+
+        let server = error_for(503, &[], "Service unavailable").await;
+        assert!(matches!(
+            server,
+            ButtonDownError::Server { status: 503, .. }
+        ));
+        assert!(server.is_retryable());
+        assert_eq!(server.error_type(), "ButtonDown.Server");
+    }
+
+    #[tokio::test]
+    async fn test_transport_errors() {
+        let unreachable = ButtonDownClient::new(
+            "test-key".to_string(),
+            Client::new(),
+            "http://127.0.0.1:1".to_string(),
+        );
+        let error = unreachable.get_email("email-1").await.unwrap_err();
+        assert!(matches!(error, ButtonDownError::Transport(_)));
+        assert!(error.is_retryable());
+
+        let server = MockServer::start();
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails/email-1");
+                then.status(200).body("not json");
+            })
+            .await;
+        let error = client(&server).get_email("email-1").await.unwrap_err();
+        assert!(!error.is_retryable());
+        assert_eq!(error.error_type(), "ButtonDown.InvalidResponse");
+    }
+
+    #[tokio::test]
+    async fn test_list_emails_filters() {
+        let server = MockServer::start();
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/v1/emails")
+                    .query_param("status", "scheduled")
+                    .query_param("page", "2");
+                then.status(200).json_body(json!({
+                    "results": [email_json("email-1", "scheduled", "435-svg")],
+                    "count": 11,
+                    "next": null,
+                    "previous": "https://api.buttondown.com/v1/emails?page=1"
+                }));
+            })
+            .await;
+
+        let emails = client(&server)
+            .list_emails(&ListEmailsRequest {
+                status: vec!["scheduled".to_string()],
+                page: Some(2),
+                ordering: None,
+            })
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(emails.count, 11);
+        assert_eq!(emails.results.len(), 1);
+        assert!(emails.next.is_none());
+        assert!(emails.previous.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_find_issue_email_across_pages() {
+        let server = MockServer::start();
+        let first_page = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/v1/emails")
+                    .query_param("page", "1")
+                    .query_param("ordering", "-creation_date");
+                then.status(200).json_body(json!({
+                    "results": [
+                        email_json("email-1", "scheduled", "435-another-title"),
+                        email_json("email-2", "draft", "welcome-email")
+                    ],
+                    "count": 3,
+                    "next": "https://api.buttondown.com/v1/emails?page=2",
+                    "previous": null
+                }));
+            })
+            .await;
+        let second_page = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails").query_param("page", "2");
+                then.status(200).json_body(json!({
+                    "results": [email_json("email-3", "scheduled", "435-an-interactive-guide-to-svg-paths")],
+                    "count": 3,
+                    "next": null,
+                    "previous": "https://api.buttondown.com/v1/emails?page=1"
+                }));
+            })
+            .await;
+
+        let email = client(&server)
+            .find_issue_email(435, "An Interactive Guide to SVG Paths")
+            .await
+            .unwrap()
+            .unwrap();
+
+        first_page.assert_async().await;
+        second_page.assert_async().await;
+        assert_eq!(email.id, "email-3");
+    }
+
+    #[tokio::test]
+    async fn test_find_issue_email_stops_paging() {
+        let server = MockServer::start();
+        let exact_match = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails").query_param("page", "1");
+                then.status(200).json_body(json!({
+                    "results": [email_json("email-1", "scheduled", "435-an-interactive-guide-to-svg-paths")],
+                    "count": 200,
+                    "next": "https://api.buttondown.com/v1/emails?page=2",
+                    "previous": null
+                }));
+            })
+            .await;
+        let archive = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails").query_param("page", "2");
+                then.status(500);
+            })
+            .await;
+
+        let email = client(&server)
+            .find_issue_email(435, "An Interactive Guide to SVG Paths")
+            .await
+            .unwrap()
+            .unwrap();
+
+        exact_match.assert_async().await;
+        archive.assert_hits_async(0).await;
+        assert_eq!(email.id, "email-1");
+
+        // Once the emails of the earlier issues are reached, the issue has no email yet
+        let server = MockServer::start();
+        let latest = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails").query_param("page", "1");
+                then.status(200).json_body(json!({
+                    "results": [{
+                        "id": "email-9",
+                        "status": "sent",
+                        "subject": "📫 Something — FullStack Bulletin #434",
+                        "slug": "434-something"
+                    }],
+                    "count": 200,
+                    "next": "https://api.buttondown.com/v1/emails?page=2",
+                    "previous": null
+                }));
+            })
+            .await;
+        let archive = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails").query_param("page", "2");
+                then.status(500);
+            })
+            .await;
+
+        let email = client(&server)
+            .find_issue_email(435, "An Interactive Guide to SVG Paths")
+            .await
+            .unwrap();
+
+        latest.assert_async().await;
+        archive.assert_hits_async(0).await;
+        assert!(email.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_email() {
+        let server = MockServer::start();
+        let mock = server
+            .mock_async(|when, then| {
+                when.method("PATCH")
+                    .path("/v1/emails/email-1")
+                    .json_body(json!({
+                        "subject": "New subject",
+                        "publish_date": "2025-09-01T17:00:00Z"
+                    }));
+                then.status(200)
+                    .json_body(email_json("email-1", "scheduled", "435-svg"));
+            })
+            .await;
+
+        let email = client(&server)
+            .update_email(
+                "email-1",
+                &UpdateEmailRequest {
+                    subject: Some("New subject".to_string()),
+                    publish_date: Some("2025-09-01T17:00:00Z".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(email.id, "email-1");
+    }
+
+    #[tokio::test]
+    async fn test_unschedule_email() {
+        let server = MockServer::start();
+        let mock = server
+            .mock_async(|when, then| {
+                when.method("PATCH")
+                    .path("/v1/emails/email-1")
+                    .json_body(json!({ "status": "draft" }));
+                then.status(200)
+                    .json_body(email_json("email-1", "draft", "435-svg"));
+            })
+            .await;
+
+        let email = client(&server).unschedule_email("email-1").await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(email.status, "draft");
+    }
+
+    #[tokio::test]
+    async fn test_create_issue_email_as_draft() {
+        let server = MockServer::start();
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/v1/emails")
+                    .json_body_partial(r#"{ "status": "draft", "slug": "435-first-link" }"#);
+                then.status(201)
+                    .json_body(email_json("email-1", "draft", "435-first-link"));
+            })
+            .await;
+
+        let email = client(&server)
+            .create_issue_email(
+                "Subject".to_string(),
+                "Body".to_string(),
+                "2025-08-25T17:00:00Z".to_string(),
+                435,
+                "First link",
+                Delivery::Draft,
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(email.status, "draft");
+    }
+
+    #[tokio::test]
+    async fn test_delete_email() {
+        let server = MockServer::start();
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(DELETE)
+                    .path("/v1/emails/email-1")
+                    .header("Authorization", "Token test-key");
+                then.status(204);
+            })
+            .await;
+
+        client(&server).delete_email("email-1").await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_test_draft_with_review_notes() {
+        let server = MockServer::start();
+        let get = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails/email-1");
+                then.status(200)
+                    .json_body(email_json("email-1", "scheduled", "435-svg"));
+            })
+            .await;
+        let drafts = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/v1/emails")
+                    .query_param("status", "draft");
+                then.status(200).json_body(json!({
+                    "results": [email_json("email-9", "draft", "435-other-draft")],
+                    "next": null
+                }));
+            })
+            .await;
+        let create = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/emails").json_body(json!({
+                    "subject": "📫 An Interactive Guide to SVG Paths — FullStack Bulletin #435 (review)",
+                    "body": "> **Review notes** (only in this test draft)\n>\n> - Warning: No description (link-text)\n\n---\n\nHello",
+                    "publish_date": "2025-08-25T17:00:00Z",
+                    "status": "draft",
+                    "slug": "review-435-svg",
+                    "commenting_mode": "enabled"
+                }));
+                then.status(201)
+                    .json_body(email_json("email-2", "draft", "review-435-svg"));
+            })
+            .await;
+        let send = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/v1/emails/email-2/send-draft")
+                    .json_body(json!({
+                        "subscribers": ["subscriber-1"],
+                        "recipients": ["editor@example.com"]
+                    }));
+                then.status(200);
+            })
+            .await;
+        let delete = server
+            .mock_async(|when, then| {
+                when.method(DELETE).path("/v1/emails/email-2");
+                then.status(204);
+            })
+            .await;
+
+        client(&server)
+            .send_test_draft(
+                "email-1",
+                &["subscriber-1".to_string()],
+                &["editor@example.com".to_string()],
+                &["Warning: No description (link-text)".to_string()],
+            )
+            .await
+            .unwrap();
+
+        get.assert_async().await;
+        drafts.assert_async().await;
+        create.assert_async().await;
+        send.assert_async().await;
+        delete.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_review_copies_are_always_deleted() {
+        let server = MockServer::start();
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/emails/email-1");
+                then.status(200)
+                    .json_body(email_json("email-1", "scheduled", "435-svg"));
+            })
+            .await;
+        // The copy of an earlier attempt whose deletion failed
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/v1/emails")
+                    .query_param("status", "draft");
+                then.status(200).json_body(json!({
+                    "results": [email_json("email-2", "draft", "review-435-svg")],
+                    "next": null
+                }));
+            })
+            .await;
+        let delete_leftover = server
+            .mock_async(|when, then| {
+                when.method(DELETE).path("/v1/emails/email-2");
+                then.status(204);
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/emails");
+                then.status(201)
+                    .json_body(email_json("email-3", "draft", "review-435-svg"));
+            })
+            .await;
+        let send = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/emails/email-3/send-draft");
+                then.status(503);
+            })
+            .await;
+        let delete_copy = server
+            .mock_async(|when, then| {
+                when.method(DELETE).path("/v1/emails/email-3");
+                then.status(204);
+            })
+            .await;
+
+        let error = client(&server)
+            .send_test_draft(
+                "email-1",
+                &["subscriber-1".to_string()],
+                &[],
+                &["Warning: No description (link-text)".to_string()],
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error
+                .downcast_ref::<ButtonDownError>()
+                .unwrap()
+                .error_type(),
+            "ButtonDown.Server"
+        );
+        delete_leftover.assert_async().await;
+        send.assert_async().await;
+        delete_copy.assert_async().await;
+    }
 }
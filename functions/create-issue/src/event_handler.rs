@@ -1,16 +1,27 @@
-use lambda_runtime::{tracing, Error, LambdaEvent};
+use lambda_runtime::{tracing, Diagnostic, LambdaEvent};
 use serde_json::{json, Value};
 
-use crate::buttondown::{self, ButtonDownClient};
-use crate::datetime_utils::get_next_monday_from;
-use crate::model::{Event, Link};
-use crate::template::{generate_extra_content_title, TemplateRenderer};
+use crate::buttondown::ButtonDownError;
+use crate::layout::{lay_out, skip_repeated_links};
+use crate::link_health::{referenced_urls, LinkChecker};
+use crate::model::Event;
+use crate::overrides::{apply_overrides, utm_campaign, OverridesReport};
+use crate::publisher::{publish_issue_email, Delivery, NewsletterPublisher, PublishAction};
+use crate::sponsor_html::{sanitize_sponsor, track_sponsor};
+use crate::template::{generate_extra_content_title, NewsletterData, TemplateRenderer};
+use crate::validation::{validate, IssueContent};
+use shared::datetime_utils::get_next_monday_from;
+use shared::history::{IssueHistory, IssueRecord};
 
-pub(crate) struct HandlerConfig {
-    pub(crate) buttondown_client: ButtonDownClient,
-    pub(crate) template_renderer: TemplateRenderer,
-    pub(crate) draft_subscriber_id: String,
-    pub(crate) draft_recipient_email: String,
+pub struct HandlerConfig<P: NewsletterPublisher> {
+    pub publisher: P,
+    pub template_renderer: TemplateRenderer,
+    pub draft_subscriber_id: String,
+    pub draft_recipient_email: String,
+    /// Checks the URLs of every issue before it's validated, when set
+    pub link_checker: Option<LinkChecker>,
+    /// Records every published issue, and tells which links were in the previous ones
+    pub issue_history: Option<Box<dyn IssueHistory + Send + Sync>>,
 }
 
 static EMOJIS: [&str; 32] = [
@@ -18,11 +29,24 @@ static EMOJIS: [&str; 32] = [
     "🟠", "🍭", "📨", "📫", "▶️", "🌀", "🍀", "🔵", "⚪️", "💬", "😎", "❇️", "🔸", "✉️", "📭", "🟤",
 ];
 
+/// Report a publishing failure with an error type that the `Retry` blocks of the
+/// state machine can match on (e.g. `ButtonDown.RateLimited`)
+fn publish_error(context: &str, error: anyhow::Error) -> Diagnostic {
+    let error_type = error
+        .downcast_ref::<ButtonDownError>()
+        .map(ButtonDownError::error_type)
+        .unwrap_or("PublishError");
+    Diagnostic {
+        error_type: error_type.to_string(),
+        error_message: format!("{}: {}", context, error),
+    }
+}
+
 /// Main Lambda function handler for creating newsletter issues
-pub(crate) async fn function_handler(
+pub async fn function_handler<P: NewsletterPublisher>(
     event: LambdaEvent<Event>,
-    config: &HandlerConfig,
-) -> Result<Value, Error> {
+    config: &HandlerConfig<P>,
+) -> Result<Value, Diagnostic> {
     tracing::info!("Starting create-issue-v2 lambda");
 
     tracing::info!("Processing issue #{}", event.payload.next_issue.number);
@@ -43,36 +67,79 @@ pub(crate) async fn function_handler(
     let quote = &event.payload.data.quote;
     let book = &event.payload.data.book;
     let links = &event.payload.data.links;
-    let sponsor = &event.payload.data.sponsor;
+    // The sponsor blocks are pasted by the customers: only their email-safe HTML is kept,
+    // and their links get the tracking parameters of the issue
+    let (sponsor, sponsor_html) = sanitize_sponsor(&event.payload.data.sponsor);
+    let (sponsor, sponsor_links) = track_sponsor(&sponsor, &campaign_name);
+    let sponsor = &sponsor;
 
     tracing::info!("Loaded quote: {}", quote.text);
     tracing::info!("Loaded book: {}", book.title);
     tracing::info!("Retrieved {} links", links.len());
     tracing::info!("Retrieved sponsor: {}", sponsor.customer);
+    for warning in &sponsor_html {
+        tracing::warn!("Cleaned the sponsor HTML: {}", warning);
+    }
 
-    // Step 3: Prepare links (primary vs secondary vs extra)
-    let primary_link = links.first().ok_or("No primary link available")?;
-    let secondary_links: Vec<&Link> = links.iter().skip(1).take(6).collect();
-    let extra_links: Vec<&Link> = links.iter().skip(7).collect();
+    // Step 3: Leave out the links of the recent issues, apply the editorial overrides, then
+    // lay out the links (primary vs secondary vs extra)
+    let link_layout = event.payload.config.link_layout.clone().unwrap_or_default();
+    let (links, repeated_links) = match (link_layout.avoid_recent_issues, &config.issue_history) {
+        (Some(issues), Some(history)) => {
+            let recent = history
+                .recent(event.payload.next_issue.number, issues)
+                .map_err(|e| format!("Failed to read the issue history: {}", e))?;
+            skip_repeated_links(links.clone(), &recent)
+        }
+        (Some(_), None) => {
+            tracing::warn!("No issue history, the links of the recent issues can't be avoided");
+            (links.clone(), Vec::new())
+        }
+        (None, _) => (links.clone(), Vec::new()),
+    };
+    for url in &repeated_links {
+        tracing::info!("Left out a link of a recent issue: {}", url);
+    }
+    let (links, overrides) = match &event.payload.config.overrides {
+        Some(overrides) => {
+            let campaign = utm_campaign(&event.payload.config.time)
+                .map_err(|e| format!("Failed to parse reference time: {}", e))?;
+            apply_overrides(links, overrides, &campaign)
+        }
+        None => (links, OverridesReport::default()),
+    };
+    for outcome in &overrides.applied {
+        tracing::info!("Applied override: {:?} {}", outcome.action, outcome.url);
+    }
+    for outcome in &overrides.ignored {
+        tracing::warn!("Ignored override: {:?} {}", outcome.action, outcome.url);
+    }
+
+    let sections = lay_out(&links, &link_layout).ok_or("No primary link available")?;
+    let primary_link = sections.primary;
 
     tracing::info!("Primary link: {}", primary_link.title);
-    tracing::info!("Secondary links: {}", secondary_links.len());
-    tracing::info!("Extra links: {}", extra_links.len());
+    tracing::info!("Secondary links: {}", sections.secondary.len());
+    tracing::info!("Extra links: {}", sections.extra.len());
+    if !sections.dropped.is_empty() {
+        tracing::info!("Links left out by the layout: {}", sections.dropped.len());
+    }
 
     // Step 4: Render the newsletter template
-    let rendered_content = config
+    let newsletter_data = NewsletterData {
+        issue_number: event.payload.next_issue.number,
+        quote,
+        book,
+        links: &sections,
+        extra_content_title: &extra_content_title,
+        sponsor: Some(sponsor),
+        intro: event.payload.config.intro.as_deref(),
+    };
+    let rendered = config
         .template_renderer
-        .render_newsletter(
-            event.payload.next_issue.number,
-            quote,
-            book,
-            primary_link,
-            &secondary_links,
-            &extra_links,
-            &extra_content_title,
-            Some(sponsor),
-        )
+        .render_newsletter(event.payload.config.template.as_deref(), &newsletter_data)
         .map_err(|e| format!("Failed to render newsletter template: {}", e))?;
+    let rendered_content = &rendered.markdown;
 
     tracing::info!("Newsletter template rendered successfully");
 
@@ -82,14 +149,40 @@ pub(crate) async fn function_handler(
 
     let subject_line = format!(
         "{} {} — FullStack Bulletin #{}",
-        selected_emoji,
-        links
-            .first()
-            .map(|l| &l.title)
-            .unwrap_or(&"Weekly Newsletter".to_string()),
-        event.payload.next_issue.number
+        selected_emoji, primary_link.title, event.payload.next_issue.number
     );
 
+    let link_health = match &config.link_checker {
+        Some(checker) => {
+            let urls = referenced_urls(quote, book, &links);
+            tracing::info!("Checking {} URLs", urls.len());
+            checker.check(&urls).await
+        }
+        None => Vec::new(),
+    };
+
+    // Validate the issue: errors (e.g. a leftover intro placeholder, a broken image URL)
+    // keep the email as a draft, for the editor to fix
+    let validation = validate(&IssueContent {
+        subject: &subject_line,
+        data: &newsletter_data,
+        markdown: rendered_content,
+        link_health: &link_health,
+        sponsor_html: &sponsor_html,
+    });
+    for finding in &validation.errors {
+        tracing::warn!("Validation error: {}", finding);
+    }
+    for finding in &validation.warnings {
+        tracing::warn!("Validation warning: {}", finding);
+    }
+    let delivery = if validation.has_errors() {
+        tracing::warn!("The issue has validation errors, the email will only be a draft");
+        Delivery::Draft
+    } else {
+        Delivery::Scheduled
+    };
+
     // Step 5: Handle dry run mode
     if event.payload.config.dry_run {
         tracing::info!("Dry run mode enabled - no campaign will be created");
@@ -99,58 +192,111 @@ pub(crate) async fn function_handler(
             "links": links,
             "sponsor": sponsor,
             "subjectLine": subject_line,
+            "overrides": overrides,
+            "repeatedLinks": repeated_links,
+            "sponsorHtml": sponsor_html,
+            "sponsorLinks": sponsor_links,
             "renderedContent": rendered_content,
+            "renderedHtml": rendered.html,
+            "renderedText": rendered.text,
+            "validation": validation,
+            "linkHealth": link_health,
+            "delivery": delivery,
             "dryRun": true
         }));
     }
 
-    // Step 6: Create ButtonDown campaign
-    tracing::info!("Creating ButtonDown campaign");
+    // Step 6: Create the campaign
+    tracing::info!("Creating campaign");
     tracing::info!(
         "Content rendered as markdown: {} characters",
         rendered_content.len()
     );
     tracing::info!("Will schedule campaign for: {}", schedule_for.to_rfc3339());
-    tracing::info!("Creating ButtonDown email with subject: {}", subject_line);
+    tracing::info!("Creating email with subject: {}", subject_line);
 
-    // Create scheduled email
+    // Create the email, scheduled unless it has to stay a draft
     tracing::info!(
-        "Creating scheduled email for: {}",
+        "Creating {} email for: {}",
+        delivery.status(),
         schedule_for.to_rfc3339()
     );
-    let email_response = config
-        .buttondown_client
-        .create_scheduled_email(
-            subject_line.clone(),
-            rendered_content.to_string(),
-            schedule_for.to_rfc3339(),
-            event.payload.next_issue.number,
-            &primary_link.title,
-        )
-        .await
-        .map_err(|e| format!("Failed to create scheduled email: {}", e))?;
+    let on_existing_email = event.payload.config.on_existing_email.unwrap_or_default();
+    let outcome = publish_issue_email(
+        &config.publisher,
+        subject_line.clone(),
+        rendered_content.to_string(),
+        schedule_for.to_rfc3339(),
+        event.payload.next_issue.number,
+        &primary_link.title,
+        delivery,
+        on_existing_email,
+    )
+    .await
+    .map_err(|e| publish_error("Failed to create scheduled email", e))?;
+    let email_response = outcome.email;
 
     let campaign_id = email_response.id.clone();
 
-    tracing::info!("ButtonDown email created with ID: {}", email_response.id);
+    tracing::info!("Email {:?} with ID: {}", outcome.action, email_response.id);
     tracing::info!("Email status: {}", email_response.status);
 
-    // Send test emails if configured
-    config
-        .buttondown_client
-        .send_draft(
-            &email_response.id,
-            buttondown::SendDraftRequest {
-                subscribers: Some(vec![config.draft_subscriber_id.clone()]),
-                recipients: Some(vec![config.draft_recipient_email.clone()]),
-            },
-        )
+    // An existing email that was left untouched still gets the test draft and the history
+    // record while it's waiting to go out: it's the email of a previous run that failed after
+    // creating it (e.g. on a retry of a ButtonDown error), which never got them
+    let pending = matches!(email_response.status.as_str(), "draft" | "scheduled");
+    let follow_up = outcome.action != PublishAction::Skipped || pending;
+
+    // Send test emails
+    if follow_up {
+        config
+            .publisher
+            .send_test_draft(
+                &email_response.id,
+                std::slice::from_ref(&config.draft_subscriber_id),
+                std::slice::from_ref(&config.draft_recipient_email),
+                &validation.review_notes(),
+            )
+            .await
+            .map_err(|e| publish_error("Failed to send draft email", e))?;
+    }
+
+    // Remember what went out. The email is there already, so a failure here doesn't fail
+    // the execution.
+    if follow_up {
+        if let Some(history) = &config.issue_history {
+            let record = IssueRecord {
+                number: event.payload.next_issue.number,
+                date: schedule_for.format("%Y-%m-%d").to_string(),
+                subject: subject_line.clone(),
+                quote_id: quote.id,
+                book_id: book.id.clone(),
+                sponsor: Some(sponsor.customer.clone())
+                    .filter(|customer| !customer.trim().is_empty()),
+                links: std::iter::once(sections.primary)
+                    .chain(sections.secondary.iter().copied())
+                    .chain(sections.extra.iter().copied())
+                    .map(|link| link.url.clone())
+                    .collect(),
+                sponsor_links: sponsor_links.clone(),
+            };
+            if let Err(e) = history.record(record) {
+                tracing::error!("Failed to record the issue in the history: {}", e);
+            }
+        }
+    }
+
+    // Confirm the status of the email after sending the test draft
+    let email_status = config
+        .publisher
+        .fetch_status(&email_response.id)
         .await
-        .map_err(|e| format!("Failed to send draft email: {}", e))?;
+        .map_err(|e| publish_error("Failed to fetch email status", e))?;
 
     tracing::info!(
-        "ButtonDown campaign created successfully with ID: {}",
-        campaign_id
+        "Campaign created successfully with ID: {} ({})",
+        campaign_id,
+        email_status
     );
 
     // Step 7: Return success response
@@ -160,14 +306,517 @@ pub(crate) async fn function_handler(
         "links": links,
         "sponsor": sponsor,
         "subjectLine": subject_line,
+        "overrides": overrides,
+        "repeatedLinks": repeated_links,
+        "sponsorHtml": sponsor_html,
+        "sponsorLinks": sponsor_links,
         "campaignId": campaign_id,
         "renderedContent": rendered_content,
-        "emailId": email_response.id
+        "renderedHtml": rendered.html,
+        "renderedText": rendered.text,
+        "emailId": email_response.id,
+        "emailStatus": email_status,
+        "emailAction": outcome.action,
+        "validation": validation,
+        "linkHealth": link_health,
+        "delivery": delivery
     }))
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO: Add proper integration test with sample event data
-    // This would require creating a valid Event structure with all required fields
+    use super::*;
+    use crate::model::{EditorialOverrides, ExistingEmailMode, LinkLayout};
+    use crate::publisher::{PublisherCall, RecordingPublisher};
+    use crate::template::INTRO_PLACEHOLDER;
+    use lambda_runtime::Context;
+    use shared::history::MemoryIssueHistory;
+    use std::sync::Arc;
+
+    const ISSUE_435: &str = include_str!("../events/issue-435.json");
+
+    fn handler_config() -> HandlerConfig<RecordingPublisher> {
+        handler_config_with(RecordingPublisher::new())
+    }
+
+    fn handler_config_with(publisher: RecordingPublisher) -> HandlerConfig<RecordingPublisher> {
+        HandlerConfig {
+            publisher,
+            template_renderer: TemplateRenderer::new().unwrap(),
+            draft_subscriber_id: "subscriber-1".to_string(),
+            draft_recipient_email: "editor@example.com".to_string(),
+            link_checker: None,
+            issue_history: None,
+        }
+    }
+
+    fn event(dry_run: bool) -> LambdaEvent<Event> {
+        let mut event: Event = serde_json::from_str(ISSUE_435).unwrap();
+        event.config.dry_run = dry_run;
+        LambdaEvent::new(event, Context::default())
+    }
+
+    #[tokio::test]
+    async fn test_creates_schedules_and_sends_test_draft() {
+        let config = handler_config();
+
+        let response = function_handler(event(false), &config).await.unwrap();
+
+        let calls = config.publisher.calls();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(
+            calls[0],
+            PublisherCall::FindExistingEmail {
+                issue_number: 435,
+                first_link_title: "Glass3D generator".to_string()
+            }
+        );
+        match &calls[1] {
+            PublisherCall::CreateIssueEmail {
+                subject,
+                body,
+                publish_date,
+                issue_number,
+                first_link_title,
+                delivery,
+            } => {
+                assert_eq!(*issue_number, 435);
+                assert_eq!(first_link_title, "Glass3D generator");
+                assert!(subject.ends_with("— FullStack Bulletin #435"));
+                assert_eq!(publish_date, "2025-08-25T17:00:00+00:00");
+                assert!(body.contains("Pablo Picasso"));
+                assert!(!body.contains(INTRO_PLACEHOLDER));
+                assert_eq!(*delivery, Delivery::Scheduled);
+            }
+            call => panic!("Unexpected second call: {:?}", call),
+        }
+        assert_eq!(
+            calls[2],
+            PublisherCall::SendTestDraft {
+                email_id: "email-1".to_string(),
+                subscriber_ids: vec!["subscriber-1".to_string()],
+                recipient_emails: vec!["editor@example.com".to_string()],
+                review_notes: Vec::new(),
+            }
+        );
+        assert_eq!(
+            calls[3],
+            PublisherCall::FetchStatus {
+                email_id: "email-1".to_string()
+            }
+        );
+
+        assert_eq!(response["emailId"], json!("email-1"));
+        assert_eq!(response["campaignId"], json!("email-1"));
+        assert_eq!(response["emailStatus"], json!("scheduled"));
+        assert_eq!(response["emailAction"], json!("created"));
+        assert_eq!(response["delivery"], json!("scheduled"));
+        assert_eq!(
+            response["validation"],
+            json!({ "errors": [], "warnings": [] })
+        );
+        assert!(response["renderedHtml"]
+            .as_str()
+            .unwrap()
+            .contains("<strong>Glass3D generator</strong>"));
+        assert!(response["renderedText"]
+            .as_str()
+            .unwrap()
+            .contains("Glass3D generator [3]"));
+        assert!(response.get("dryRun").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rerun_skips_existing_email_by_default() {
+        let config = handler_config_with(RecordingPublisher::with_existing_email(435, "sent"));
+
+        let response = function_handler(event(false), &config).await.unwrap();
+
+        assert_eq!(response["emailAction"], json!("skipped"));
+        assert_eq!(response["emailId"], json!("email-1"));
+        assert!(!config
+            .publisher
+            .calls()
+            .iter()
+            .any(|call| matches!(call, PublisherCall::SendTestDraft { .. })));
+        assert_eq!(config.publisher.emails().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rerun_with_validation_errors_unschedules_existing_email() {
+        let config = handler_config_with(RecordingPublisher::with_existing_email(435, "scheduled"));
+        let mut event = event(false);
+        event.payload.data.links[0].image = None;
+
+        let response = function_handler(event, &config).await.unwrap();
+
+        assert_eq!(response["delivery"], json!("draft"));
+        assert_eq!(response["emailAction"], json!("skipped"));
+        assert_eq!(response["emailStatus"], json!("draft"));
+        assert_eq!(config.publisher.emails()[0].status, "draft");
+        let calls = config.publisher.calls();
+        assert_eq!(
+            calls[1],
+            PublisherCall::UnscheduleIssueEmail {
+                email_id: "email-1".to_string()
+            }
+        );
+        assert!(matches!(
+            &calls[2],
+            PublisherCall::SendTestDraft { review_notes, .. }
+                if review_notes[0].starts_with("Error: The primary link")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_a_failed_test_draft_sends_it() {
+        let history = Arc::new(MemoryIssueHistory::new());
+        let publisher = RecordingPublisher::new();
+        publisher.fail_test_drafts(1);
+        let mut config = handler_config_with(publisher);
+        config.issue_history = Some(Box::new(history.clone()));
+
+        let error = function_handler(event(false), &config).await.unwrap_err();
+        assert_eq!(error.error_type, "ButtonDown.Server");
+        assert!(history.issue(435).unwrap().is_none());
+
+        // The retry of the state machine finds the scheduled email of the first attempt
+        let response = function_handler(event(false), &config).await.unwrap();
+
+        assert_eq!(response["emailAction"], json!("skipped"));
+        assert_eq!(response["emailStatus"], json!("scheduled"));
+        assert_eq!(config.publisher.emails().len(), 1);
+        let test_drafts: Vec<_> = config
+            .publisher
+            .calls()
+            .into_iter()
+            .filter(|call| matches!(call, PublisherCall::SendTestDraft { .. }))
+            .collect();
+        assert_eq!(test_drafts.len(), 2);
+        assert!(history.issue(435).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rerun_updates_existing_email() {
+        let config = handler_config_with(RecordingPublisher::with_existing_email(435, "scheduled"));
+        let mut event = event(false);
+        event.payload.config.on_existing_email = Some(ExistingEmailMode::Update);
+
+        let response = function_handler(event, &config).await.unwrap();
+
+        assert_eq!(response["emailAction"], json!("updated"));
+        let calls = config.publisher.calls();
+        assert!(matches!(
+            &calls[1],
+            PublisherCall::UpdateIssueEmail { email_id, .. } if email_id == "email-1"
+        ));
+        assert!(matches!(calls[2], PublisherCall::SendTestDraft { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_rerun_fails_on_existing_email() {
+        let config = handler_config_with(RecordingPublisher::with_existing_email(435, "scheduled"));
+        let mut event = event(false);
+        event.payload.config.on_existing_email = Some(ExistingEmailMode::Fail);
+
+        assert!(function_handler(event, &config).await.is_err());
+        assert_eq!(config.publisher.calls().len(), 1);
+    }
+
+    #[test]
+    fn test_publish_error_types() {
+        let rate_limited = anyhow::Error::from(ButtonDownError::RateLimited { retry_after: None });
+        let error = publish_error("Failed to create scheduled email", rate_limited);
+        assert_eq!(error.error_type, "ButtonDown.RateLimited");
+        assert_eq!(
+            error.error_message,
+            "Failed to create scheduled email: ButtonDown rate limit exceeded"
+        );
+
+        let other = publish_error("Failed to send draft email", anyhow::anyhow!("boom"));
+        assert_eq!(other.error_type, "PublishError");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_publish() {
+        let config = handler_config();
+
+        let response = function_handler(event(true), &config).await.unwrap();
+
+        assert!(config.publisher.calls().is_empty());
+        assert_eq!(response["dryRun"], json!(true));
+
+        let html = response["renderedHtml"].as_str().unwrap();
+        assert!(html.contains("<strong>Glass3D generator</strong>"));
+        assert!(!html.contains("subscriber.metadata"));
+        let text = response["renderedText"].as_str().unwrap();
+        assert!(text.contains("Glass3D generator [3]"));
+        assert!(text.contains("\n[3] https://glass3d.dev/?utm_source=fullstackbulletin.com"));
+        assert!(!text.contains("subscriber.metadata"));
+        assert!(response["renderedContent"]
+            .as_str()
+            .unwrap()
+            .contains("Glass3D generator"));
+    }
+
+    #[tokio::test]
+    async fn test_cleans_and_tracks_the_sponsor_html() {
+        let config = handler_config();
+        let mut event = event(true);
+        event.payload.data.sponsor.customer = "ACME".to_string();
+        event.payload.data.sponsor.banner_html =
+            r#"<a href="https://acme.example">ACME</a><script>track()</script>"#.to_string();
+
+        let response = function_handler(event, &config).await.unwrap();
+
+        let tracked_url = "https://acme.example/?utm_source=fullstackbulletin.com\
+                           &utm_medium=newsletter&utm_campaign=fullstackBulletin-435\
+                           &utm_content=top-banner";
+        let banner = format!(
+            r#"<a href="{}" rel="noopener noreferrer">ACME</a>"#,
+            tracked_url.replace('&', "&amp;")
+        );
+        assert_eq!(response["sponsor"]["banner_html"], json!(banner));
+        assert!(response["renderedContent"]
+            .as_str()
+            .unwrap()
+            .contains(&banner));
+        assert_eq!(
+            response["sponsorLinks"],
+            json!([{
+                "customer": "ACME",
+                "block": "top-banner",
+                "url": "https://acme.example",
+                "trackedUrl": tracked_url
+            }])
+        );
+        assert!(!response["renderedHtml"]
+            .as_str()
+            .unwrap()
+            .contains("track()"));
+        assert_eq!(
+            response["sponsorHtml"][1],
+            json!({
+                "customer": "ACME",
+                "block": "top-banner",
+                "message": "`<script>` was removed"
+            })
+        );
+        assert_eq!(response["delivery"], json!("scheduled"));
+        assert_eq!(
+            response["validation"]["warnings"][1]["message"],
+            json!("top-banner of ACME: `<script>` was removed")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fails_without_links() {
+        let config = handler_config();
+        let mut event = event(false);
+        event.payload.data.links.clear();
+
+        assert!(function_handler(event, &config).await.is_err());
+        assert!(config.publisher.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fails_with_unknown_template() {
+        let config = handler_config();
+        let mut event = event(false);
+        event.payload.config.template = Some("weekly".to_string());
+
+        let error = function_handler(event, &config).await.unwrap_err();
+
+        assert!(error.error_message.contains("Unknown template 'weekly'"));
+        assert!(config.publisher.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_applies_link_layout_from_config() {
+        let config = handler_config();
+        let mut event = event(true);
+        event.payload.config.link_layout = Some(LinkLayout {
+            secondary_links: 1,
+            extra_links: Some(0),
+            ..LinkLayout::default()
+        });
+        let second = event.payload.data.links[1].title.clone();
+        let third = event.payload.data.links[2].title.clone();
+
+        let response = function_handler(event, &config).await.unwrap();
+
+        let rendered = response["renderedContent"].as_str().unwrap();
+        assert!(rendered.contains(&second));
+        assert!(!rendered.contains(&third));
+        assert!(!rendered.contains("###"));
+        assert_eq!(response["links"].as_array().unwrap().len(), 14);
+    }
+
+    #[tokio::test]
+    async fn test_applies_editorial_overrides() {
+        let config = handler_config();
+        let mut event = event(false);
+        let links = event.payload.data.links.clone();
+        event.payload.config.overrides = Some(EditorialOverrides {
+            primary: Some(links[3].url.clone()),
+            exclude: vec![links[0].url.clone(), "https://missing.example".to_string()],
+            ..EditorialOverrides::default()
+        });
+
+        let response = function_handler(event, &config).await.unwrap();
+
+        assert_eq!(
+            config.publisher.calls()[0],
+            PublisherCall::FindExistingEmail {
+                issue_number: 435,
+                first_link_title: links[3].title.clone()
+            }
+        );
+        assert!(response["subjectLine"]
+            .as_str()
+            .unwrap()
+            .contains(&links[3].title));
+        assert_eq!(response["links"].as_array().unwrap().len(), 13);
+        assert_eq!(
+            response["overrides"],
+            json!({
+                "applied": [
+                    { "action": "exclude", "url": links[0].url },
+                    { "action": "pin", "url": links[3].url }
+                ],
+                "ignored": [{ "action": "exclude", "url": "https://missing.example" }]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generates_intro_unless_given() {
+        let config = handler_config();
+
+        let response = function_handler(event(true), &config).await.unwrap();
+        let rendered = response["renderedContent"].as_str().unwrap();
+        assert!(rendered.contains("This issue opens with **Glass3D generator**"));
+        assert!(!rendered.contains(INTRO_PLACEHOLDER));
+
+        let mut event = event(true);
+        event.payload.config.intro = Some("A very special issue this week.".to_string());
+        let response = function_handler(event, &config).await.unwrap();
+        let rendered = response["renderedContent"].as_str().unwrap();
+        assert!(rendered.contains("\n\nA very special issue this week.\n\n"));
+        assert!(!rendered.contains("This issue opens with"));
+    }
+
+    #[tokio::test]
+    async fn test_intro_placeholder_keeps_email_as_draft() {
+        let mut config = handler_config();
+        config.template_renderer = TemplateRenderer::from_sources(
+            [(
+                "newsletter.md".to_string(),
+                "{{ greeting }}\n\nTODO: WRITE INTRO\n".to_string(),
+            )]
+            .into(),
+        )
+        .unwrap();
+
+        let response = function_handler(event(false), &config).await.unwrap();
+
+        assert!(matches!(
+            &config.publisher.calls()[1],
+            PublisherCall::CreateIssueEmail { delivery, .. } if *delivery == Delivery::Draft
+        ));
+        assert_eq!(response["delivery"], json!("draft"));
+        assert_eq!(response["emailStatus"], json!("draft"));
+        assert_eq!(
+            response["validation"]["errors"][0]["rule"],
+            json!("leftover-todo")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validation_findings_reach_the_test_draft() {
+        let config = handler_config();
+        let mut event = event(false);
+        event.payload.data.links[0].image = None;
+        event.payload.data.links[1].description.clear();
+
+        let response = function_handler(event, &config).await.unwrap();
+
+        assert_eq!(response["delivery"], json!("draft"));
+        assert_eq!(
+            response["validation"]["errors"].as_array().unwrap().len(),
+            1
+        );
+        assert_eq!(
+            response["validation"]["warnings"].as_array().unwrap().len(),
+            1
+        );
+        let calls = config.publisher.calls();
+        assert!(matches!(
+            &calls[1],
+            PublisherCall::CreateIssueEmail { delivery, .. } if *delivery == Delivery::Draft
+        ));
+        match &calls[2] {
+            PublisherCall::SendTestDraft { review_notes, .. } => {
+                assert_eq!(review_notes.len(), 2);
+                assert!(review_notes[0].starts_with("Error: The primary link"));
+                assert!(review_notes[1].starts_with("Warning: The secondary link"));
+            }
+            call => panic!("Unexpected third call: {:?}", call),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_published_issues_in_the_history() {
+        let history = Arc::new(MemoryIssueHistory::new());
+        let mut config = handler_config();
+        config.issue_history = Some(Box::new(history.clone()));
+
+        function_handler(event(true), &config).await.unwrap();
+        assert!(history.issues().unwrap().is_empty());
+
+        let response = function_handler(event(false), &config).await.unwrap();
+
+        let record = history.issue(435).unwrap().unwrap();
+        assert_eq!(record.date, "2025-08-25");
+        assert_eq!(record.subject, response["subjectLine"].as_str().unwrap());
+        assert_eq!(
+            record.quote_id,
+            response["quote"]["id"].as_u64().unwrap() as u32
+        );
+        assert_eq!(record.book_id, response["book"]["id"].as_str().unwrap());
+        assert_eq!(record.links[0], "https://glass3d.dev");
+        assert_eq!(record.links.len(), 14);
+    }
+
+    #[tokio::test]
+    async fn test_avoids_the_links_of_recent_issues() {
+        let history = MemoryIssueHistory::new();
+        history
+            .record(IssueRecord {
+                number: 434,
+                date: "2025-08-18".to_string(),
+                subject: "Issue #434".to_string(),
+                quote_id: 0,
+                book_id: "building-microservices".to_string(),
+                sponsor: None,
+                links: vec!["https://glass3d.dev/".to_string()],
+                sponsor_links: Vec::new(),
+            })
+            .unwrap();
+        let mut config = handler_config();
+        config.issue_history = Some(Box::new(history));
+        let mut event = event(true);
+        event.payload.config.link_layout = Some(LinkLayout {
+            avoid_recent_issues: Some(4),
+            ..LinkLayout::default()
+        });
+
+        let response = function_handler(event, &config).await.unwrap();
+
+        assert_eq!(response["repeatedLinks"], json!(["https://glass3d.dev"]));
+        assert!(!response["subjectLine"]
+            .as_str()
+            .unwrap()
+            .contains("Glass3D generator"));
+    }
 }
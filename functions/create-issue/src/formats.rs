@@ -0,0 +1,353 @@
+//! The other output formats of the newsletter, derived from the rendered markdown so that
+//! every format carries exactly the same content: sanitized HTML for the website and RSS,
+//! and plain text for email clients that don't render markdown.
+
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+
+/// The newsletter rendered in every output format
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenderedNewsletter {
+    /// What ButtonDown consumes, including its merge tags
+    pub markdown: String,
+    pub html: String,
+    pub text: String,
+}
+
+impl RenderedNewsletter {
+    pub fn from_markdown(markdown: String) -> Self {
+        Self {
+            html: to_html(&markdown),
+            text: to_text(&markdown),
+            markdown,
+        }
+    }
+}
+
+/// Remove the ButtonDown merge tags (e.g. `{{ subscriber.metadata.first_name }}`), which only
+/// make sense in the email, together with the `, ` that introduces them
+fn strip_merge_tags(markdown: &str) -> String {
+    let mut result = String::with_capacity(markdown.len());
+    let mut rest = markdown;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start..].find("}}") else {
+            break;
+        };
+        let before = &rest[..start];
+        result.push_str(before.strip_suffix(", ").unwrap_or(before));
+        rest = &rest[start + length + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Render the markdown as HTML, sanitizing the embedded HTML (e.g. the sponsor blocks)
+pub fn to_html(markdown: &str) -> String {
+    let markdown = strip_merge_tags(markdown);
+    let mut rendered = String::with_capacity(markdown.len() * 2);
+    html::push_html(
+        &mut rendered,
+        Parser::new_ext(&markdown, Options::ENABLE_STRIKETHROUGH),
+    );
+    ammonia::clean(&rendered)
+}
+
+/// Render the markdown as plain text, replacing every link with a numbered reference
+/// listed at the end
+pub fn to_text(markdown: &str) -> String {
+    let markdown = strip_merge_tags(markdown);
+    let mut writer = TextWriter::default();
+    for event in Parser::new_ext(&markdown, Options::ENABLE_STRIKETHROUGH) {
+        writer.event(event);
+    }
+    writer.finish()
+}
+
+#[derive(Default)]
+struct TextWriter {
+    text: String,
+    references: Vec<String>,
+    /// Open links: their URL (if any) and where their text starts
+    links: Vec<(Option<String>, usize)>,
+    /// Open lists: the number of the next item, `None` for bullet lists
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    image_depth: usize,
+    html_block: Option<String>,
+}
+
+impl TextWriter {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::BlockQuote(_)) => self.quote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => self.quote_depth -= 1,
+            Event::Start(Tag::List(first)) => {
+                self.end_line();
+                self.lists.push(first);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.end_block();
+                }
+            }
+            Event::Start(Tag::Item) => {
+                self.end_line();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.push(&format!("{}{}", indent, marker));
+            }
+            Event::End(TagEnd::Item) => self.end_line(),
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                self.links
+                    .push((Some(dest_url.to_string()), self.text.len()));
+            }
+            Event::End(TagEnd::Link) => self.end_link(),
+            Event::Start(Tag::Image { .. }) => self.image_depth += 1,
+            Event::End(TagEnd::Image) => self.image_depth -= 1,
+            Event::Start(Tag::HtmlBlock) => self.html_block = Some(String::new()),
+            Event::Html(html) => {
+                if let Some(block) = self.html_block.as_mut() {
+                    block.push_str(&html);
+                }
+            }
+            Event::End(TagEnd::HtmlBlock) => {
+                if let Some(block) = self.html_block.take() {
+                    self.html(&block);
+                }
+                self.end_block();
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock) => {
+                if self.lists.is_empty() {
+                    self.end_block();
+                } else {
+                    self.end_line();
+                }
+            }
+            Event::Text(text) | Event::Code(text) if self.image_depth == 0 => self.push(&text),
+            Event::SoftBreak | Event::HardBreak => self.push("\n"),
+            Event::Rule => {
+                self.end_block();
+                self.push("----------");
+                self.end_block();
+            }
+            _ => {}
+        }
+    }
+
+    /// Append text, quoting every new line inside a block quote
+    fn push(&mut self, text: &str) {
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.text.push('\n');
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if self.quote_depth > 0 && (self.text.is_empty() || self.text.ends_with('\n')) {
+                self.text.push_str(&"> ".repeat(self.quote_depth));
+            }
+            self.text.push_str(line);
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.trim_trailing_spaces();
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+
+    fn end_block(&mut self) {
+        self.end_line();
+        if !self.text.is_empty() && !self.text.ends_with("\n\n") {
+            self.text.push('\n');
+        }
+    }
+
+    fn trim_trailing_spaces(&mut self) {
+        let trimmed = self.text.trim_end_matches([' ', '\t']).len();
+        self.text.truncate(trimmed);
+    }
+
+    /// Close a link, adding its reference unless it has no text (e.g. a linked image)
+    fn end_link(&mut self) {
+        let Some((url, start)) = self.links.pop() else {
+            return;
+        };
+        let Some(url) = url else {
+            return;
+        };
+        if self.text[start..].trim().is_empty() {
+            return;
+        }
+        let number = match self.references.iter().position(|known| *known == url) {
+            Some(index) => index + 1,
+            None => {
+                self.references.push(url);
+                self.references.len()
+            }
+        };
+        self.push(&format!(" [{}]", number));
+    }
+
+    /// Extract the text and the links of a block of HTML
+    fn html(&mut self, html: &str) {
+        // Sanitizing first drops comments, scripts and styles and normalizes the markup
+        let clean = ammonia::clean(html);
+        let mut rest = clean.as_str();
+
+        while let Some(start) = rest.find('<') {
+            self.html_text(&rest[..start]);
+            let Some(length) = rest[start..].find('>') else {
+                rest = "";
+                break;
+            };
+            let tag = &rest[start + 1..start + length];
+            let name = tag
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            match name.as_str() {
+                "a" => self.links.push((attribute(tag, "href"), self.text.len())),
+                "/a" => self.end_link(),
+                "br" => self.push("\n"),
+                "li" => {
+                    self.end_line();
+                    self.push("- ");
+                }
+                "p" | "/p" | "div" | "/div" | "/li" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+                | "/h1" | "/h2" | "/h3" | "/h4" | "/h5" | "/h6" | "tr" => self.end_line(),
+                _ => {}
+            }
+            rest = &rest[start + length + 1..];
+        }
+        self.html_text(rest);
+    }
+
+    fn html_text(&mut self, text: &str) {
+        let text = decode_entities(text);
+        let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            return;
+        }
+        let at_line_start = self.text.is_empty() || self.text.ends_with('\n');
+        if !at_line_start && text.starts_with(char::is_whitespace) {
+            self.push(" ");
+        }
+        self.push(&collapsed);
+        if text.ends_with(char::is_whitespace) {
+            self.push(" ");
+        }
+    }
+
+    fn finish(mut self) -> String {
+        let mut text = self.text.trim().to_string();
+        if !self.references.is_empty() {
+            text.push_str("\n\n");
+            for (index, url) in self.references.drain(..).enumerate() {
+                text.push_str(&format!("[{}] {}\n", index + 1, url));
+            }
+        } else {
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// The value of an attribute of a tag serialized by ammonia (always double quoted)
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+    let length = tag[start..].find('"')?;
+    Some(decode_entities(&tag[start..start + length]))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_merge_tags() {
+        assert_eq!(
+            strip_merge_tags("Hey there, {{ subscriber.metadata.first_name }}\n\nHi"),
+            "Hey there\n\nHi"
+        );
+        assert_eq!(strip_merge_tags("No tags {{ here"), "No tags {{ here");
+    }
+
+    #[test]
+    fn test_html_is_sanitized() {
+        let html = to_html(
+            "Hello, {{ subscriber.metadata.first_name }}\n\n\
+             <div onclick=\"steal()\"><script>alert(1)</script><a href=\"https://sponsor.example\">Sponsor</a></div>\n\n\
+             [**Link**](https://example.com)",
+        );
+
+        assert!(html.contains("<p>Hello</p>"));
+        assert!(html.contains(
+            "<a href=\"https://sponsor.example\" rel=\"noopener noreferrer\">Sponsor</a>"
+        ));
+        assert!(html.contains("<strong>Link</strong>"));
+        assert!(!html.contains("script"));
+        assert!(!html.contains("onclick"));
+        assert!(!html.contains("subscriber"));
+    }
+
+    #[test]
+    fn test_text_with_link_references() {
+        let text = to_text(
+            "# Title\n\n\
+             > \"Talk is cheap\"  \n\
+             > — [Linus](https://linus.example), Creator\n\n\
+             [**First**](https://first.example) — a link [**Read Article**](https://first.example)\n\n\
+             ---\n\n\
+             - [Extra](https://extra.example)\n\
+             - Second extra\n",
+        );
+
+        assert_eq!(
+            text,
+            "Title\n\n\
+             > \"Talk is cheap\"\n\
+             > — Linus [1], Creator\n\n\
+             First [2] — a link Read Article [2]\n\n\
+             ----------\n\n\
+             - Extra [3]\n\
+             - Second extra\n\n\
+             [1] https://linus.example\n\
+             [2] https://first.example\n\
+             [3] https://extra.example\n"
+        );
+    }
+
+    #[test]
+    fn test_text_skips_images_and_reads_html_blocks() {
+        let text = to_text(
+            "<a href=\"https://image.example\"><img src=\"https://image.example/a.png\"></a>\n\n\
+             [![Cover](https://cover.example/c.jpg)](https://book.example)\n\n\
+             <div><p>Try <a href=\"https://sponsor.example?a=1&amp;b=2\">ACME</a> &amp; co</p><ul><li>Fast</li><li>Safe</li></ul></div>\n",
+        );
+
+        assert_eq!(
+            text,
+            "Try ACME [1] & co\n- Fast\n- Safe\n\n[1] https://sponsor.example?a=1&b=2\n"
+        );
+    }
+}
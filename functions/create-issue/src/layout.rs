@@ -0,0 +1,362 @@
+//! How the links of an issue are distributed between the sections of the newsletter,
+//! according to the [`LinkLayout`] of the event config.
+
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{Link, LinkGrouping, LinkLayout};
+use shared::history::IssueRecord;
+
+/// The kind of content a link points to, guessed from its URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    Repo,
+    Video,
+    Article,
+}
+
+impl LinkKind {
+    pub fn from_url(url: &str) -> Self {
+        if url.contains("github.com") {
+            LinkKind::Repo
+        } else if url.contains("youtube.com") || url.contains("youtu.be") {
+            LinkKind::Video
+        } else {
+            LinkKind::Article
+        }
+    }
+
+    /// The call to action of a link of this kind
+    pub fn action_text(self) -> &'static str {
+        match self {
+            LinkKind::Repo => "Check Repo",
+            LinkKind::Video => "Watch Video",
+            LinkKind::Article => "Read Article",
+        }
+    }
+}
+
+/// The domain of a URL without `www.` (the whole URL when it can't be parsed)
+pub fn link_domain(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .map(|host| {
+            host.strip_prefix("www.")
+                .map(str::to_string)
+                .unwrap_or(host)
+        })
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// The links of an issue, by section
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkSections<'a> {
+    pub primary: &'a Link,
+    pub secondary: Vec<&'a Link>,
+    pub extra: Vec<&'a Link>,
+    /// The links left out, because of the domain cap or the size of the sections
+    pub dropped: Vec<&'a Link>,
+}
+
+/// Distribute the links between the sections, keeping their order (i.e. their ranking)
+/// unless they're grouped. `None` when there are no links.
+pub fn lay_out<'a>(links: &'a [Link], layout: &LinkLayout) -> Option<LinkSections<'a>> {
+    let mut dropped = Vec::new();
+    let mut kept = Vec::new();
+    let mut per_domain: HashMap<String, usize> = HashMap::new();
+    for link in links {
+        if let Some(max_per_domain) = layout.max_per_domain {
+            let count = per_domain.entry(link_domain(&link.url)).or_default();
+            if *count >= max_per_domain.max(1) {
+                dropped.push(link);
+                continue;
+            }
+            *count += 1;
+        }
+        kept.push(link);
+    }
+
+    let mut kept = kept.into_iter();
+    let primary = kept.next()?;
+    let mut secondary: Vec<&Link> = kept.by_ref().take(layout.secondary_links).collect();
+    let mut extra: Vec<&Link> = match layout.extra_links {
+        Some(extra_links) => kept.by_ref().take(extra_links).collect(),
+        None => kept.by_ref().collect(),
+    };
+    dropped.extend(kept);
+
+    // Grouping happens within each section, so that it never promotes a link to an
+    // earlier section
+    if let Some(grouping) = layout.group_by {
+        group(&mut secondary, grouping);
+        group(&mut extra, grouping);
+    }
+
+    Some(LinkSections {
+        primary,
+        secondary,
+        extra,
+        dropped,
+    })
+}
+
+/// Leave out the links that were already in one of the `recent` issues, comparing URLs
+/// ignoring a trailing slash. Returns the remaining links and the URLs left out.
+pub fn skip_repeated_links(links: Vec<Link>, recent: &[IssueRecord]) -> (Vec<Link>, Vec<String>) {
+    let published: HashSet<&str> = recent
+        .iter()
+        .flat_map(|issue| issue.links.iter())
+        .map(|url| url.trim_end_matches('/'))
+        .collect();
+    let (repeated, links): (Vec<Link>, Vec<Link>) = links
+        .into_iter()
+        .partition(|link| published.contains(link.url.trim_end_matches('/')));
+    (links, repeated.into_iter().map(|link| link.url).collect())
+}
+
+/// Move the links of the same group next to each other. Groups appear in the order of their
+/// first link, and links keep their order within a group.
+fn group(links: &mut [&Link], grouping: LinkGrouping) {
+    let key = |link: &Link| match grouping {
+        LinkGrouping::Domain => link_domain(&link.url),
+        LinkGrouping::Kind => LinkKind::from_url(&link.url).action_text().to_string(),
+    };
+    let mut groups: Vec<String> = Vec::new();
+    for link in links.iter() {
+        let key = key(link);
+        if !groups.contains(&key) {
+            groups.push(key);
+        }
+    }
+    links.sort_by_cached_key(|link| {
+        let key = key(link);
+        groups.iter().position(|group| *group == key)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CampaignUrls;
+
+    fn link(url: &str) -> Link {
+        Link {
+            title: url.to_string(),
+            url: url.to_string(),
+            description: String::new(),
+            image: None,
+            score: 0,
+            original_image: String::new(),
+            campaign_urls: CampaignUrls {
+                title: url.to_string(),
+                image: url.to_string(),
+                description: url.to_string(),
+            },
+        }
+    }
+
+    fn urls(links: &[&Link]) -> Vec<String> {
+        links.iter().map(|link| link.url.clone()).collect()
+    }
+
+    fn links() -> Vec<Link> {
+        [
+            "https://blog.example.com/1",
+            "https://github.com/a/b",
+            "https://www.youtube.com/watch?v=1",
+            "https://blog.example.com/2",
+            "https://github.com/c/d",
+            "https://news.example.org/1",
+            "https://blog.example.com/3",
+            "https://youtu.be/2",
+            "https://blog.example.com/4",
+            "https://news.example.org/2",
+        ]
+        .into_iter()
+        .map(link)
+        .collect()
+    }
+
+    #[test]
+    fn test_link_kind() {
+        assert_eq!(LinkKind::from_url("https://github.com/a/b"), LinkKind::Repo);
+        assert_eq!(LinkKind::from_url("https://youtu.be/x"), LinkKind::Video);
+        assert_eq!(
+            LinkKind::from_url("https://example.com/post"),
+            LinkKind::Article
+        );
+        assert_eq!(LinkKind::Video.action_text(), "Watch Video");
+    }
+
+    #[test]
+    fn test_link_domain() {
+        assert_eq!(
+            link_domain("https://www.youtube.com/watch?v=1"),
+            "youtube.com"
+        );
+        assert_eq!(
+            link_domain("https://blog.example.com/1"),
+            "blog.example.com"
+        );
+        assert_eq!(link_domain("not a url"), "not a url");
+    }
+
+    #[test]
+    fn test_default_layout() {
+        let links = links();
+
+        let sections = lay_out(&links, &LinkLayout::default()).unwrap();
+
+        assert_eq!(sections.primary.url, "https://blog.example.com/1");
+        assert_eq!(
+            urls(&sections.secondary),
+            urls(&links[1..7].iter().collect::<Vec<_>>())
+        );
+        assert_eq!(
+            urls(&sections.extra),
+            urls(&links[7..].iter().collect::<Vec<_>>())
+        );
+        assert!(sections.dropped.is_empty());
+        assert!(lay_out(&[], &LinkLayout::default()).is_none());
+    }
+
+    #[test]
+    fn test_section_sizes() {
+        let links = links();
+        let layout = LinkLayout {
+            secondary_links: 2,
+            extra_links: Some(3),
+            ..LinkLayout::default()
+        };
+
+        let sections = lay_out(&links, &layout).unwrap();
+
+        assert_eq!(sections.secondary.len(), 2);
+        assert_eq!(
+            urls(&sections.extra),
+            vec![
+                "https://blog.example.com/2",
+                "https://github.com/c/d",
+                "https://news.example.org/1"
+            ]
+        );
+        assert_eq!(sections.dropped.len(), 4);
+    }
+
+    #[test]
+    fn test_max_per_domain() {
+        let links = links();
+        let layout = LinkLayout {
+            max_per_domain: Some(1),
+            ..LinkLayout::default()
+        };
+
+        let sections = lay_out(&links, &layout).unwrap();
+
+        assert_eq!(
+            urls(&sections.secondary),
+            vec![
+                "https://github.com/a/b",
+                "https://www.youtube.com/watch?v=1",
+                "https://news.example.org/1",
+                "https://youtu.be/2",
+            ]
+        );
+        assert!(sections.extra.is_empty());
+        assert_eq!(
+            urls(&sections.dropped),
+            vec![
+                "https://blog.example.com/2",
+                "https://github.com/c/d",
+                "https://blog.example.com/3",
+                "https://blog.example.com/4",
+                "https://news.example.org/2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_kind() {
+        let links = links();
+        let layout = LinkLayout {
+            group_by: Some(LinkGrouping::Kind),
+            ..LinkLayout::default()
+        };
+
+        let sections = lay_out(&links, &layout).unwrap();
+
+        assert_eq!(sections.primary.url, "https://blog.example.com/1");
+        assert_eq!(
+            urls(&sections.secondary),
+            vec![
+                "https://github.com/a/b",
+                "https://github.com/c/d",
+                "https://www.youtube.com/watch?v=1",
+                "https://blog.example.com/2",
+                "https://news.example.org/1",
+                "https://blog.example.com/3",
+            ]
+        );
+        assert_eq!(
+            urls(&sections.extra),
+            vec![
+                "https://youtu.be/2",
+                "https://blog.example.com/4",
+                "https://news.example.org/2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_domain() {
+        let links = links();
+        let layout = LinkLayout {
+            group_by: Some(LinkGrouping::Domain),
+            ..LinkLayout::default()
+        };
+
+        let sections = lay_out(&links, &layout).unwrap();
+
+        assert_eq!(
+            urls(&sections.secondary),
+            vec![
+                "https://github.com/a/b",
+                "https://github.com/c/d",
+                "https://www.youtube.com/watch?v=1",
+                "https://blog.example.com/2",
+                "https://blog.example.com/3",
+                "https://news.example.org/1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_repeated_links() {
+        let recent = [IssueRecord {
+            number: 434,
+            date: "2025-08-18".to_string(),
+            subject: "Issue #434".to_string(),
+            quote_id: 0,
+            book_id: "building-microservices".to_string(),
+            sponsor: None,
+            links: vec![
+                "https://github.com/a/b/".to_string(),
+                "https://news.example.org/1".to_string(),
+            ],
+            sponsor_links: Vec::new(),
+        }];
+
+        let (links, repeated) = skip_repeated_links(links(), &recent);
+
+        assert_eq!(
+            repeated,
+            vec!["https://github.com/a/b", "https://news.example.org/1"]
+        );
+        assert_eq!(links.len(), 8);
+        assert!(links.iter().all(|link| !repeated.contains(&link.url)));
+        assert_eq!(skip_repeated_links(links.clone(), &[]).0, links);
+    }
+}
@@ -0,0 +1,12 @@
+pub mod buttondown;
+pub mod event_handler;
+pub mod formats;
+pub mod layout;
+pub mod link_health;
+pub mod lint;
+pub mod model;
+pub mod overrides;
+pub mod publisher;
+pub mod sponsor_html;
+pub mod template;
+pub mod validation;
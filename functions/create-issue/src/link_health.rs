@@ -0,0 +1,446 @@
+//! Health checks of the URLs of an issue: every link, image and book URL is requested
+//! (`HEAD`, or `GET` when the server doesn't allow `HEAD`) without following redirects, a
+//! few at a time, and classified. The results can be kept in a file cache, so that the
+//! local runs of an issue don't hit the same servers over and over.
+
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use lambda_runtime::tracing;
+use reqwest::{header::LOCATION, redirect::Policy, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::model::{Book, Link, Quote};
+
+/// Where the local runner keeps the results, relative to the working directory
+pub const DEFAULT_CACHE_FILE: &str = ".cache/link-health.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkHealth {
+    Ok,
+    Redirect,
+    ClientError,
+    ServerError,
+    Timeout,
+    /// DNS, connection or TLS errors
+    Unreachable,
+}
+
+impl LinkHealth {
+    fn from_status(status: StatusCode) -> Self {
+        if status.is_redirection() {
+            LinkHealth::Redirect
+        } else if status.is_client_error() {
+            LinkHealth::ClientError
+        } else if status.is_server_error() {
+            LinkHealth::ServerError
+        } else {
+            LinkHealth::Ok
+        }
+    }
+
+    /// Whether the same check may well succeed a bit later, so it's not worth caching
+    fn is_transient(self) -> bool {
+        matches!(
+            self,
+            LinkHealth::ServerError | LinkHealth::Timeout | LinkHealth::Unreachable
+        )
+    }
+}
+
+impl fmt::Display for LinkHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LinkHealth::Ok => "ok",
+            LinkHealth::Redirect => "redirect",
+            LinkHealth::ClientError => "client error",
+            LinkHealth::ServerError => "server error",
+            LinkHealth::Timeout => "timeout",
+            LinkHealth::Unreachable => "unreachable",
+        })
+    }
+}
+
+/// The outcome of the check of a single URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrlCheck {
+    pub url: String,
+    pub health: LinkHealth,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Where a redirect points to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+/// Every URL of the issue content, without duplicates, in a stable order
+pub fn referenced_urls(quote: &Quote, book: &Book, links: &[Link]) -> Vec<String> {
+    let link_urls = links.iter().flat_map(|link| {
+        [
+            Some(&link.url),
+            link.image.as_ref(),
+            Some(&link.campaign_urls.title),
+            Some(&link.campaign_urls.image),
+            Some(&link.campaign_urls.description),
+        ]
+    });
+    let book_urls = [&book.cover_picture, &book.links.us, &book.links.uk]
+        .into_iter()
+        .chain(book.links.other.values())
+        .map(Some);
+
+    let mut urls: Vec<String> = Vec::new();
+    for url in link_urls
+        .chain(book_urls)
+        .chain([quote.author_url.as_ref()])
+        .flatten()
+    {
+        if !url.is_empty() && !urls.contains(url) {
+            urls.push(url.clone());
+        }
+    }
+    urls
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedCheck {
+    #[serde(flatten)]
+    check: UrlCheck,
+    checked_at: DateTime<Utc>,
+}
+
+/// Check results stored in a JSON file, valid for `ttl`
+#[derive(Debug)]
+pub struct LinkCache {
+    path: PathBuf,
+    ttl: chrono::Duration,
+    entries: Mutex<BTreeMap<String, CachedCheck>>,
+}
+
+impl LinkCache {
+    /// Open the cache stored in `path`. A missing or unreadable file is an empty cache.
+    pub fn open(path: impl Into<PathBuf>, ttl: Duration) -> Self {
+        let path = path.into();
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            ttl: chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn get(&self, url: &str, now: DateTime<Utc>) -> Option<UrlCheck> {
+        let entries = self.entries.lock().unwrap();
+        let cached = entries.get(url)?;
+        (now.signed_duration_since(cached.checked_at) < self.ttl).then(|| cached.check.clone())
+    }
+
+    fn insert(&self, check: UrlCheck, now: DateTime<Utc>) {
+        self.entries.lock().unwrap().insert(
+            check.url.clone(),
+            CachedCheck {
+                check,
+                checked_at: now,
+            },
+        );
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let entries = self.entries.lock().unwrap();
+        std::fs::write(&self.path, serde_json::to_string_pretty(&*entries)?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkCheckOptions {
+    /// How many URLs are checked at the same time
+    pub concurrency: usize,
+    /// How long a single request can take, redirects excluded
+    pub timeout: Duration,
+}
+
+impl Default for LinkCheckOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+pub struct LinkChecker {
+    client: Client,
+    concurrency: usize,
+    cache: Option<LinkCache>,
+}
+
+impl LinkChecker {
+    pub fn new(options: &LinkCheckOptions) -> Result<Self, reqwest::Error> {
+        let client = Client::builder()
+            .redirect(Policy::none())
+            .timeout(options.timeout)
+            .user_agent("FullStackBulletin-LinkChecker/1.0")
+            .build()?;
+        Ok(Self {
+            client,
+            concurrency: options.concurrency.max(1),
+            cache: None,
+        })
+    }
+
+    pub fn with_cache(self, cache: LinkCache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Check every URL, in the same order, reusing the cached results that are still valid
+    pub async fn check(&self, urls: &[String]) -> Vec<UrlCheck> {
+        let now = Utc::now();
+        let results: Vec<(UrlCheck, bool)> = stream::iter(urls)
+            .map(|url| async move {
+                match self.cache.as_ref().and_then(|cache| cache.get(url, now)) {
+                    Some(check) => (check, true),
+                    None => (self.check_url(url).await, false),
+                }
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        if let Some(cache) = &self.cache {
+            for (check, _) in results
+                .iter()
+                .filter(|(check, cached)| !cached && !check.health.is_transient())
+            {
+                cache.insert(check.clone(), now);
+            }
+            if let Err(error) = cache.save() {
+                tracing::warn!(
+                    "Failed to save the link cache {}: {}",
+                    cache.path().display(),
+                    error
+                );
+            }
+        }
+
+        results.into_iter().map(|(check, _)| check).collect()
+    }
+
+    async fn check_url(&self, url: &str) -> UrlCheck {
+        let response = match self.client.head(url).send().await {
+            // Some servers only answer GET requests
+            Ok(response)
+                if matches!(
+                    response.status(),
+                    StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+                ) =>
+            {
+                self.client.get(url).send().await
+            }
+            response => response,
+        };
+
+        match response {
+            Ok(response) => UrlCheck {
+                url: url.to_string(),
+                health: LinkHealth::from_status(response.status()),
+                status: Some(response.status().as_u16()),
+                location: response
+                    .status()
+                    .is_redirection()
+                    .then(|| response.headers().get(LOCATION))
+                    .flatten()
+                    .and_then(|location| location.to_str().ok())
+                    .map(str::to_string),
+            },
+            Err(error) => UrlCheck {
+                url: url.to_string(),
+                health: if error.is_timeout() {
+                    LinkHealth::Timeout
+                } else {
+                    LinkHealth::Unreachable
+                },
+                status: None,
+                location: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Event;
+    use httpmock::prelude::*;
+
+    const ISSUE_435: &str = include_str!("../events/issue-435.json");
+
+    fn checker() -> LinkChecker {
+        LinkChecker::new(&LinkCheckOptions {
+            concurrency: 2,
+            timeout: Duration::from_millis(500),
+        })
+        .unwrap()
+    }
+
+    fn cache_file(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "create-issue-link-cache-{}-{}/link-health.json",
+            test,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_referenced_urls() {
+        let event: Event = serde_json::from_str(ISSUE_435).unwrap();
+        let data = &event.data;
+
+        let urls = referenced_urls(&data.quote, &data.book, &data.links);
+
+        assert_eq!(urls[0], data.links[0].url);
+        assert!(urls.contains(&data.links[0].campaign_urls.image));
+        assert!(urls.contains(&data.book.cover_picture));
+        assert!(urls.contains(&data.book.links.us));
+        let mut unique = urls.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), urls.len());
+        assert!(urls.iter().all(|url| !url.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_classifies_responses() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method("HEAD").path("/ok");
+            then.status(200);
+        });
+        server.mock(|when, then| {
+            when.method("HEAD").path("/moved");
+            then.status(301)
+                .header("Location", "https://example.com/new");
+        });
+        server.mock(|when, then| {
+            when.method("HEAD").path("/missing");
+            then.status(404);
+        });
+        server.mock(|when, then| {
+            when.method("HEAD").path("/broken");
+            then.status(503);
+        });
+        server.mock(|when, then| {
+            when.method("HEAD").path("/slow");
+            then.status(200).delay(Duration::from_secs(2));
+        });
+        server.mock(|when, then| {
+            when.method("HEAD").path("/get-only");
+            then.status(405);
+        });
+        let get_only = server.mock(|when, then| {
+            when.method(GET).path("/get-only");
+            then.status(200);
+        });
+        let urls: Vec<String> = ["/ok", "/moved", "/missing", "/broken", "/slow", "/get-only"]
+            .into_iter()
+            .map(|path| server.url(path))
+            .chain(["http://127.0.0.1:1/closed".to_string()])
+            .collect();
+
+        let checks = checker().check(&urls).await;
+
+        assert_eq!(
+            checks
+                .iter()
+                .map(|check| (check.health, check.status))
+                .collect::<Vec<_>>(),
+            vec![
+                (LinkHealth::Ok, Some(200)),
+                (LinkHealth::Redirect, Some(301)),
+                (LinkHealth::ClientError, Some(404)),
+                (LinkHealth::ServerError, Some(503)),
+                (LinkHealth::Timeout, None),
+                (LinkHealth::Ok, Some(200)),
+                (LinkHealth::Unreachable, None),
+            ]
+        );
+        assert_eq!(
+            checks[1].location.as_deref(),
+            Some("https://example.com/new")
+        );
+        assert_eq!(checks[0].url, urls[0]);
+        get_only.assert();
+    }
+
+    #[tokio::test]
+    async fn test_cache_skips_known_urls() {
+        let server = MockServer::start();
+        let ok = server.mock(|when, then| {
+            when.method("HEAD").path("/ok");
+            then.status(200);
+        });
+        let broken = server.mock(|when, then| {
+            when.method("HEAD").path("/broken");
+            then.status(500);
+        });
+        let urls = vec![server.url("/ok"), server.url("/broken")];
+        let path = cache_file("skips");
+
+        let checker = checker().with_cache(LinkCache::open(&path, Duration::from_secs(3600)));
+        let first = checker.check(&urls).await;
+        let checker = checker.with_cache(LinkCache::open(&path, Duration::from_secs(3600)));
+        let second = checker.check(&urls).await;
+
+        assert_eq!(first, second);
+        // Server errors may be transient, so they're checked again
+        assert_eq!(ok.hits(), 1);
+        assert_eq!(broken.hits(), 2);
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"checkedAt\""));
+    }
+
+    #[tokio::test]
+    async fn test_expired_cache_entries_are_checked_again() {
+        let server = MockServer::start();
+        let ok = server.mock(|when, then| {
+            when.method("HEAD").path("/ok");
+            then.status(200);
+        });
+        let urls = vec![server.url("/ok")];
+        let path = cache_file("expired");
+
+        checker()
+            .with_cache(LinkCache::open(&path, Duration::ZERO))
+            .check(&urls)
+            .await;
+        checker()
+            .with_cache(LinkCache::open(&path, Duration::ZERO))
+            .check(&urls)
+            .await;
+
+        assert_eq!(ok.hits(), 2);
+    }
+}
@@ -0,0 +1,681 @@
+//! Checks of the newsletter templates, so that a typo in a template fails a test or the
+//! `lint-templates` command of the local runner instead of the Lambda on a Friday evening.
+//!
+//! Every selectable template is rendered with the issue 435 fixture and a few edge cases
+//! derived from it. Its variables are also checked against the context, including the
+//! ones only used in conditions, which Tera silently treats as `false` when undefined.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    path::Path,
+};
+use tera::ast::{Expr, ExprVal, Node};
+
+use crate::layout::lay_out;
+use crate::model::{Event, LinkLayout, Sponsor, SponsorPlacement, SponsorSlot};
+use crate::template::{
+    generate_extra_content_title, load_sources, newsletter_context, NewsletterData,
+    TemplateRenderer, PARTIALS_DIR,
+};
+
+const FIXTURE: &str = include_str!("../events/issue-435.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The template fails to render or renders something broken
+    Error,
+    /// The template works, but something is probably a mistake
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    pub severity: Severity,
+    pub template: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.template)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LintReport {
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    pub fn errors(&self) -> impl Iterator<Item = &LintFinding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &LintFinding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    fn push(&mut self, severity: Severity, template: &str, line: Option<usize>, message: String) {
+        self.findings.push(LintFinding {
+            severity,
+            template: template.to_string(),
+            line,
+            message,
+        });
+    }
+}
+
+/// A sample event the templates are rendered with
+pub struct Scenario {
+    pub name: &'static str,
+    pub event: Event,
+}
+
+/// The fixture event and the edge cases every template has to handle
+pub fn scenarios() -> Vec<Scenario> {
+    let fixture: Event = serde_json::from_str(FIXTURE).expect("The fixture event is valid");
+
+    let mut no_extra_links = fixture.clone();
+    no_extra_links.data.links.truncate(7);
+    let mut empty_sponsor = fixture.clone();
+    empty_sponsor.data.sponsor = Sponsor::default();
+    let mut missing_author_url = fixture.clone();
+    missing_author_url.data.quote.author_url = None;
+    let mut no_uk_link = fixture.clone();
+    no_uk_link.data.book.links.uk.clear();
+    let mut several_sponsors = fixture.clone();
+    several_sponsors.data.sponsor.placements = vec![
+        SponsorPlacement {
+            slot: SponsorSlot::MidBanner,
+            customer: "ACME".to_string(),
+            html: "<a href=\"https://acme.example\">ACME</a>".to_string(),
+        },
+        SponsorPlacement {
+            slot: SponsorSlot::Classified,
+            customer: "Initech".to_string(),
+            html: "<a href=\"https://initech.example\">Initech</a> is hiring".to_string(),
+        },
+    ];
+
+    vec![
+        Scenario {
+            name: "issue 435",
+            event: fixture,
+        },
+        Scenario {
+            name: "no extra links",
+            event: no_extra_links,
+        },
+        Scenario {
+            name: "empty sponsor",
+            event: empty_sponsor,
+        },
+        Scenario {
+            name: "missing authorUrl",
+            event: missing_author_url,
+        },
+        Scenario {
+            name: "no UK link",
+            event: no_uk_link,
+        },
+        Scenario {
+            name: "several sponsors",
+            event: several_sponsors,
+        },
+    ]
+}
+
+fn render(renderer: &TemplateRenderer, template: &str, event: &Event) -> Result<()> {
+    let links = lay_out(&event.data.links, &LinkLayout::default()).expect("The events have links");
+    renderer.render_newsletter(
+        Some(template),
+        &NewsletterData {
+            issue_number: event.next_issue.number,
+            quote: &event.data.quote,
+            book: &event.data.book,
+            links: &links,
+            extra_content_title: &generate_extra_content_title(event.next_issue.number),
+            sponsor: Some(&event.data.sponsor),
+            intro: event.config.intro.as_deref(),
+        },
+    )?;
+    Ok(())
+}
+
+fn context(event: &Event) -> Value {
+    let links = lay_out(&event.data.links, &LinkLayout::default()).expect("The events have links");
+    newsletter_context(&NewsletterData {
+        issue_number: event.next_issue.number,
+        quote: &event.data.quote,
+        book: &event.data.book,
+        links: &links,
+        extra_content_title: &generate_extra_content_title(event.next_issue.number),
+        sponsor: Some(&event.data.sponsor),
+        intro: event.config.intro.as_deref(),
+    })
+    .into_json()
+}
+
+/// Lint the embedded templates, overridden by the ones in `dir` like
+/// [`TemplateRenderer::from_dir`] does. Problems with the templates are reported as
+/// findings: an error is only returned when the templates can't be read.
+pub fn lint_templates(dir: Option<&Path>) -> Result<LintReport> {
+    let sources = load_sources(dir)?;
+    let mut report = LintReport::default();
+
+    // Tera reports these as a generic syntax error, often far from the culprit
+    for (name, source) in &sources {
+        check_raw_blocks(name, source, &mut report);
+    }
+    if report.has_errors() {
+        return Ok(report);
+    }
+
+    let renderer = match TemplateRenderer::from_sources(sources.clone()) {
+        Ok(renderer) => renderer,
+        Err(error) => {
+            let message = error.to_string();
+            let template = message.split('\'').nth(1).unwrap_or_default().to_string();
+            report.push(Severity::Error, &template, None, message);
+            return Ok(report);
+        }
+    };
+
+    let scenarios = scenarios();
+    let contexts: Vec<Value> = scenarios
+        .iter()
+        .map(|scenario| context(&scenario.event))
+        .collect();
+    let context_keys: BTreeSet<&str> = contexts
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|context| context.keys().map(String::as_str))
+        .collect();
+    let mut included = BTreeSet::new();
+
+    for template in renderer.templates() {
+        let mut checker = VariableChecker {
+            renderer: &renderer,
+            contexts: &contexts,
+            report: &mut report,
+            used: BTreeSet::new(),
+            undefined: BTreeSet::new(),
+            included: &mut included,
+            stack: vec![template.to_string()],
+        };
+        if let Some(parsed) = renderer.template(template) {
+            checker.nodes(template, &parsed.ast, &mut HashMap::new());
+        }
+        let VariableChecker {
+            used, undefined, ..
+        } = checker;
+
+        // The same error usually happens with every scenario: report it once
+        let mut failures: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for scenario in &scenarios {
+            if let Err(error) = render(&renderer, template, &scenario.event) {
+                failures
+                    .entry(error.to_string())
+                    .or_default()
+                    .push(scenario.name);
+            }
+        }
+        for (message, failing) in failures {
+            let already_reported = undefined
+                .iter()
+                .any(|ident| message.contains(&format!("Variable `{}` not found", ident)));
+            if !already_reported {
+                let message = format!("{} (with: {})", message, failing.join(", "));
+                report.push(Severity::Error, template, None, message);
+            }
+        }
+
+        for key in context_keys.iter().filter(|key| !used.contains(**key)) {
+            let message = format!("context key `{}` is never used", key);
+            report.push(Severity::Warning, template, None, message);
+        }
+    }
+
+    for partial in sources.keys() {
+        if partial.starts_with(PARTIALS_DIR) && !included.contains(partial) {
+            let message = "is never included".to_string();
+            report.push(Severity::Warning, partial, None, message);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Report `{% raw %}` blocks that are never closed, nested or closed twice
+fn check_raw_blocks(template: &str, source: &str, report: &mut LintReport) {
+    let mut open: Option<usize> = None;
+
+    for (start, _) in source.match_indices("{%") {
+        let Some(length) = source[start..].find("%}") else {
+            break;
+        };
+        let tag =
+            source[start + 2..start + length].trim_matches(|c: char| c == '-' || c.is_whitespace());
+        let line = source[..start].matches('\n').count() + 1;
+        match (tag, open) {
+            ("raw", Some(opened)) => report.push(
+                Severity::Error,
+                template,
+                Some(line),
+                format!(
+                    "`{{% raw %}}` inside the raw block opened on line {} (raw blocks can't be nested)",
+                    opened
+                ),
+            ),
+            ("raw", None) => open = Some(line),
+            ("endraw", Some(_)) => open = None,
+            ("endraw", None) => report.push(
+                Severity::Error,
+                template,
+                Some(line),
+                "`{% endraw %}` without a matching `{% raw %}`".to_string(),
+            ),
+            _ => {}
+        }
+    }
+
+    if let Some(line) = open {
+        report.push(
+            Severity::Error,
+            template,
+            Some(line),
+            "`{% raw %}` is never closed by an `{% endraw %}`".to_string(),
+        );
+    }
+}
+
+/// What a local variable (e.g. a loop variable) stands for: the path of the context value
+/// it aliases, or `None` when it can't be known statically
+type Scope = HashMap<String, Option<Vec<String>>>;
+
+/// Walks a template, following its includes, to find the variables it uses
+struct VariableChecker<'a> {
+    renderer: &'a TemplateRenderer,
+    contexts: &'a [Value],
+    report: &'a mut LintReport,
+    /// The top-level context keys used
+    used: BTreeSet<String>,
+    /// The variables already reported as undefined
+    undefined: BTreeSet<String>,
+    included: &'a mut BTreeSet<String>,
+    /// The templates being walked, to stop recursive includes
+    stack: Vec<String>,
+}
+
+impl VariableChecker<'_> {
+    fn nodes(&mut self, template: &str, nodes: &[Node], scope: &mut Scope) {
+        for node in nodes {
+            match node {
+                Node::VariableBlock(_, expr) => self.expr(template, expr, scope),
+                Node::Set(_, set) => {
+                    self.expr(template, &set.value, scope);
+                    let alias = match &set.value.val {
+                        ExprVal::Ident(ident) if set.value.filters.is_empty() => {
+                            self.path(ident, scope)
+                        }
+                        _ => None,
+                    };
+                    scope.insert(set.key.clone(), alias);
+                }
+                Node::Include(_, names, _) => {
+                    let Some((name, parsed)) = names
+                        .iter()
+                        .find_map(|name| Some((name, self.renderer.template(name)?)))
+                    else {
+                        continue;
+                    };
+                    self.included.insert(name.clone());
+                    if !self.stack.contains(name) {
+                        self.stack.push(name.clone());
+                        self.nodes(name, &parsed.ast, scope);
+                        self.stack.pop();
+                    }
+                }
+                Node::Forloop(_, forloop, _) => {
+                    self.expr(template, &forloop.container, scope);
+                    let mut inner = scope.clone();
+                    inner.insert("loop".to_string(), None);
+                    match &forloop.key {
+                        Some(key) => {
+                            inner.insert(key.clone(), None);
+                            inner.insert(forloop.value.clone(), None);
+                        }
+                        None => {
+                            let alias = match &forloop.container.val {
+                                ExprVal::Ident(ident) => self.path(ident, scope),
+                                _ => None,
+                            };
+                            inner.insert(forloop.value.clone(), alias);
+                        }
+                    }
+                    self.nodes(template, &forloop.body, &mut inner);
+                    if let Some(body) = &forloop.empty_body {
+                        self.nodes(template, body, &mut scope.clone());
+                    }
+                }
+                // Unlike loops, conditions and blocks share the scope of their parent
+                Node::If(condition, _) => {
+                    for (_, expr, body) in &condition.conditions {
+                        self.expr(template, expr, scope);
+                        self.nodes(template, body, scope);
+                    }
+                    if let Some((_, body)) = &condition.otherwise {
+                        self.nodes(template, body, scope);
+                    }
+                }
+                Node::FilterSection(_, section, _) => {
+                    for arg in section.filter.args.values() {
+                        self.expr(template, arg, scope);
+                    }
+                    self.nodes(template, &section.body, scope);
+                }
+                Node::Block(_, block, _) => self.nodes(template, &block.body, scope),
+                _ => {}
+            }
+        }
+    }
+
+    fn expr(&mut self, template: &str, expr: &Expr, scope: &Scope) {
+        // `default` is the way to use a variable that might be undefined
+        let optional = expr.filters.iter().any(|filter| filter.name == "default");
+
+        match &expr.val {
+            ExprVal::Ident(ident) => self.ident(template, ident, scope, optional),
+            ExprVal::Math(math) => {
+                self.expr(template, &math.lhs, scope);
+                self.expr(template, &math.rhs, scope);
+            }
+            ExprVal::Logic(logic) => {
+                self.expr(template, &logic.lhs, scope);
+                self.expr(template, &logic.rhs, scope);
+            }
+            ExprVal::In(in_expr) => {
+                self.expr(template, &in_expr.lhs, scope);
+                self.expr(template, &in_expr.rhs, scope);
+            }
+            ExprVal::Test(test) => {
+                let optional = matches!(test.name.as_str(), "defined" | "undefined");
+                self.ident(template, &test.ident, scope, optional);
+                for arg in &test.args {
+                    self.expr(template, arg, scope);
+                }
+            }
+            ExprVal::FunctionCall(call) => {
+                for arg in call.args.values() {
+                    self.expr(template, arg, scope);
+                }
+            }
+            ExprVal::MacroCall(call) => {
+                for arg in call.args.values() {
+                    self.expr(template, arg, scope);
+                }
+            }
+            ExprVal::Array(items) => {
+                for item in items {
+                    self.expr(template, item, scope);
+                }
+            }
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    if let ExprVal::Ident(ident) = value {
+                        self.ident(template, ident, scope, false);
+                    }
+                }
+            }
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+        }
+
+        for filter in &expr.filters {
+            for arg in filter.args.values() {
+                self.expr(template, arg, scope);
+            }
+        }
+    }
+
+    /// The path in the context of a variable (e.g. `link.title` in a loop over
+    /// `secondary_links` is `secondary_links.title`), `None` for unknown local variables
+    fn path(&self, ident: &str, scope: &Scope) -> Option<Vec<String>> {
+        let segments: Vec<String> = ident
+            .replace('[', ".")
+            .replace([']', '"', '\''], "")
+            .split('.')
+            .map(str::to_string)
+            .collect();
+        let (first, rest) = segments.split_first()?;
+        let mut path = match scope.get(first) {
+            Some(alias) => alias.clone()?,
+            None => vec![first.clone()],
+        };
+        path.extend(rest.iter().cloned());
+        Some(path)
+    }
+
+    fn ident(&mut self, template: &str, ident: &str, scope: &Scope, optional: bool) {
+        if ident.starts_with("__tera_context") {
+            return;
+        }
+        let Some(path) = self.path(ident, scope) else {
+            return;
+        };
+        self.used.insert(path[0].clone());
+
+        if optional || self.contexts.iter().any(|context| resolves(context, &path)) {
+            return;
+        }
+        if self.undefined.insert(ident.to_string()) {
+            let line = self.renderer.line_of(template, ident);
+            let message = format!("`{}` is not defined in any of the sample events", ident);
+            self.report.push(Severity::Error, template, line, message);
+        }
+    }
+}
+
+/// Whether a path exists in a value, looking at the first item of every array
+fn resolves(value: &Value, path: &[String]) -> bool {
+    let mut current = value;
+    for segment in path {
+        if let Value::Array(items) = current {
+            let index = segment.parse::<usize>().ok();
+            // Nothing to check in an empty list
+            let Some(item) = items.get(index.unwrap_or(0)) else {
+                return true;
+            };
+            current = item;
+            if index.is_some() {
+                continue;
+            }
+        }
+        current = match current.as_object().and_then(|object| object.get(segment)) {
+            Some(value) => value,
+            None => return false,
+        };
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    /// A directory of templates, unique to the test
+    fn templates_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("create-issue-lint-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (name, source) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    fn lint(test: &str, files: &[(&str, &str)]) -> LintReport {
+        let dir = templates_dir(test, files);
+        let report = lint_templates(Some(&dir)).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        report
+    }
+
+    #[test]
+    fn test_embedded_templates_pass() {
+        let report = lint_templates(None).unwrap();
+
+        assert_eq!(report.errors().count(), 0, "{:#?}", report.findings);
+        // Available to custom templates, but not used by the default one
+        assert_eq!(
+            report
+                .warnings()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["warning: newsletter.md: context key `issue_number` is never used"]
+        );
+    }
+
+    #[test]
+    fn test_reports_undefined_variables() {
+        let report = lint(
+            "undefined",
+            &[
+                (
+                    "partials/link.md",
+                    "[**{{ link.title }}**]({{ link.campaignUrls.titel }})\n",
+                ),
+                (
+                    "partials/sponsor_banner.md",
+                    "{% if sponsor.banner_htm %}{{ sponsor.banner_html }}{% endif %}\n",
+                ),
+            ],
+        );
+
+        let errors: Vec<String> = report.errors().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "error: partials/sponsor_banner.md:1: `sponsor.banner_htm` is not defined in any of the sample events",
+                "error: partials/link.md:1: `link.campaignUrls.titel` is not defined in any of the sample events",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_render_errors_once() {
+        let report = lint(
+            "render",
+            &[("weekly.md", "{{ greeting | truncate(length=\"five\") }}\n")],
+        );
+
+        let errors: Vec<&LintFinding> = report.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].template, "weekly.md");
+        assert!(errors[0].message.ends_with(
+            "(with: issue 435, no extra links, empty sponsor, missing authorUrl, no UK link, several sponsors)"
+        ));
+    }
+
+    #[test]
+    fn test_reports_unused_context_keys_and_partials() {
+        let report = lint(
+            "unused",
+            &[("short.md", "{{ greeting }}, {{ quote.text }}\n")],
+        );
+
+        let warnings: Vec<String> = report
+            .warnings()
+            .filter(|finding| finding.template == "short.md")
+            .map(|finding| finding.message.clone())
+            .collect();
+        assert!(warnings.contains(&"context key `book` is never used".to_string()));
+        assert!(!warnings.contains(&"context key `quote` is never used".to_string()));
+        assert!(!warnings.contains(&"context key `greeting` is never used".to_string()));
+        assert_eq!(report.errors().count(), 0);
+    }
+
+    #[test]
+    fn test_never_included_partials() {
+        let report = lint("partials", &[("partials/unused.md", "Unused\n")]);
+
+        assert!(
+            report
+                .warnings()
+                .any(|finding| finding.to_string()
+                    == "warning: partials/unused.md: is never included")
+        );
+    }
+
+    #[test]
+    fn test_reports_unbalanced_raw_blocks() {
+        let report = lint(
+            "raw",
+            &[
+                ("unclosed.md", "Hi\n{% raw %}{{ subscriber }}\n"),
+                (
+                    "nested.md",
+                    "{% raw %}\n{%- raw %}{% endraw %}\n{% endraw %}\n",
+                ),
+            ],
+        );
+
+        let errors: Vec<String> = report.errors().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "error: nested.md:2: `{% raw %}` inside the raw block opened on line 1 (raw blocks can't be nested)",
+                "error: nested.md:3: `{% endraw %}` without a matching `{% raw %}`",
+                "error: unclosed.md:2: `{% raw %}` is never closed by an `{% endraw %}`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_syntax_errors() {
+        let report = lint("syntax", &[("broken.md", "{{ greeting | }}\n")]);
+
+        let errors: Vec<&LintFinding> = report.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].template, "broken.md");
+    }
+
+    #[test]
+    fn test_edge_case_scenarios() {
+        let scenarios = scenarios();
+
+        assert_eq!(scenarios.len(), 6);
+        assert!(scenarios[1].event.data.links.len() <= 7);
+        assert!(scenarios[2].event.data.sponsor.banner_html.is_empty());
+        assert!(scenarios[3].event.data.quote.author_url.is_none());
+        assert!(scenarios[4].event.data.book.links.uk.is_empty());
+        assert_eq!(scenarios[5].event.data.sponsor.placements.len(), 2);
+    }
+}
@@ -1,14 +1,11 @@
-mod buttondown;
-mod datetime_utils;
-mod event_handler;
-mod model;
-mod template;
-
-use crate::{
-    buttondown::ButtonDownClient, event_handler::HandlerConfig, template::TemplateRenderer,
+use create_issue::{
+    buttondown::ButtonDownClient,
+    event_handler::{function_handler, HandlerConfig},
+    link_health::{LinkCheckOptions, LinkChecker},
+    template::TemplateRenderer,
 };
-use event_handler::function_handler;
 use lambda_runtime::{run, service_fn, tracing, Error};
+use shared::history::{FileIssueHistory, IssueHistory};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -28,13 +25,36 @@ async fn main() -> Result<(), Error> {
 
     let buttondown_client =
         ButtonDownClient::new(buttondown_api_key, reqwest_client, buttondown_base_url);
-    let template_renderer = TemplateRenderer::new().expect("Failed to create template renderer");
+    // Templates can be overridden without a rebuild by shipping them in a directory
+    // (e.g. a Lambda layer), the embedded ones are used for everything else
+    let template_renderer = match std::env::var("TEMPLATES_DIR") {
+        Ok(templates_dir) => TemplateRenderer::from_dir(templates_dir),
+        Err(_) => TemplateRenderer::new(),
+    }
+    .map_err(|e| format!("Failed to create template renderer: {:#}", e))?;
+
+    // Checking the links of the issue is opt-in, as it takes a few seconds
+    let link_checker = match std::env::var("CHECK_LINKS").as_deref() {
+        Ok("true") => Some(
+            LinkChecker::new(&LinkCheckOptions::default())
+                .map_err(|e| format!("Failed to create link checker: {}", e))?,
+        ),
+        _ => None,
+    };
+
+    // Recording the published issues is opt-in: the file has to be shared with the fetchers
+    // that read it (e.g. on an EFS file system mounted in every function)
+    let issue_history = std::env::var("ISSUE_HISTORY_FILE")
+        .ok()
+        .map(|path| Box::new(FileIssueHistory::new(path)) as Box<dyn IssueHistory + Send + Sync>);
 
     let handler_config = HandlerConfig {
-        buttondown_client,
+        publisher: buttondown_client,
         template_renderer,
         draft_subscriber_id,
         draft_recipient_email,
+        link_checker,
+        issue_history,
     };
 
     run(service_fn(|event| function_handler(event, &handler_config))).await
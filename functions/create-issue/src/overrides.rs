@@ -0,0 +1,360 @@
+//! Editorial overrides: the changes the editor asks for in the event config (pin, exclude,
+//! reorder, annotate and inject links), applied to the fetched links before the layout.
+
+use chrono::{DateTime, Datelike};
+use reqwest::Url;
+use serde::Serialize;
+
+use crate::model::{CampaignUrls, EditorialOverrides, InjectedLink, Link};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideAction {
+    Pin,
+    Exclude,
+    Reorder,
+    Annotate,
+    Inject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OverrideOutcome {
+    pub action: OverrideAction,
+    pub url: String,
+}
+
+/// What happened to every override, reported in the response
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OverridesReport {
+    pub applied: Vec<OverrideOutcome>,
+    /// Overrides of URLs that aren't among the links (or injected links that already are)
+    pub ignored: Vec<OverrideOutcome>,
+}
+
+impl OverridesReport {
+    fn record(&mut self, action: OverrideAction, url: &str, applied: bool) {
+        let outcome = OverrideOutcome {
+            action,
+            url: url.to_string(),
+        };
+        if applied {
+            self.applied.push(outcome);
+        } else {
+            self.ignored.push(outcome);
+        }
+    }
+}
+
+/// The `utm_campaign` of the links of an issue, e.g. `fullstackBulletin-34-2025` for an
+/// execution started during the 34th (ISO) week of 2025, like fetch-links does
+pub fn utm_campaign(reference_time: &str) -> Result<String, chrono::ParseError> {
+    let week = DateTime::parse_from_rfc3339(reference_time)?.iso_week();
+    Ok(format!("fullstackBulletin-{}-{}", week.week(), week.year()))
+}
+
+/// Add the tracking parameters of the newsletter to a URL
+pub(crate) fn campaign_url(url: &str, campaign: &str, content: &str) -> String {
+    let Ok(mut url) = Url::parse(url) else {
+        return url.to_string();
+    };
+    url.query_pairs_mut()
+        .append_pair("utm_source", "fullstackbulletin.com")
+        .append_pair("utm_medium", "newsletter")
+        .append_pair("utm_campaign", campaign)
+        .append_pair("utm_content", content);
+    url.into()
+}
+
+fn injected_link(injected: &InjectedLink, campaign: &str) -> Link {
+    Link {
+        title: injected.title.clone(),
+        url: injected.url.clone(),
+        description: injected.description.clone(),
+        image: injected.image.clone(),
+        score: 0,
+        original_image: injected.image.clone().unwrap_or_default(),
+        campaign_urls: CampaignUrls {
+            title: campaign_url(&injected.url, campaign, "title"),
+            image: campaign_url(&injected.url, campaign, "image"),
+            description: campaign_url(&injected.url, campaign, "description"),
+        },
+    }
+}
+
+/// URLs are compared ignoring a trailing slash, since fetch-links doesn't always keep it
+fn same_url(left: &str, right: &str) -> bool {
+    left.trim_end_matches('/') == right.trim_end_matches('/')
+}
+
+fn position(links: &[Link], url: &str) -> Option<usize> {
+    links.iter().position(|link| same_url(&link.url, url))
+}
+
+/// Apply the overrides in a fixed order: injected links first, so that they can be pinned
+/// or reordered like the others, then exclusions, annotations, reordering and finally the
+/// pinned primary link, which wins over the reordering
+pub fn apply_overrides(
+    mut links: Vec<Link>,
+    overrides: &EditorialOverrides,
+    campaign: &str,
+) -> (Vec<Link>, OverridesReport) {
+    let mut report = OverridesReport::default();
+
+    for injected in &overrides.inject {
+        let new = position(&links, &injected.url).is_none();
+        if new {
+            let index = injected.position.unwrap_or(links.len()).min(links.len());
+            links.insert(index, injected_link(injected, campaign));
+        }
+        report.record(OverrideAction::Inject, &injected.url, new);
+    }
+
+    for url in &overrides.exclude {
+        let found = position(&links, url).map(|index| links.remove(index));
+        report.record(OverrideAction::Exclude, url, found.is_some());
+    }
+
+    for (url, annotation) in &overrides.annotations {
+        let found = position(&links, url).map(|index| &mut links[index]);
+        let applied = found.is_some();
+        if let Some(link) = found {
+            if let Some(title) = &annotation.title {
+                link.title = title.clone();
+            }
+            if let Some(description) = &annotation.description {
+                link.description = description.clone();
+            }
+        }
+        report.record(OverrideAction::Annotate, url, applied);
+    }
+
+    let mut reordered = Vec::new();
+    for url in &overrides.order {
+        let found = position(&links, url).map(|index| links.remove(index));
+        report.record(OverrideAction::Reorder, url, found.is_some());
+        reordered.extend(found);
+    }
+    links.splice(0..0, reordered);
+
+    if let Some(url) = &overrides.primary {
+        let found = position(&links, url).map(|index| links.remove(index));
+        report.record(OverrideAction::Pin, url, found.is_some());
+        if let Some(link) = found {
+            links.insert(0, link);
+        }
+    }
+
+    (links, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::LinkAnnotation;
+    use std::collections::BTreeMap;
+
+    const CAMPAIGN: &str = "fullstackBulletin-34-2025";
+
+    fn link(url: &str) -> Link {
+        injected_link(
+            &InjectedLink {
+                url: url.to_string(),
+                title: format!("Title of {}", url),
+                description: format!("Description of {}", url),
+                image: None,
+                position: None,
+            },
+            CAMPAIGN,
+        )
+    }
+
+    fn links() -> Vec<Link> {
+        [
+            "https://a.example/",
+            "https://b.example",
+            "https://c.example",
+            "https://d.example",
+        ]
+        .into_iter()
+        .map(link)
+        .collect()
+    }
+
+    fn urls(links: &[Link]) -> Vec<&str> {
+        links.iter().map(|link| link.url.as_str()).collect()
+    }
+
+    #[test]
+    fn test_utm_campaign() {
+        assert_eq!(
+            utm_campaign("2025-08-22T17:00:00Z").unwrap(),
+            "fullstackBulletin-34-2025"
+        );
+        // The ISO year of the first days of January can be the previous one
+        assert_eq!(
+            utm_campaign("2027-01-01T17:00:00Z").unwrap(),
+            "fullstackBulletin-53-2026"
+        );
+        assert!(utm_campaign("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_no_overrides() {
+        let (result, report) = apply_overrides(links(), &EditorialOverrides::default(), CAMPAIGN);
+
+        assert_eq!(result, links());
+        assert_eq!(report, OverridesReport::default());
+    }
+
+    #[test]
+    fn test_pin_exclude_and_reorder() {
+        let overrides = EditorialOverrides {
+            primary: Some("https://c.example/".to_string()),
+            exclude: vec!["https://b.example".to_string()],
+            order: vec![
+                "https://d.example".to_string(),
+                "https://c.example".to_string(),
+            ],
+            ..EditorialOverrides::default()
+        };
+
+        let (result, report) = apply_overrides(links(), &overrides, CAMPAIGN);
+
+        assert_eq!(
+            urls(&result),
+            vec![
+                "https://c.example",
+                "https://d.example",
+                "https://a.example/"
+            ]
+        );
+        assert_eq!(
+            report
+                .applied
+                .iter()
+                .map(|outcome| outcome.action)
+                .collect::<Vec<_>>(),
+            vec![
+                OverrideAction::Exclude,
+                OverrideAction::Reorder,
+                OverrideAction::Reorder,
+                OverrideAction::Pin
+            ]
+        );
+        assert!(report.ignored.is_empty());
+    }
+
+    #[test]
+    fn test_annotate() {
+        let overrides = EditorialOverrides {
+            annotations: BTreeMap::from([
+                (
+                    "https://a.example".to_string(),
+                    LinkAnnotation {
+                        title: Some("A much better title".to_string()),
+                        description: None,
+                    },
+                ),
+                (
+                    "https://z.example".to_string(),
+                    LinkAnnotation {
+                        title: None,
+                        description: Some("Not in the issue".to_string()),
+                    },
+                ),
+            ]),
+            ..EditorialOverrides::default()
+        };
+
+        let (result, report) = apply_overrides(links(), &overrides, CAMPAIGN);
+
+        assert_eq!(result[0].title, "A much better title");
+        assert_eq!(result[0].description, "Description of https://a.example/");
+        assert_eq!(
+            report.applied,
+            vec![OverrideOutcome {
+                action: OverrideAction::Annotate,
+                url: "https://a.example".to_string()
+            }]
+        );
+        assert_eq!(
+            report.ignored,
+            vec![OverrideOutcome {
+                action: OverrideAction::Annotate,
+                url: "https://z.example".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_inject() {
+        let overrides = EditorialOverrides {
+            inject: vec![
+                InjectedLink {
+                    url: "https://new.example/post".to_string(),
+                    title: "Hand-picked".to_string(),
+                    description: "Something the editor loved".to_string(),
+                    image: None,
+                    position: Some(1),
+                },
+                InjectedLink {
+                    url: "https://last.example".to_string(),
+                    title: "At the end".to_string(),
+                    description: String::new(),
+                    image: None,
+                    position: None,
+                },
+                InjectedLink {
+                    url: "https://a.example".to_string(),
+                    title: "Already there".to_string(),
+                    description: String::new(),
+                    image: None,
+                    position: Some(0),
+                },
+            ],
+            ..EditorialOverrides::default()
+        };
+
+        let (result, report) = apply_overrides(links(), &overrides, CAMPAIGN);
+
+        assert_eq!(
+            urls(&result),
+            vec![
+                "https://a.example/",
+                "https://new.example/post",
+                "https://b.example",
+                "https://c.example",
+                "https://d.example",
+                "https://last.example"
+            ]
+        );
+        assert_eq!(result[1].title, "Hand-picked");
+        assert_eq!(
+            result[1].campaign_urls.title,
+            "https://new.example/post?utm_source=fullstackbulletin.com&utm_medium=newsletter&utm_campaign=fullstackBulletin-34-2025&utm_content=title"
+        );
+        assert_eq!(report.applied.len(), 2);
+        assert_eq!(report.ignored[0].url, "https://a.example");
+    }
+
+    #[test]
+    fn test_injected_links_can_be_pinned() {
+        let overrides = EditorialOverrides {
+            primary: Some("https://new.example".to_string()),
+            inject: vec![InjectedLink {
+                url: "https://new.example".to_string(),
+                title: "Hand-picked".to_string(),
+                description: String::new(),
+                image: Some("https://new.example/cover.png".to_string()),
+                position: None,
+            }],
+            ..EditorialOverrides::default()
+        };
+
+        let (result, _) = apply_overrides(links(), &overrides, CAMPAIGN);
+
+        assert_eq!(result[0].url, "https://new.example");
+        assert_eq!(result[0].original_image, "https://new.example/cover.png");
+        assert_eq!(result.len(), 5);
+    }
+}
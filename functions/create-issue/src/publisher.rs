@@ -0,0 +1,562 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::{future::Future, sync::Mutex};
+
+use crate::buttondown::ButtonDownError;
+use crate::model::ExistingEmailMode;
+
+/// An email as seen by the newsletter provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishedEmail {
+    pub id: String,
+    pub status: String,
+}
+
+/// Whether the email of an issue goes out on its publish date or waits for the editor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Delivery {
+    #[default]
+    Scheduled,
+    Draft,
+}
+
+impl Delivery {
+    /// The status of the email at the newsletter provider
+    pub fn status(self) -> &'static str {
+        match self {
+            Delivery::Scheduled => "scheduled",
+            Delivery::Draft => "draft",
+        }
+    }
+}
+
+/// A newsletter provider that can schedule an issue and send test drafts of it
+pub trait NewsletterPublisher {
+    /// Create an email for `publish_date` (RFC 3339), scheduled or kept as a draft
+    fn create_issue_email(
+        &self,
+        subject: String,
+        body: String,
+        publish_date: String,
+        issue_number: u32,
+        first_link_title: &str,
+        delivery: Delivery,
+    ) -> impl Future<Output = Result<PublishedEmail>> + Send;
+
+    /// Look for an email that was already created for the given issue
+    fn find_existing_email(
+        &self,
+        issue_number: u32,
+        first_link_title: &str,
+    ) -> impl Future<Output = Result<Option<PublishedEmail>>> + Send;
+
+    /// Replace the content and publish date of an existing email, scheduling it or moving
+    /// it back to draft
+    fn update_issue_email(
+        &self,
+        email_id: &str,
+        subject: String,
+        body: String,
+        publish_date: String,
+        delivery: Delivery,
+    ) -> impl Future<Output = Result<PublishedEmail>> + Send;
+
+    /// Move a scheduled email back to draft, leaving its content as it is
+    fn unschedule_issue_email(
+        &self,
+        email_id: &str,
+    ) -> impl Future<Output = Result<PublishedEmail>> + Send;
+
+    /// Send a test version of an existing email to the given subscribers and email addresses,
+    /// with the review notes (if any) on top. The notes never end up in the email itself.
+    fn send_test_draft(
+        &self,
+        email_id: &str,
+        subscriber_ids: &[String],
+        recipient_emails: &[String],
+        review_notes: &[String],
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Retrieve the current status of an email (e.g. `scheduled`, `draft`, `sent`)
+    fn fetch_status(&self, email_id: &str) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// What [`publish_issue_email`] did to get an email for the issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishAction {
+    Created,
+    Updated,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishOutcome {
+    pub email: PublishedEmail,
+    pub action: PublishAction,
+}
+
+/// Create the email of an issue, unless one already exists, in which case `on_existing`
+/// decides whether it gets updated, left alone or treated as an error.
+/// This makes re-running the state machine for the same issue safe. In every case, an
+/// issue that has to stay a draft never leaves a scheduled email behind.
+#[allow(clippy::too_many_arguments)]
+pub async fn publish_issue_email<P: NewsletterPublisher>(
+    publisher: &P,
+    subject: String,
+    body: String,
+    publish_date: String,
+    issue_number: u32,
+    first_link_title: &str,
+    delivery: Delivery,
+    on_existing: ExistingEmailMode,
+) -> Result<PublishOutcome> {
+    let Some(existing) = publisher
+        .find_existing_email(issue_number, first_link_title)
+        .await?
+    else {
+        let email = publisher
+            .create_issue_email(
+                subject,
+                body,
+                publish_date,
+                issue_number,
+                first_link_title,
+                delivery,
+            )
+            .await?;
+        return Ok(PublishOutcome {
+            email,
+            action: PublishAction::Created,
+        });
+    };
+
+    // The update below moves the email back to draft itself
+    let existing = if delivery == Delivery::Draft
+        && existing.status == "scheduled"
+        && on_existing != ExistingEmailMode::Update
+    {
+        publisher.unschedule_issue_email(&existing.id).await?
+    } else {
+        existing
+    };
+
+    match on_existing {
+        ExistingEmailMode::Skip => Ok(PublishOutcome {
+            email: existing,
+            action: PublishAction::Skipped,
+        }),
+        ExistingEmailMode::Fail => bail!(
+            "An email already exists for issue #{} (id: {}, status: {})",
+            issue_number,
+            existing.id,
+            existing.status
+        ),
+        ExistingEmailMode::Update if existing.status == "sent" => bail!(
+            "The email of issue #{} (id: {}) was already sent and can't be updated",
+            issue_number,
+            existing.id
+        ),
+        ExistingEmailMode::Update => {
+            let email = publisher
+                .update_issue_email(&existing.id, subject, body, publish_date, delivery)
+                .await?;
+            Ok(PublishOutcome {
+                email,
+                action: PublishAction::Updated,
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublisherCall {
+    CreateIssueEmail {
+        subject: String,
+        body: String,
+        publish_date: String,
+        issue_number: u32,
+        first_link_title: String,
+        delivery: Delivery,
+    },
+    FindExistingEmail {
+        issue_number: u32,
+        first_link_title: String,
+    },
+    UpdateIssueEmail {
+        email_id: String,
+        subject: String,
+        body: String,
+        publish_date: String,
+        delivery: Delivery,
+    },
+    UnscheduleIssueEmail {
+        email_id: String,
+    },
+    SendTestDraft {
+        email_id: String,
+        subscriber_ids: Vec<String>,
+        recipient_emails: Vec<String>,
+        review_notes: Vec<String>,
+    },
+    FetchStatus {
+        email_id: String,
+    },
+}
+
+/// An in-memory publisher that records every call, useful to test the handler
+/// without talking to a real provider
+#[derive(Debug, Default)]
+pub struct RecordingPublisher {
+    calls: Mutex<Vec<PublisherCall>>,
+    emails: Mutex<Vec<(u32, PublishedEmail)>>,
+    /// How many of the next test drafts fail with a ButtonDown server error
+    failing_test_drafts: Mutex<u32>,
+}
+
+impl RecordingPublisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All the calls received so far, in order
+    pub fn calls(&self) -> Vec<PublisherCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// A publisher that already holds an email for the given issue, as if a previous run created it
+    pub fn with_existing_email(issue_number: u32, status: &str) -> Self {
+        let publisher = Self::new();
+        publisher.store(issue_number, status);
+        publisher
+    }
+
+    /// Fail the next `count` test drafts, as ButtonDown does when it has an outage
+    pub fn fail_test_drafts(&self, count: u32) {
+        *self.failing_test_drafts.lock().unwrap() = count;
+    }
+
+    /// All the emails created so far, in order
+    pub fn emails(&self) -> Vec<PublishedEmail> {
+        self.emails
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, email)| email.clone())
+            .collect()
+    }
+
+    fn store(&self, issue_number: u32, status: &str) -> PublishedEmail {
+        let mut emails = self.emails.lock().unwrap();
+        let email = PublishedEmail {
+            id: format!("email-{}", emails.len() + 1),
+            status: status.to_string(),
+        };
+        emails.push((issue_number, email.clone()));
+        email
+    }
+
+    fn record(&self, call: PublisherCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl NewsletterPublisher for RecordingPublisher {
+    async fn create_issue_email(
+        &self,
+        subject: String,
+        body: String,
+        publish_date: String,
+        issue_number: u32,
+        first_link_title: &str,
+        delivery: Delivery,
+    ) -> Result<PublishedEmail> {
+        self.record(PublisherCall::CreateIssueEmail {
+            subject,
+            body,
+            publish_date,
+            issue_number,
+            first_link_title: first_link_title.to_string(),
+            delivery,
+        });
+        Ok(self.store(issue_number, delivery.status()))
+    }
+
+    async fn find_existing_email(
+        &self,
+        issue_number: u32,
+        first_link_title: &str,
+    ) -> Result<Option<PublishedEmail>> {
+        self.record(PublisherCall::FindExistingEmail {
+            issue_number,
+            first_link_title: first_link_title.to_string(),
+        });
+        Ok(self
+            .emails
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(number, _)| *number == issue_number)
+            .map(|(_, email)| email.clone()))
+    }
+
+    async fn update_issue_email(
+        &self,
+        email_id: &str,
+        subject: String,
+        body: String,
+        publish_date: String,
+        delivery: Delivery,
+    ) -> Result<PublishedEmail> {
+        self.record(PublisherCall::UpdateIssueEmail {
+            email_id: email_id.to_string(),
+            subject,
+            body,
+            publish_date,
+            delivery,
+        });
+        self.emails
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|(_, email)| email.id == email_id)
+            .map(|(_, email)| {
+                email.status = delivery.status().to_string();
+                email.clone()
+            })
+            .ok_or_else(|| anyhow!("Email {} not found", email_id))
+    }
+
+    async fn unschedule_issue_email(&self, email_id: &str) -> Result<PublishedEmail> {
+        self.record(PublisherCall::UnscheduleIssueEmail {
+            email_id: email_id.to_string(),
+        });
+        self.emails
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|(_, email)| email.id == email_id)
+            .map(|(_, email)| {
+                email.status = Delivery::Draft.status().to_string();
+                email.clone()
+            })
+            .ok_or_else(|| anyhow!("Email {} not found", email_id))
+    }
+
+    async fn send_test_draft(
+        &self,
+        email_id: &str,
+        subscriber_ids: &[String],
+        recipient_emails: &[String],
+        review_notes: &[String],
+    ) -> Result<()> {
+        self.record(PublisherCall::SendTestDraft {
+            email_id: email_id.to_string(),
+            subscriber_ids: subscriber_ids.to_vec(),
+            recipient_emails: recipient_emails.to_vec(),
+            review_notes: review_notes.to_vec(),
+        });
+        let mut failing = self.failing_test_drafts.lock().unwrap();
+        if *failing > 0 {
+            *failing -= 1;
+            return Err(ButtonDownError::Server {
+                status: 503,
+                message: "Service Unavailable".to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn fetch_status(&self, email_id: &str) -> Result<String> {
+        self.record(PublisherCall::FetchStatus {
+            email_id: email_id.to_string(),
+        });
+
+        self.emails
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, email)| email.id == email_id)
+            .map(|(_, email)| email.status.clone())
+            .ok_or_else(|| anyhow!("Email {} not found", email_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_recording_publisher_assigns_ids_and_tracks_status() {
+        let publisher = RecordingPublisher::new();
+
+        let email = publisher
+            .create_issue_email(
+                "Subject".to_string(),
+                "Body".to_string(),
+                "2025-08-25T17:00:00+00:00".to_string(),
+                435,
+                "First link",
+                Delivery::Scheduled,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(email.id, "email-1");
+        assert_eq!(
+            publisher.fetch_status("email-1").await.unwrap(),
+            "scheduled"
+        );
+        assert!(publisher.fetch_status("email-2").await.is_err());
+        assert_eq!(publisher.calls().len(), 3);
+    }
+
+    async fn publish(
+        publisher: &RecordingPublisher,
+        on_existing: ExistingEmailMode,
+    ) -> Result<PublishOutcome> {
+        publish_with(publisher, Delivery::Scheduled, on_existing).await
+    }
+
+    async fn publish_with(
+        publisher: &RecordingPublisher,
+        delivery: Delivery,
+        on_existing: ExistingEmailMode,
+    ) -> Result<PublishOutcome> {
+        publish_issue_email(
+            publisher,
+            "Subject".to_string(),
+            "Body".to_string(),
+            "2025-08-25T17:00:00+00:00".to_string(),
+            435,
+            "First link",
+            delivery,
+            on_existing,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_publish_creates_missing_email() {
+        let publisher = RecordingPublisher::new();
+
+        let outcome = publish(&publisher, ExistingEmailMode::Fail).await.unwrap();
+
+        assert_eq!(outcome.action, PublishAction::Created);
+        assert_eq!(outcome.email.id, "email-1");
+        assert_eq!(publisher.emails().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_publish_skips_existing_email() {
+        let publisher = RecordingPublisher::with_existing_email(435, "scheduled");
+
+        let outcome = publish(&publisher, ExistingEmailMode::Skip).await.unwrap();
+
+        assert_eq!(outcome.action, PublishAction::Skipped);
+        assert_eq!(outcome.email.id, "email-1");
+        assert_eq!(publisher.emails().len(), 1);
+        assert_eq!(publisher.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_publish_updates_existing_email() {
+        let publisher = RecordingPublisher::with_existing_email(435, "scheduled");
+
+        let outcome = publish(&publisher, ExistingEmailMode::Update)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.action, PublishAction::Updated);
+        assert_eq!(
+            publisher.calls()[1],
+            PublisherCall::UpdateIssueEmail {
+                email_id: "email-1".to_string(),
+                subject: "Subject".to_string(),
+                body: "Body".to_string(),
+                publish_date: "2025-08-25T17:00:00+00:00".to_string(),
+                delivery: Delivery::Scheduled,
+            }
+        );
+        assert_eq!(publisher.emails().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_publish_as_draft() {
+        let publisher = RecordingPublisher::new();
+
+        let created = publish_with(&publisher, Delivery::Draft, ExistingEmailMode::Fail)
+            .await
+            .unwrap();
+        assert_eq!(created.email.status, "draft");
+
+        // Updating a scheduled email as a draft unschedules it
+        let publisher = RecordingPublisher::with_existing_email(435, "scheduled");
+        let updated = publish_with(&publisher, Delivery::Draft, ExistingEmailMode::Update)
+            .await
+            .unwrap();
+        assert_eq!(updated.email.status, "draft");
+        assert_eq!(publisher.emails()[0].status, "draft");
+    }
+
+    #[tokio::test]
+    async fn test_publish_as_draft_unschedules_existing_email() {
+        for on_existing in [ExistingEmailMode::Skip, ExistingEmailMode::Fail] {
+            let publisher = RecordingPublisher::with_existing_email(435, "scheduled");
+
+            let outcome = publish_with(&publisher, Delivery::Draft, on_existing).await;
+
+            assert_eq!(outcome.is_ok(), on_existing == ExistingEmailMode::Skip);
+            assert_eq!(
+                publisher.calls()[1],
+                PublisherCall::UnscheduleIssueEmail {
+                    email_id: "email-1".to_string()
+                }
+            );
+            assert_eq!(publisher.emails()[0].status, "draft");
+        }
+
+        // A sent email is past saving, and a draft one needs nothing
+        for status in ["sent", "draft"] {
+            let publisher = RecordingPublisher::with_existing_email(435, status);
+            publish_with(&publisher, Delivery::Draft, ExistingEmailMode::Skip)
+                .await
+                .unwrap();
+            assert_eq!(publisher.calls().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_fails_on_existing_email() {
+        let publisher = RecordingPublisher::with_existing_email(435, "scheduled");
+
+        let error = publish(&publisher, ExistingEmailMode::Fail)
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("issue #435"));
+        assert_eq!(publisher.emails().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_publish_never_updates_sent_email() {
+        let publisher = RecordingPublisher::with_existing_email(435, "sent");
+
+        assert!(publish(&publisher, ExistingEmailMode::Update)
+            .await
+            .is_err());
+        assert_eq!(publisher.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_publish_ignores_other_issues() {
+        let publisher = RecordingPublisher::with_existing_email(434, "sent");
+
+        let outcome = publish(&publisher, ExistingEmailMode::Fail).await.unwrap();
+
+        assert_eq!(outcome.action, PublishAction::Created);
+        assert_eq!(outcome.email.id, "email-2");
+    }
+}
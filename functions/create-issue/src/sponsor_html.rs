@@ -0,0 +1,657 @@
+//! The sponsor blocks are HTML pasted in Airtable by the customers, and end up in the email
+//! of every subscriber. They're cleaned against an allowlist of email-safe tags and
+//! attributes before being rendered, and everything that the cleaning changes is reported.
+//! Their links then get the tracking parameters of the newsletter, like the other links of
+//! the issue, so that the clicks of every sponsor can be told apart in the analytics.
+
+use ammonia::Builder;
+use reqwest::Url;
+use serde::Serialize;
+use shared::history::SponsorLink;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::model::{Sponsor, SponsorSlot};
+use crate::overrides::campaign_url;
+
+const ALLOWED_TAGS: [&str; 37] = [
+    "a",
+    "abbr",
+    "b",
+    "big",
+    "blockquote",
+    "br",
+    "center",
+    "code",
+    "div",
+    "em",
+    "font",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+    "wbr",
+];
+
+/// Layout attributes that email clients understand, allowed on every tag
+const GENERIC_ATTRIBUTES: [&str; 12] = [
+    "align",
+    "bgcolor",
+    "border",
+    "cellpadding",
+    "cellspacing",
+    "color",
+    "dir",
+    "height",
+    "style",
+    "title",
+    "valign",
+    "width",
+];
+
+const TAG_ATTRIBUTES: [(&str, &[&str]); 4] = [
+    ("a", &["href", "target"]),
+    ("img", &["src", "alt"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan"]),
+];
+
+const URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Every link gets it, replacing the `rel` of the customer
+const LINK_REL: &str = "noopener noreferrer";
+
+/// Elements without a closing tag
+const VOID_TAGS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Something the cleaning of a sponsor block changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SponsorHtmlWarning {
+    pub customer: String,
+    /// `top-banner`, `sponsored-article`, `mid-banner` or `classified`
+    pub block: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for SponsorHtmlWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {}: {}", self.block, self.customer, self.message)
+    }
+}
+
+/// A tag of the HTML, as written
+#[derive(Debug)]
+struct Tag {
+    name: String,
+    closing: bool,
+    attributes: Vec<(String, String)>,
+    /// Where the tag is in the HTML
+    start: usize,
+    end: usize,
+}
+
+impl Tag {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// An image of at most 1×1 pixels, or a hidden one
+    fn is_tracking_pixel(&self) -> bool {
+        let tiny = |name: &str| {
+            self.attribute(name).is_some_and(|value| {
+                matches!(value.trim().trim_end_matches("px").trim(), "0" | "1")
+            })
+        };
+        let style = self
+            .attribute("style")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .replace(' ', "");
+        self.name == "img"
+            && !self.closing
+            && ((tiny("width") && tiny("height"))
+                || style.contains("display:none")
+                || (style.contains("width:1px") && style.contains("height:1px")))
+    }
+}
+
+/// The tags of the HTML, skipping comments, doctypes and the text in between
+fn tags(html: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = html[position..].find('<') {
+        let start = position + offset;
+        let rest = &html[start..];
+        if rest.starts_with("<!--") {
+            position = rest.find("-->").map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            position = rest.find('>').map_or(html.len(), |end| start + end + 1);
+            continue;
+        }
+        let (closing, name_start) = match rest.strip_prefix("</") {
+            Some(_) => (true, 2),
+            None => (false, 1),
+        };
+        let name_length = rest[name_start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len() - name_start);
+        if name_length == 0 {
+            // A `<` in the text
+            position = start + 1;
+            continue;
+        }
+        let name = rest[name_start..name_start + name_length].to_ascii_lowercase();
+        let (attributes, length) = attributes(&rest[name_start + name_length..]);
+        let end = start + name_start + name_length + length;
+        tags.push(Tag {
+            name,
+            closing,
+            attributes,
+            start,
+            end,
+        });
+        position = end;
+    }
+    tags
+}
+
+/// The attributes of a tag up to its `>`, and the length of the text they take
+fn attributes(text: &str) -> (Vec<(String, String)>, usize) {
+    let mut attributes = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c == '>' {
+            return (attributes, index + 1);
+        }
+        if c.is_whitespace() || c == '/' {
+            continue;
+        }
+        let mut name = c.to_string();
+        while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && !"=>/".contains(*c)) {
+            name.push(c);
+        }
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if(|(_, c)| *c == '=').is_some() {
+            while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            match chars.next_if(|(_, c)| *c == '"' || *c == '\'') {
+                Some((_, quote)) => {
+                    for (_, c) in chars.by_ref() {
+                        if c == quote {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                }
+                None => {
+                    while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && *c != '>')
+                    {
+                        value.push(c);
+                    }
+                }
+            }
+        }
+        attributes.push((name.to_ascii_lowercase(), value));
+    }
+    (attributes, text.len())
+}
+
+/// Splits a `style` attribute between the declarations that are kept and the ones that are
+/// removed: backgrounds and other `url(...)`s load remote content (i.e. tracking beacons),
+/// and hidden content is something the reader can't see but the email still carries
+fn split_style(style: &str) -> (Vec<&str>, Vec<&str>) {
+    style
+        .split(';')
+        .map(str::trim)
+        .filter(|declaration| !declaration.is_empty())
+        .partition(|declaration| {
+            let normalized = declaration
+                .to_ascii_lowercase()
+                .replace(char::is_whitespace, "");
+            !(normalized.contains("url(")
+                || normalized.starts_with("display:none")
+                || normalized.starts_with("visibility:hidden"))
+        })
+}
+
+fn is_allowed_attribute(tag: &str, attribute: &str) -> bool {
+    GENERIC_ATTRIBUTES.contains(&attribute)
+        || TAG_ATTRIBUTES
+            .iter()
+            .any(|(name, attributes)| *name == tag && attributes.contains(&attribute))
+}
+
+/// What the cleaning is going to change, and where the tracking pixels are
+fn inspect(tags: &[Tag]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut warn = |message: String| {
+        if !warnings.contains(&message) {
+            warnings.push(message);
+        }
+    };
+    let mut open: Vec<&str> = Vec::new();
+
+    for tag in tags {
+        if tag.closing {
+            match open.iter().rposition(|name| *name == tag.name) {
+                Some(index) => {
+                    for unclosed in open.drain(index..).skip(1).collect::<Vec<_>>() {
+                        warn(format!("`<{}>` is never closed", unclosed));
+                    }
+                }
+                None => warn(format!("`</{}>` closes nothing", tag.name)),
+            }
+            continue;
+        }
+        if !VOID_TAGS.contains(&tag.name.as_str()) {
+            open.push(&tag.name);
+        }
+
+        if !ALLOWED_TAGS.contains(&tag.name.as_str()) {
+            warn(format!("`<{}>` was removed", tag.name));
+            continue;
+        }
+        if tag.is_tracking_pixel() {
+            warn(format!(
+                "the tracking pixel {} was removed",
+                tag.attribute("src").unwrap_or_default()
+            ));
+            continue;
+        }
+        for (attribute, value) in &tag.attributes {
+            if attribute == "rel" && tag.name == "a" {
+                continue;
+            }
+            if !is_allowed_attribute(&tag.name, attribute) {
+                warn(format!(
+                    "the `{}` attribute of `<{}>` was removed",
+                    attribute, tag.name
+                ));
+            } else if attribute == "style" {
+                for declaration in split_style(value).1 {
+                    warn(format!(
+                        "the style `{}` of `<{}>` was removed",
+                        declaration, tag.name
+                    ));
+                }
+            } else if matches!(attribute.as_str(), "href" | "src") {
+                let scheme = value.split_once(':').map(|(scheme, _)| scheme.trim());
+                if scheme.is_some_and(|scheme| {
+                    !scheme.contains('/')
+                        && !URL_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str())
+                }) {
+                    warn(format!("the URL {} was removed", value));
+                }
+            }
+        }
+        if tag.name == "a"
+            && tag.attribute("href").is_some()
+            && !tag
+                .attribute("rel")
+                .is_some_and(|rel| rel.split_whitespace().any(|rel| rel == "noopener"))
+        {
+            warn(format!("`rel=\"{}\"` was added to the links", LINK_REL));
+        }
+    }
+    for unclosed in open {
+        warn(format!("`<{}>` is never closed", unclosed));
+    }
+    warnings
+}
+
+/// The email-safe allowlist
+fn allowlist() -> Builder<'static> {
+    let tag_attributes = TAG_ATTRIBUTES
+        .iter()
+        .map(|(tag, attributes)| (*tag, attributes.iter().copied().collect()))
+        .collect();
+    let mut builder = Builder::empty();
+    builder
+        .tags(HashSet::from(ALLOWED_TAGS))
+        .generic_attributes(HashSet::from(GENERIC_ATTRIBUTES))
+        .tag_attributes(tag_attributes)
+        .url_schemes(HashSet::from(URL_SCHEMES))
+        .link_rel(Some(LINK_REL));
+    builder
+}
+
+/// Clean a block of HTML, returning the clean HTML and what the cleaning changed
+pub fn sanitize_html(html: &str) -> (String, Vec<String>) {
+    if html.trim().is_empty() {
+        return (html.to_string(), Vec::new());
+    }
+    let tags = tags(html);
+    let warnings = inspect(&tags);
+
+    // ammonia can't drop an element because of its attributes, so the tracking pixels are
+    // cut out of the HTML beforehand
+    let mut without_pixels = String::with_capacity(html.len());
+    let mut position = 0;
+    for tag in tags.iter().filter(|tag| tag.is_tracking_pixel()) {
+        without_pixels.push_str(&html[position..tag.start]);
+        position = tag.end;
+    }
+    without_pixels.push_str(&html[position..]);
+
+    let clean = allowlist()
+        .attribute_filter(|_, attribute, value| {
+            if attribute != "style" {
+                return Some(value.into());
+            }
+            let kept = split_style(value).0;
+            (!kept.is_empty()).then(|| kept.join("; ").into())
+        })
+        .clean(&without_pixels)
+        .to_string();
+    (clean, warnings)
+}
+
+/// Add the tracking parameters of the newsletter to the web links of a block of HTML,
+/// returning the HTML and the links, as they were and as they are. The links that already
+/// have tracking parameters (the ones of the sponsor) are kept as they are.
+pub fn track_links(html: &str, campaign: &str, content: &str) -> (String, Vec<(String, String)>) {
+    if html.trim().is_empty() {
+        return (html.to_string(), Vec::new());
+    }
+    let links = Arc::new(Mutex::new(Vec::new()));
+    let found = Arc::clone(&links);
+    let (campaign, content) = (campaign.to_string(), content.to_string());
+    let tracked = allowlist()
+        .attribute_filter(move |element, attribute, value| {
+            let url = Url::parse(value)
+                .ok()
+                .filter(|url| matches!(url.scheme(), "http" | "https"));
+            let Some(url) = url.filter(|_| (element, attribute) == ("a", "href")) else {
+                return Some(value.into());
+            };
+            let tracked = if url.query_pairs().any(|(key, _)| key.starts_with("utm_")) {
+                value.to_string()
+            } else {
+                campaign_url(value, &campaign, &content)
+            };
+            found
+                .lock()
+                .unwrap()
+                .push((value.to_string(), tracked.clone()));
+            Some(tracked.into())
+        })
+        .clean(html)
+        .to_string();
+    let links = std::mem::take(&mut *links.lock().unwrap());
+    (tracked, links)
+}
+
+/// Apply `f` to the HTML of every block of the sponsors of an issue, with the customer and
+/// the name of the block
+fn map_blocks(sponsor: &Sponsor, mut f: impl FnMut(&str, &str, &'static str) -> String) -> Sponsor {
+    let mut mapped = sponsor.clone();
+    mapped.banner_html = f(&sponsor.banner_html, &sponsor.customer, "top-banner");
+    mapped.sponsored_article_html = f(
+        &sponsor.sponsored_article_html,
+        &sponsor.customer,
+        "sponsored-article",
+    );
+    for placement in &mut mapped.placements {
+        let block = match placement.slot {
+            SponsorSlot::MidBanner => "mid-banner",
+            SponsorSlot::Classified => "classified",
+        };
+        placement.html = f(&placement.html, &placement.customer, block);
+    }
+    mapped
+}
+
+/// Clean every block of the sponsors of an issue
+pub fn sanitize_sponsor(sponsor: &Sponsor) -> (Sponsor, Vec<SponsorHtmlWarning>) {
+    let mut warnings = Vec::new();
+    let clean = map_blocks(sponsor, |html, customer, block| {
+        let (clean, messages) = sanitize_html(html);
+        warnings.extend(messages.into_iter().map(|message| SponsorHtmlWarning {
+            customer: customer.to_string(),
+            block,
+            message,
+        }));
+        clean
+    });
+    (clean, warnings)
+}
+
+/// Track the links of every block of the sponsors of an issue, with the name of the block as
+/// `utm_content`
+pub fn track_sponsor(sponsor: &Sponsor, campaign: &str) -> (Sponsor, Vec<SponsorLink>) {
+    let mut sponsor_links = Vec::new();
+    let tracked = map_blocks(sponsor, |html, customer, block| {
+        let (tracked, links) = track_links(html, campaign, block);
+        sponsor_links.extend(links.into_iter().map(|(url, tracked_url)| SponsorLink {
+            customer: customer.to_string(),
+            block: block.to_string(),
+            url,
+            tracked_url,
+        }));
+        tracked
+    });
+    (tracked, sponsor_links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::SponsorPlacement;
+
+    #[test]
+    fn test_clean_html_is_kept() {
+        let html = r#"<p><a href="https://acme.example" rel="noopener noreferrer">ACME</a> makes <strong>rockets</strong></p>"#;
+
+        let (clean, warnings) = sanitize_html(html);
+
+        assert_eq!(clean, html);
+        assert_eq!(warnings, Vec::<String>::new());
+        assert_eq!(sanitize_html(""), (String::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_unsafe_html_is_removed() {
+        let html = concat!(
+            r#"<div onclick="steal()"><script>alert(1)</script>"#,
+            r#"<a href="javascript:alert(1)">Click</a> "#,
+            r#"<iframe src="https://tracker.example"></iframe>"#,
+            r#"<img src="https://tracker.example/p.gif" width="1" height="1">"#,
+            r#"<img src="https://acme.example/logo.png" alt="ACME"></div>"#,
+        );
+
+        let (clean, warnings) = sanitize_html(html);
+
+        assert_eq!(
+            clean,
+            concat!(
+                r#"<div><a rel="noopener noreferrer">Click</a> "#,
+                r#"<img src="https://acme.example/logo.png" alt="ACME"></div>"#
+            )
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "the `onclick` attribute of `<div>` was removed",
+                "`<script>` was removed",
+                "the URL javascript:alert(1) was removed",
+                "`rel=\"noopener noreferrer\"` was added to the links",
+                "`<iframe>` was removed",
+                "the tracking pixel https://tracker.example/p.gif was removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hidden_and_remote_styles_are_removed() {
+        let html = concat!(
+            r#"<table><tr><td style="background: URL('https://tracker.example/b.gif'); color: red">"#,
+            r#"Rockets</td></tr></table>"#,
+            r#"<div style="display : none">Hidden</div>"#,
+            r#"<span style="visibility:hidden;">Hidden</span>"#,
+        );
+
+        let (clean, warnings) = sanitize_html(html);
+
+        assert_eq!(
+            clean,
+            concat!(
+                r#"<table><tbody><tr><td style="color: red">Rockets</td></tr></tbody></table>"#,
+                r#"<div>Hidden</div><span>Hidden</span>"#,
+            )
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "the style `background: URL('https://tracker.example/b.gif')` of `<td>` was removed",
+                "the style `display : none` of `<div>` was removed",
+                "the style `visibility:hidden` of `<span>` was removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_markup() {
+        let (clean, warnings) =
+            sanitize_html(r#"<table><tr><td><b>Bold</td></tr></table></span><p>Open"#);
+
+        assert_eq!(
+            warnings,
+            vec![
+                "`<b>` is never closed",
+                "`</span>` closes nothing",
+                "`<p>` is never closed",
+            ]
+        );
+        assert!(clean.ends_with("<p>Open</p>"));
+    }
+
+    #[test]
+    fn test_track_links() {
+        let html = concat!(
+            r#"<a href="https://acme.example/rockets?plan=pro" rel="noopener noreferrer">ACME</a> "#,
+            r#"<a href="https://acme.example/?utm_source=acme" rel="noopener noreferrer">Own</a> "#,
+            r#"<a href="mailto:sales@acme.example" rel="noopener noreferrer">Mail</a> "#,
+            r#"<img src="https://acme.example/logo.png">"#,
+        );
+
+        let (tracked, links) = track_links(html, "fullstackBulletin-435", "top-banner");
+
+        let tracked_url = "https://acme.example/rockets?plan=pro&utm_source=fullstackbulletin.com\
+                           &utm_medium=newsletter&utm_campaign=fullstackBulletin-435\
+                           &utm_content=top-banner";
+        assert_eq!(
+            links,
+            vec![
+                (
+                    "https://acme.example/rockets?plan=pro".to_string(),
+                    tracked_url.to_string()
+                ),
+                (
+                    "https://acme.example/?utm_source=acme".to_string(),
+                    "https://acme.example/?utm_source=acme".to_string()
+                ),
+            ]
+        );
+        assert!(tracked.starts_with(&format!(
+            r#"<a href="{}" rel="noopener noreferrer">ACME</a>"#,
+            tracked_url.replace('&', "&amp;")
+        )));
+        assert!(tracked.contains(r#"href="mailto:sales@acme.example""#));
+        assert!(tracked.contains(r#"<img src="https://acme.example/logo.png">"#));
+        assert_eq!(
+            track_links("", "fullstackBulletin-435", "top-banner").1,
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_sanitize_sponsor() {
+        let sponsor = Sponsor {
+            banner_html: r#"<a href="https://acme.example">ACME</a>"#.to_string(),
+            sponsored_article_html: "<p>Rockets!</p>".to_string(),
+            customer: "ACME".to_string(),
+            placements: vec![SponsorPlacement {
+                slot: SponsorSlot::Classified,
+                customer: "Initech".to_string(),
+                html: "<marquee>Hiring</marquee>".to_string(),
+            }],
+            bookings: None,
+        };
+
+        let (clean, warnings) = sanitize_sponsor(&sponsor);
+
+        assert_eq!(
+            clean.banner_html,
+            r#"<a href="https://acme.example" rel="noopener noreferrer">ACME</a>"#
+        );
+        assert_eq!(clean.sponsored_article_html, "<p>Rockets!</p>");
+        assert_eq!(clean.placements[0].html, "Hiring");
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "top-banner of ACME: `rel=\"noopener noreferrer\"` was added to the links",
+                "classified of Initech: `<marquee>` was removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_track_sponsor() {
+        let sponsor = Sponsor {
+            banner_html: r#"<a href="https://acme.example">ACME</a>"#.to_string(),
+            sponsored_article_html: String::new(),
+            customer: "ACME".to_string(),
+            placements: vec![SponsorPlacement {
+                slot: SponsorSlot::MidBanner,
+                customer: "Globex".to_string(),
+                html: r#"<a href="https://globex.example">Globex</a>"#.to_string(),
+            }],
+            bookings: None,
+        };
+
+        let (tracked, links) = track_sponsor(&sponsor, "fullstackBulletin-435");
+
+        assert_eq!(
+            links
+                .iter()
+                .map(|link| (link.customer.as_str(), link.block.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("ACME", "top-banner"), ("Globex", "mid-banner")]
+        );
+        assert!(links[1].tracked_url.ends_with("&utm_content=mid-banner"));
+        assert!(tracked.placements[0]
+            .html
+            .contains("utm_content=mid-banner"));
+        assert_eq!(tracked.sponsored_article_html, "");
+    }
+}
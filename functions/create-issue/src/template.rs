@@ -1,8 +1,16 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context as _, Result};
 use serde::Serialize;
-use tera::{Context, Tera};
-
-use crate::model::{Book, Link, Quote, Sponsor};
+use std::{
+    collections::BTreeMap,
+    error::Error as _,
+    fs,
+    path::{Path, PathBuf},
+};
+use tera::{ast::Node, Context, Tera};
+
+use crate::formats::RenderedNewsletter;
+use crate::layout::{LinkKind, LinkSections};
+use crate::model::{Book, Link, Quote, Sponsor, SponsorPlacement, SponsorSlot};
 
 /// Enhanced link with action text for template rendering
 #[derive(Serialize, Debug)]
@@ -10,17 +18,23 @@ pub struct EnhancedLink<'a> {
     #[serde(flatten)]
     pub link: &'a Link,
     pub action_text: &'static str,
+    pub kind: LinkKind,
+}
+
+impl<'a> EnhancedLink<'a> {
+    pub fn new(link: &'a Link) -> Self {
+        let kind = LinkKind::from_url(&link.url);
+        Self {
+            link,
+            action_text: kind.action_text(),
+            kind,
+        }
+    }
 }
 
 /// Generate appropriate action text based on the URL
 pub fn get_link_action_text(url: &str) -> &'static str {
-    if url.contains("github.com") {
-        "Check Repo"
-    } else if url.contains("youtube.com") || url.contains("youtu.be") {
-        "Watch Video"
-    } else {
-        "Read Article"
-    }
+    LinkKind::from_url(url).action_text()
 }
 
 pub fn generate_extra_content_title(issue_number: u32) -> String {
@@ -134,78 +148,419 @@ pub fn generate_intro_closing(issue_number: u32) -> String {
     .to_string()
 }
 
-// Embed the newsletter template at compile time
-const NEWSLETTER_TEMPLATE: &str = include_str!("../templates/newsletter.md");
+/// Generate the intro of an issue from its content, unless the editor writes one.
+/// The phrasing rotates with the issue number, like the greeting.
+pub fn generate_intro(
+    issue_number: u32,
+    links: &LinkSections,
+    book: &Book,
+    sponsor: Option<&Sponsor>,
+) -> String {
+    let primary = &links.primary.title;
+    let book = &book.title;
+    let count = 1 + links.secondary.len() + links.extra.len();
+    let links = match count {
+        1 => "1 link".to_string(),
+        count => format!("{} links", count),
+    };
+
+    let mut intro = match issue_number % 5 {
+        1 => format!(
+            "We picked {} for you this week, starting with **{}**. The book of the week is **{}**.",
+            links, primary, book
+        ),
+        2 => format!(
+            "First up, **{}**: it leads a selection of {}. And when you need a break from the screen, **{}** is our book of the week.",
+            primary, links, book
+        ),
+        3 => format!(
+            "This week's favourite among {} is **{}**. Don't miss **{}**, our book of the week.",
+            links, primary, book
+        ),
+        4 => format!(
+            "Grab a coffee: this issue has {}, headlined by **{}**, and **{}** as the book of the week.",
+            links, primary, book
+        ),
+        _ => format!(
+            "This issue opens with **{}** and comes with {} in total. Our book of the week is **{}**.",
+            primary, links, book
+        ),
+    };
+
+    if let Some(sponsor) = sponsor.filter(|sponsor| !sponsor.customer.trim().is_empty()) {
+        let thanks = match issue_number % 3 {
+            1 => format!(
+                "A big thank you to **{}** for sponsoring this issue!",
+                sponsor.customer
+            ),
+            2 => format!(
+                "Thanks to **{}**, our sponsor this week, for supporting FullStack Bulletin!",
+                sponsor.customer
+            ),
+            _ => format!(
+                "This issue is made possible by **{}**, check out their message below!",
+                sponsor.customer
+            ),
+        };
+        intro.push(' ');
+        intro.push_str(&thanks);
+    }
 
-pub struct TemplateRenderer;
+    intro
+}
+
+/// What the default template used to say before intros were generated. Custom templates
+/// may still carry it: an issue with the placeholder is never scheduled.
+pub const INTRO_PLACEHOLDER: &str = "TODO: WRITE INTRO";
+
+/// Everything an issue is rendered from
+#[derive(Debug, Clone, Copy)]
+pub struct NewsletterData<'a> {
+    pub issue_number: u32,
+    pub quote: &'a Quote,
+    pub book: &'a Book,
+    pub links: &'a LinkSections<'a>,
+    pub extra_content_title: &'a str,
+    pub sponsor: Option<&'a Sponsor>,
+    /// The intro written by the editor, if any
+    pub intro: Option<&'a str>,
+}
+
+/// The template rendered when the event doesn't select one
+pub const DEFAULT_TEMPLATE: &str = "newsletter.md";
+
+/// Templates in this directory can only be included by other templates, not selected
+pub(crate) const PARTIALS_DIR: &str = "partials/";
+
+/// The default template set, embedded at compile time so that the renderer works
+/// without any file on disk
+const EMBEDDED_TEMPLATES: [(&str, &str); 8] = [
+    ("newsletter.md", include_str!("../templates/newsletter.md")),
+    (
+        "partials/book.md",
+        include_str!("../templates/partials/book.md"),
+    ),
+    (
+        "partials/classifieds.md",
+        include_str!("../templates/partials/classifieds.md"),
+    ),
+    (
+        "partials/link.md",
+        include_str!("../templates/partials/link.md"),
+    ),
+    (
+        "partials/quote.md",
+        include_str!("../templates/partials/quote.md"),
+    ),
+    (
+        "partials/sponsor_banner.md",
+        include_str!("../templates/partials/sponsor_banner.md"),
+    ),
+    (
+        "partials/sponsor_mid_banner.md",
+        include_str!("../templates/partials/sponsor_mid_banner.md"),
+    ),
+    (
+        "partials/sponsored_article.md",
+        include_str!("../templates/partials/sponsored_article.md"),
+    ),
+];
+
+/// A set of newsletter templates, parsed and validated once when the renderer is created
+pub struct TemplateRenderer {
+    tera: Tera,
+    /// The source of every template, used to point errors to a line
+    sources: BTreeMap<String, String>,
+}
 
 impl TemplateRenderer {
+    /// A renderer using the embedded templates
     pub fn new() -> Result<Self> {
-        Ok(Self)
+        Self::from_sources(load_sources(None)?)
     }
 
-    pub fn render_newsletter(
-        &self,
-        issue_number: u32,
-        quote: &Quote,
-        book: &Book,
-        primary_link: &Link,
-        secondary_links: &[&Link],
-        extra_links: &[&Link],
-        extra_content_title: &str,
-        sponsor: Option<&Sponsor>,
-    ) -> Result<String> {
-        let mut context = Context::new();
+    /// A renderer using the `.md` templates found in `dir` (and its subdirectories),
+    /// falling back to the embedded ones for every template the directory doesn't override
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::from_sources(load_sources(Some(dir.as_ref()))?)
+    }
 
-        context.insert("issue_number", &issue_number);
-        context.insert("quote", quote);
-        context.insert("book", book);
+    pub(crate) fn from_sources(sources: BTreeMap<String, String>) -> Result<Self> {
+        let mut tera = Tera::default();
+        // autoescape is disabled since we're rendering Markdown, not HTML
+        tera.autoescape_on(vec![]);
+        // Editors add a final newline to every file: dropping it from the partials lets them
+        // be included on a line of their own without adding an empty line
+        let templates = sources.iter().map(|(name, source)| {
+            let source = if name.starts_with(PARTIALS_DIR) {
+                source
+                    .strip_suffix('\n')
+                    .map(|source| source.strip_suffix('\r').unwrap_or(source))
+                    .unwrap_or(source)
+            } else {
+                source
+            };
+            (name.as_str(), source)
+        });
+        tera.add_raw_templates(templates)
+            .map_err(|error| anyhow!(describe_error(&error, &sources)))?;
+
+        let renderer = Self { tera, sources };
+        renderer.check_includes()?;
+        Ok(renderer)
+    }
 
-        // Create enhanced primary link with action text
-        let enhanced_primary_link = EnhancedLink {
-            link: primary_link,
-            action_text: get_link_action_text(&primary_link.url),
-        };
-        context.insert("primary_link", &enhanced_primary_link);
+    /// Tera only notices a missing include when rendering: fail as early as a syntax error
+    fn check_includes(&self) -> Result<()> {
+        for (name, template) in &self.tera.templates {
+            let mut includes = Vec::new();
+            collect_includes(&template.ast, &mut includes);
+            for included in includes {
+                if !self.tera.templates.contains_key(included) {
+                    bail!(
+                        "Template '{}' includes '{}', which doesn't exist{}",
+                        name,
+                        included,
+                        self.line_of(name, &format!("\"{}\"", included))
+                            .map(|line| format!(" (line {})", line))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The line (1-based) of the first occurrence of `needle` in a template
+    pub(crate) fn line_of(&self, template: &str, needle: &str) -> Option<usize> {
+        line_of(self.sources.get(template)?, needle)
+    }
+
+    /// The templates that can be selected for a run (i.e. all but the partials)
+    pub fn templates(&self) -> Vec<&str> {
+        self.sources
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !name.starts_with(PARTIALS_DIR))
+            .collect()
+    }
 
-        // Create enhanced secondary links with action text
-        let enhanced_secondary_links: Vec<EnhancedLink> = secondary_links
+    /// The parsed template with the given name, including partials
+    pub(crate) fn template(&self, name: &str) -> Option<&tera::Template> {
+        self.tera.templates.get(name)
+    }
+
+    /// Find a selectable template by name, with or without its `.md` extension
+    fn resolve(&self, name: &str) -> Result<&str> {
+        let templates = self.templates();
+        templates
             .iter()
-            .map(|link| EnhancedLink {
-                link,
-                action_text: get_link_action_text(&link.url),
+            .find(|template| **template == name || template.strip_suffix(".md") == Some(name))
+            .copied()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown template '{}' (available: {})",
+                    name,
+                    templates.join(", ")
+                )
             })
-            .collect();
-        context.insert("secondary_links", &enhanced_secondary_links);
+    }
 
-        if !extra_links.is_empty() {
-            context.insert("extra_links", extra_links);
-            context.insert("extra_content_title", extra_content_title);
-        }
+    pub fn render_newsletter(
+        &self,
+        template: Option<&str>,
+        data: &NewsletterData,
+    ) -> Result<RenderedNewsletter> {
+        let template = self.resolve(template.unwrap_or(DEFAULT_TEMPLATE))?;
+        let context = newsletter_context(data);
+
+        let rendered = self
+            .tera
+            .render(template, &context)
+            .map_err(|error| anyhow!(describe_error(&error, &self.sources)))?;
+        Ok(RenderedNewsletter::from_markdown(rendered))
+    }
+}
 
-        if let Some(sponsor) = sponsor {
-            context.insert("sponsor", sponsor);
+/// The variables available to the newsletter templates
+pub(crate) fn newsletter_context(data: &NewsletterData) -> Context {
+    let NewsletterData {
+        issue_number,
+        quote,
+        book,
+        links,
+        extra_content_title,
+        sponsor,
+        intro,
+    } = *data;
+    let mut context = Context::new();
+
+    context.insert("issue_number", &issue_number);
+    context.insert("quote", quote);
+    context.insert("book", book);
+
+    // Create enhanced links with action text
+    context.insert("primary_link", &EnhancedLink::new(links.primary));
+    let secondary_links: Vec<EnhancedLink> = links
+        .secondary
+        .iter()
+        .map(|link| EnhancedLink::new(link))
+        .collect();
+    context.insert("secondary_links", &secondary_links);
+
+    if !links.extra.is_empty() {
+        context.insert("extra_links", &links.extra);
+        context.insert("extra_content_title", extra_content_title);
+    }
+
+    if let Some(sponsor) = sponsor {
+        context.insert("sponsor", sponsor);
+
+        // The mid banner goes halfway through the secondary links (0 is right after the
+        // primary link)
+        let placements = |slot| {
+            sponsor
+                .placements
+                .iter()
+                .filter(move |placement: &&SponsorPlacement| placement.slot == slot)
+        };
+        if let Some(mid_banner) = placements(SponsorSlot::MidBanner).next() {
+            context.insert("mid_banner", mid_banner);
+            context.insert("mid_banner_after", &(links.secondary.len() / 2));
+        }
+        let classifieds: Vec<&SponsorPlacement> = placements(SponsorSlot::Classified).collect();
+        if !classifieds.is_empty() {
+            context.insert("classifieds", &classifieds);
         }
+    }
+
+    // Add greeting variable
+    let greeting = generate_greeting(issue_number);
+    context.insert("greeting", &greeting);
 
-        // Add greeting variable
-        let greeting = generate_greeting(issue_number);
-        context.insert("greeting", &greeting);
+    // The editor's intro wins over the generated one
+    let intro = match intro.filter(|intro| !intro.trim().is_empty()) {
+        Some(intro) => intro.to_string(),
+        None => generate_intro(issue_number, links, book, sponsor),
+    };
+    context.insert("intro", &intro);
 
-        // Add intro closing variable
-        let intro_closing = generate_intro_closing(issue_number);
-        context.insert("intro_closing", &intro_closing);
+    // Add intro closing variable
+    let intro_closing = generate_intro_closing(issue_number);
+    context.insert("intro_closing", &intro_closing);
 
-        // Add closing variables
-        let closing_title = generate_closing_title(issue_number);
-        let closing_message = generate_closing_message(issue_number);
-        context.insert("closing_title", &closing_title);
-        context.insert("closing_message", &closing_message);
+    // Add closing variables
+    let closing_title = generate_closing_title(issue_number);
+    let closing_message = generate_closing_message(issue_number);
+    context.insert("closing_title", &closing_title);
+    context.insert("closing_message", &closing_message);
+
+    context
+}
 
-        // Use Tera's one-off rendering function with embedded template
-        // autoescape=false since we're rendering Markdown, not HTML
-        let rendered = Tera::one_off(NEWSLETTER_TEMPLATE, &context, false)?;
-        Ok(rendered)
+/// The source of every template: the embedded ones, overridden by the `.md` files in `dir`
+pub(crate) fn load_sources(dir: Option<&Path>) -> Result<BTreeMap<String, String>> {
+    let mut sources: BTreeMap<String, String> = EMBEDDED_TEMPLATES
+        .iter()
+        .map(|(name, source)| (name.to_string(), source.to_string()))
+        .collect();
+    let Some(dir) = dir else {
+        return Ok(sources);
+    };
+
+    for path in template_files(dir)? {
+        let name = path
+            .strip_prefix(dir)?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read template {}", path.display()))?;
+        sources.insert(name, source);
     }
+    Ok(sources)
+}
+
+/// All the `.md` files in a directory, recursively
+fn template_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read templates directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(template_files(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "md") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+pub(crate) fn collect_includes<'a>(nodes: &'a [Node], includes: &mut Vec<&'a str>) {
+    for node in nodes {
+        match node {
+            // With `ignore missing` a missing template is fine, otherwise any of the
+            // alternatives is enough
+            Node::Include(_, names, false) => {
+                if let Some(name) = names.first() {
+                    includes.push(name);
+                }
+            }
+            Node::Block(_, block, _) => collect_includes(&block.body, includes),
+            Node::FilterSection(_, section, _) => collect_includes(&section.body, includes),
+            Node::Forloop(_, forloop, _) => {
+                collect_includes(&forloop.body, includes);
+                if let Some(body) = &forloop.empty_body {
+                    collect_includes(body, includes);
+                }
+            }
+            Node::If(condition, _) => {
+                for (_, _, body) in &condition.conditions {
+                    collect_includes(body, includes);
+                }
+                if let Some((_, body)) = &condition.otherwise {
+                    collect_includes(body, includes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn line_of(source: &str, needle: &str) -> Option<usize> {
+    source
+        .lines()
+        .position(|line| line.contains(needle))
+        .map(|index| index + 1)
+}
+
+/// Flatten a Tera error and its causes into a single message.
+///
+/// Syntax errors already point to a line; render errors (e.g. "Variable `quote.txt` not
+/// found in context while rendering 'partials/quote.md'") only name the template, so the
+/// line is looked up in its source.
+fn describe_error(error: &tera::Error, sources: &BTreeMap<String, String>) -> String {
+    let mut messages = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(cause) = source {
+        messages.push(cause.to_string());
+        source = cause.source();
+    }
+
+    let innermost = messages.last().cloned().unwrap_or_default();
+    let location = innermost
+        .split("while rendering '")
+        .nth(1)
+        .and_then(|rest| rest.split('\'').next())
+        .and_then(|template| {
+            let needle = innermost.split('`').nth(1)?;
+            let line = line_of(sources.get(template)?, needle)?;
+            Some(format!(" ({}, line {})", template, line))
+        });
+
+    format!("{}{}", messages.join(": "), location.unwrap_or_default())
 }
 
 #[cfg(test)]
@@ -229,6 +584,7 @@ mod tests {
             links: BookLinks {
                 us: "https://www.amazon.com/dp/1492034029?tag=loige0e-20".to_string(),
                 uk: "https://www.amazon.co.uk/dp/1492034029?tag=loige-21".to_string(),
+                ..Default::default()
             },
             cover_picture: "https://fullStackbulletin.github.io/fullstack-books/covers/building-microservices-2-sam-newman.jpg".to_string(),
             description: "As organizations shift from monolithic applications to smaller, self-contained microservices...".to_string(),
@@ -284,6 +640,8 @@ mod tests {
             banner_html: "<!-- Sponsor banner HTML -->".to_string(),
             sponsored_article_html: "<!-- Sponsored article HTML -->".to_string(),
             customer: "Example Sponsor".to_string(),
+            placements: Vec::new(),
+            bookings: None,
         };
 
         (
@@ -370,6 +728,54 @@ mod tests {
         assert_eq!(intro41, "Happy reading and coding!"); // Issue 41 (0 modulo - default)
     }
 
+    #[test]
+    fn test_intro_generation() {
+        let (_, book, primary_link, secondary_links, extra_links, mut sponsor) =
+            create_sample_data();
+        let links = LinkSections {
+            primary: &primary_link,
+            secondary: secondary_links.iter().collect(),
+            extra: extra_links.iter().collect(),
+            dropped: Vec::new(),
+        };
+        let count = 1 + secondary_links.len() + extra_links.len();
+
+        let intro = generate_intro(435, &links, &book, Some(&sponsor));
+        assert!(intro.starts_with(&format!(
+            "This issue opens with **{}** and comes with {} links in total.",
+            primary_link.title, count
+        )));
+        assert!(intro.contains(&format!("**{}**", book.title)));
+        assert!(intro.contains(&sponsor.customer));
+
+        // The phrasing rotates, and an empty sponsor isn't thanked
+        sponsor.customer.clear();
+        let other = generate_intro(436, &links, &book, Some(&sponsor));
+        assert_ne!(intro, other);
+        assert_eq!(other, generate_intro(436, &links, &book, None));
+
+        let single = LinkSections {
+            primary: &primary_link,
+            secondary: Vec::new(),
+            extra: Vec::new(),
+            dropped: Vec::new(),
+        };
+        assert!(generate_intro(436, &single, &book, None).contains("We picked 1 link for you"));
+    }
+
+    #[test]
+    fn test_intro_from_config_wins() {
+        let renderer = TemplateRenderer::new().unwrap();
+
+        let generated = render(&renderer, None).unwrap();
+        let written = render_with_intro(&renderer, None, Some("Written by hand.")).unwrap();
+        let blank = render_with_intro(&renderer, None, Some("  ")).unwrap();
+
+        assert!(!generated.markdown.contains(INTRO_PLACEHOLDER));
+        assert!(written.markdown.contains("\n\nWritten by hand.\n\n"));
+        assert_eq!(blank, generated);
+    }
+
     #[test]
     fn test_extra_content_title_generation() {
         let title1 = generate_extra_content_title(1);
@@ -559,24 +965,37 @@ Book: {{ book.title }}
         }
 
         let result = renderer.render_newsletter(
-            435,
-            &quote,
-            &book,
-            &primary_link,
-            &secondary_link_refs,
-            &extra_link_refs,
-            "You have to BELIEVE in the power of more content! 🙏",
-            Some(&sponsor),
+            None,
+            &NewsletterData {
+                issue_number: 435,
+                quote: &quote,
+                book: &book,
+                links: &LinkSections {
+                    primary: &primary_link,
+                    secondary: secondary_link_refs,
+                    extra: extra_link_refs,
+                    dropped: Vec::new(),
+                },
+                extra_content_title: "You have to BELIEVE in the power of more content! 🙏",
+                sponsor: Some(&sponsor),
+                intro: None,
+            },
         );
 
         match result {
             Ok(rendered) => {
                 println!("Template rendered successfully!");
                 // Basic checks to ensure template was rendered
-                assert!(rendered.contains("Pablo Picasso"));
-                assert!(rendered.contains("Building Microservices"));
-                assert!(rendered.contains("An Interactive Guide to SVG Paths"));
-                assert!(rendered.contains("{{ subscriber.metadata.first_name }}"));
+                assert!(rendered.markdown.contains("Pablo Picasso"));
+                assert!(rendered.markdown.contains("Building Microservices"));
+                assert!(rendered
+                    .markdown
+                    .contains("An Interactive Guide to SVG Paths"));
+                assert!(rendered
+                    .markdown
+                    .contains("{{ subscriber.metadata.first_name }}"));
+                assert!(rendered.html.contains("Building Microservices"));
+                assert!(rendered.text.contains("Pablo Picasso"));
             }
             Err(e) => {
                 println!("Template rendering failed: {}", e);
@@ -584,4 +1003,215 @@ Book: {{ book.title }}
             }
         }
     }
+
+    #[test]
+    fn test_sponsor_slots() {
+        let renderer = TemplateRenderer::new().unwrap();
+        let (quote, book, primary_link, secondary_links, extra_links, mut sponsor) =
+            create_sample_data();
+        sponsor.placements = vec![
+            SponsorPlacement {
+                slot: SponsorSlot::Classified,
+                customer: "Initech".to_string(),
+                html: "Initech is hiring".to_string(),
+            },
+            SponsorPlacement {
+                slot: SponsorSlot::MidBanner,
+                customer: "ACME".to_string(),
+                html: "<a>ACME banner</a>".to_string(),
+            },
+            SponsorPlacement {
+                slot: SponsorSlot::Classified,
+                customer: "Globex".to_string(),
+                html: "Globex ships".to_string(),
+            },
+        ];
+        let links = LinkSections {
+            primary: &primary_link,
+            secondary: secondary_links.iter().collect(),
+            extra: extra_links.iter().collect(),
+            dropped: Vec::new(),
+        };
+
+        let markdown = renderer
+            .render_newsletter(
+                None,
+                &NewsletterData {
+                    issue_number: 435,
+                    quote: &quote,
+                    book: &book,
+                    links: &links,
+                    extra_content_title: "Extra content",
+                    sponsor: Some(&sponsor),
+                    intro: None,
+                },
+            )
+            .unwrap()
+            .markdown;
+
+        // With a single secondary link, the mid banner goes right before it
+        let position = |text: &str| markdown.find(text).unwrap();
+        assert_eq!(secondary_links.len(), 1);
+        assert!(position(&format!("[**{}**]", primary_link.title)) < position("ACME banner"));
+        assert!(position("ACME banner") < position(&secondary_links[0].title));
+        assert!(markdown.contains("### Classifieds\n\n- Initech is hiring\n- Globex ships\n"));
+        assert!(position("<!-- Sponsored article HTML -->") < position("### Classifieds"));
+    }
+
+    /// A directory of templates, unique to the test
+    fn templates_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "create-issue-templates-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        for (name, source) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    fn render(renderer: &TemplateRenderer, template: Option<&str>) -> Result<RenderedNewsletter> {
+        render_with_intro(renderer, template, None)
+    }
+
+    fn render_with_intro(
+        renderer: &TemplateRenderer,
+        template: Option<&str>,
+        intro: Option<&str>,
+    ) -> Result<RenderedNewsletter> {
+        let (quote, book, primary_link, secondary_links, extra_links, sponsor) =
+            create_sample_data();
+        renderer.render_newsletter(
+            template,
+            &NewsletterData {
+                issue_number: 435,
+                quote: &quote,
+                book: &book,
+                links: &LinkSections {
+                    primary: &primary_link,
+                    secondary: secondary_links.iter().collect(),
+                    extra: extra_links.iter().collect(),
+                    dropped: Vec::new(),
+                },
+                extra_content_title: "Extra content",
+                sponsor: Some(&sponsor),
+                intro,
+            },
+        )
+    }
+
+    #[test]
+    fn test_embedded_templates() {
+        let renderer = TemplateRenderer::new().unwrap();
+
+        assert_eq!(renderer.templates(), vec!["newsletter.md"]);
+        let rendered = render(&renderer, Some("newsletter")).unwrap();
+        assert!(rendered.markdown.contains(
+            "> —[Pablo Picasso](https://en.wikipedia.org/wiki/Pablo_Picasso), Artist\n\n---"
+        ));
+        assert!(rendered.markdown.contains(
+            "[**Closer to the Metal: Leaving Playwright for CDP**](https://browser-use.com/posts/playwright-to-cdp?utm_source=fullstackbulletin.com&utm_medium=newsletter&utm_campaign=fullstackBulletin-34-2025&utm_content=title) — Let's switch gears... but not completely... [**Read Article**]"
+        ));
+    }
+
+    #[test]
+    fn test_templates_dir_overrides_embedded_templates() {
+        let dir = templates_dir(
+            "overrides",
+            &[
+                ("partials/quote.md", "QUOTE: {{ quote.author }}\n"),
+                (
+                    "short.md",
+                    "{{ greeting }}\n\n{% include \"partials/quote.md\" %}\n",
+                ),
+                ("README.txt", "Not a template"),
+            ],
+        );
+
+        let renderer = TemplateRenderer::from_dir(&dir).unwrap();
+
+        assert_eq!(renderer.templates(), vec!["newsletter.md", "short.md"]);
+        let newsletter = render(&renderer, None).unwrap();
+        assert!(newsletter
+            .markdown
+            .contains("---\n\nQUOTE: Pablo Picasso\n\n---"));
+        assert!(newsletter.markdown.contains("# 📕 Book of the week!"));
+        let short = render(&renderer, Some("short.md")).unwrap();
+        assert_eq!(short.markdown, "Good day\n\nQUOTE: Pablo Picasso\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_template() {
+        let renderer = TemplateRenderer::new().unwrap();
+
+        let error = render(&renderer, Some("weekly")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Unknown template 'weekly' (available: newsletter.md)"
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors_name_template_and_line() {
+        let dir = templates_dir(
+            "syntax",
+            &[("broken.md", "Hello\n\n{{ greeting | }}\nBye\n")],
+        );
+
+        let error = TemplateRenderer::from_dir(&dir).err().unwrap().to_string();
+
+        assert!(
+            error.starts_with("Failed to parse 'broken.md'"),
+            "{}",
+            error
+        );
+        assert!(error.contains("--> 3:"), "{}", error);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_includes_fail_at_startup() {
+        let dir = templates_dir(
+            "includes",
+            &[(
+                "partials/book.md",
+                "Book\n{% include \"partials/cover.md\" %}\n",
+            )],
+        );
+
+        let error = TemplateRenderer::from_dir(&dir).err().unwrap().to_string();
+
+        assert_eq!(
+            error,
+            "Template 'partials/book.md' includes 'partials/cover.md', which doesn't exist (line 2)"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_errors_name_template_and_line() {
+        let dir = templates_dir(
+            "render",
+            &[(
+                "partials/quote.md",
+                "> {{ quote.text }}\n> {{ quote.name }}\n",
+            )],
+        );
+        let renderer = TemplateRenderer::from_dir(&dir).unwrap();
+
+        let error = render(&renderer, None).unwrap_err().to_string();
+
+        assert!(
+            error.ends_with("Variable `quote.name` not found in context while rendering 'partials/quote.md' (partials/quote.md, line 2)"),
+            "{}",
+            error
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,573 @@
+//! Checks of an issue right before it's published: a set of named rules run on the data and
+//! on the rendered markdown. Errors keep the email as a draft, warnings are only reported
+//! (in the response and in the test draft).
+
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::link_health::{LinkHealth, UrlCheck};
+use crate::model::Link;
+use crate::sponsor_html::SponsorHtmlWarning;
+use crate::template::{NewsletterData, INTRO_PLACEHOLDER};
+
+/// Longer subjects get truncated by most email clients
+pub const MAX_SUBJECT_LENGTH: usize = 150;
+
+/// What the rules check
+pub struct IssueContent<'a> {
+    pub subject: &'a str,
+    pub data: &'a NewsletterData<'a>,
+    pub markdown: &'a str,
+    /// The results of the link checker, empty when the links weren't checked
+    pub link_health: &'a [UrlCheck],
+    /// What the cleaning of the sponsor HTML changed
+    pub sponsor_html: &'a [SponsorHtmlWarning],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationFinding {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.rule)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub errors: Vec<ValidationFinding>,
+    pub warnings: Vec<ValidationFinding>,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+
+    /// One line per finding, errors first, for the editor reviewing the test draft
+    pub fn review_notes(&self) -> Vec<String> {
+        let errors = self
+            .errors
+            .iter()
+            .map(|finding| format!("Error: {}", finding));
+        let warnings = self
+            .warnings
+            .iter()
+            .map(|finding| format!("Warning: {}", finding));
+        errors.chain(warnings).collect()
+    }
+}
+
+/// Where a rule records what it finds
+struct Findings<'r> {
+    rule: &'static str,
+    report: &'r mut ValidationReport,
+}
+
+impl Findings<'_> {
+    fn error(&mut self, message: String) {
+        self.report.errors.push(ValidationFinding {
+            rule: self.rule,
+            message,
+        });
+    }
+
+    fn warning(&mut self, message: String) {
+        self.report.warnings.push(ValidationFinding {
+            rule: self.rule,
+            message,
+        });
+    }
+}
+
+type Rule = fn(&IssueContent, &mut Findings);
+
+const RULES: [(&str, Rule); 9] = [
+    ("subject-length", check_subject_length),
+    ("leftover-todo", check_leftover_todo),
+    ("template-syntax", check_template_syntax),
+    ("link-url", check_link_urls),
+    ("image-url", check_image_urls),
+    ("link-text", check_link_text),
+    ("quote", check_quote),
+    ("link-health", check_link_health),
+    ("sponsor-html", check_sponsor_html),
+];
+
+/// Run every rule on the issue
+pub fn validate(content: &IssueContent) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    for (rule, check) in RULES {
+        check(
+            content,
+            &mut Findings {
+                rule,
+                report: &mut report,
+            },
+        );
+    }
+    report
+}
+
+fn is_web_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// The links rendered in the issue, with the name of their section
+fn issue_links<'a>(content: &'a IssueContent) -> impl Iterator<Item = (&'static str, &'a Link)> {
+    let links = content.data.links;
+    std::iter::once(("primary", links.primary))
+        .chain(links.secondary.iter().map(|link| ("secondary", *link)))
+        .chain(links.extra.iter().map(|link| ("extra", *link)))
+}
+
+fn check_subject_length(content: &IssueContent, findings: &mut Findings) {
+    let length = content.subject.chars().count();
+    if length > MAX_SUBJECT_LENGTH {
+        findings.error(format!(
+            "The subject is {} characters long (at most {})",
+            length, MAX_SUBJECT_LENGTH
+        ));
+    }
+}
+
+/// The intro placeholder and the `TODO:` markers of the templates are errors. Any other
+/// `TODO` is likely in the text of a link (e.g. "Build a TODO app"), and only a warning.
+fn check_leftover_todo(content: &IssueContent, findings: &mut Findings) {
+    for (index, line) in content.markdown.lines().enumerate() {
+        if line.contains(INTRO_PLACEHOLDER) {
+            findings.error(format!(
+                "The intro is still a placeholder (line {})",
+                index + 1
+            ));
+        } else if line.trim_start().starts_with("TODO:") {
+            findings.error(format!("Line {} has a leftover TODO", index + 1));
+        } else if line.contains("TODO") {
+            findings.warning(format!("Line {} mentions a TODO", index + 1));
+        }
+    }
+}
+
+/// Remove the ButtonDown merge tags (e.g. `{{ subscriber.metadata.first_name }}`), which
+/// are the only template syntax expected in the rendered markdown
+fn without_merge_tags(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        let tag = &rest[start + 2..];
+        let end = tag.find("}}");
+        match end {
+            Some(end) if tag[..end].trim().starts_with("subscriber.") => {
+                result.push_str(&rest[..start]);
+                rest = &tag[end + 2..];
+            }
+            _ => {
+                result.push_str(&rest[..start + 2]);
+                rest = tag;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn check_template_syntax(content: &IssueContent, findings: &mut Findings) {
+    for (index, line) in content.markdown.lines().enumerate() {
+        let line = without_merge_tags(line);
+        if ["{{", "}}", "{%", "%}", "{#", "#}"]
+            .iter()
+            .any(|syntax| line.contains(syntax))
+        {
+            findings.error(format!(
+                "Line {} has raw template syntax: {}",
+                index + 1,
+                line.trim()
+            ));
+        }
+    }
+}
+
+fn check_link_urls(content: &IssueContent, findings: &mut Findings) {
+    for (section, link) in issue_links(content) {
+        if !is_web_url(&link.url) || !is_web_url(&link.campaign_urls.title) {
+            findings.error(format!(
+                "The {} link \"{}\" has an invalid URL: {}",
+                section, link.title, link.campaign_urls.title
+            ));
+        }
+    }
+
+    let book = content.data.book;
+    if !is_web_url(&book.links.us) {
+        findings.error(format!(
+            "The book \"{}\" has an invalid US link: {}",
+            book.title, book.links.us
+        ));
+    }
+    if !book.links.uk.is_empty() && !is_web_url(&book.links.uk) {
+        findings.warning(format!(
+            "The book \"{}\" has an invalid UK link: {}",
+            book.title, book.links.uk
+        ));
+    }
+}
+
+fn check_image_urls(content: &IssueContent, findings: &mut Findings) {
+    // Only the image of the primary link is rendered
+    let primary = content.data.links.primary;
+    match primary.image.as_deref() {
+        Some(image) if is_web_url(image) => {}
+        Some(image) => findings.error(format!(
+            "The primary link \"{}\" has an invalid image URL: {}",
+            primary.title, image
+        )),
+        None => findings.error(format!(
+            "The primary link \"{}\" has no image",
+            primary.title
+        )),
+    }
+
+    let book = content.data.book;
+    if !is_web_url(&book.cover_picture) {
+        findings.error(format!(
+            "The book \"{}\" has an invalid cover picture URL: {:?}",
+            book.title, book.cover_picture
+        ));
+    }
+}
+
+fn check_link_text(content: &IssueContent, findings: &mut Findings) {
+    for (section, link) in issue_links(content) {
+        if link.title.trim().is_empty() {
+            findings.error(format!("A {} link has no title: {}", section, link.url));
+        } else if section != "extra" && link.description.trim().is_empty() {
+            // Extra links are only listed by title
+            findings.warning(format!(
+                "The {} link \"{}\" has no description",
+                section, link.title
+            ));
+        }
+    }
+}
+
+fn check_quote(content: &IssueContent, findings: &mut Findings) {
+    let quote = content.data.quote;
+    if quote.text.trim().is_empty() || quote.author.trim().is_empty() {
+        findings.error(format!("The quote #{} has no text or no author", quote.id));
+    }
+}
+
+/// The URLs the issue links to or shows: the campaign URLs and images of the links that made
+/// it into the sections, the book and the author of the quote
+fn rendered_urls<'a>(content: &'a IssueContent) -> HashSet<&'a str> {
+    let book = content.data.book;
+    let link_urls = issue_links(content).flat_map(|(_, link)| {
+        [
+            Some(&link.campaign_urls.title),
+            Some(&link.campaign_urls.image),
+            Some(&link.campaign_urls.description),
+            link.image.as_ref(),
+        ]
+    });
+    let book_urls = [&book.cover_picture, &book.links.us, &book.links.uk]
+        .into_iter()
+        .chain(book.links.other.values())
+        .map(Some);
+    link_urls
+        .chain(book_urls)
+        .chain([content.data.quote.author_url.as_ref()])
+        .flatten()
+        .map(String::as_str)
+        .collect()
+}
+
+fn check_link_health(content: &IssueContent, findings: &mut Findings) {
+    // Only the URLs that made it into the issue matter
+    let rendered = rendered_urls(content);
+    for check in content
+        .link_health
+        .iter()
+        .filter(|check| rendered.contains(check.url.as_str()))
+    {
+        match (check.health, check.status) {
+            (LinkHealth::Ok | LinkHealth::Redirect, _) => {}
+            (LinkHealth::ClientError, Some(status @ (404 | 410))) => {
+                findings.error(format!("{} is gone ({})", check.url, status))
+            }
+            (LinkHealth::Timeout, _) => findings.warning(format!("{} timed out", check.url)),
+            (health, Some(status)) => {
+                findings.warning(format!("{} returned a {} ({})", check.url, health, status))
+            }
+            (_, None) => findings.warning(format!("{} is unreachable", check.url)),
+        }
+    }
+}
+
+fn check_sponsor_html(content: &IssueContent, findings: &mut Findings) {
+    // The HTML is already clean, but the customer should hear about what was removed
+    for warning in content.sponsor_html {
+        findings.warning(warning.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{lay_out, LinkSections};
+    use crate::model::{Event, LinkLayout};
+
+    const ISSUE_435: &str = include_str!("../events/issue-435.json");
+
+    const MARKDOWN: &str = "Hello, {{ subscriber.metadata.first_name }}\n\nA fine intro\n";
+
+    fn event() -> Event {
+        serde_json::from_str(ISSUE_435).unwrap()
+    }
+
+    fn validate_event(event: &Event, subject: &str, markdown: &str) -> ValidationReport {
+        let links = lay_out(&event.data.links, &LinkLayout::default()).unwrap();
+        validate_sections(event, &links, subject, markdown)
+    }
+
+    fn validate_sections(
+        event: &Event,
+        links: &LinkSections,
+        subject: &str,
+        markdown: &str,
+    ) -> ValidationReport {
+        validate_with_health(event, links, subject, markdown, &[])
+    }
+
+    fn validate_with_health(
+        event: &Event,
+        links: &LinkSections,
+        subject: &str,
+        markdown: &str,
+        link_health: &[UrlCheck],
+    ) -> ValidationReport {
+        validate(&IssueContent {
+            subject,
+            data: &NewsletterData {
+                issue_number: event.next_issue.number,
+                quote: &event.data.quote,
+                book: &event.data.book,
+                links,
+                extra_content_title: "Extra",
+                sponsor: Some(&event.data.sponsor),
+                intro: None,
+            },
+            markdown,
+            link_health,
+            sponsor_html: &[],
+        })
+    }
+
+    fn rules(findings: &[ValidationFinding]) -> Vec<&str> {
+        findings.iter().map(|finding| finding.rule).collect()
+    }
+
+    #[test]
+    fn test_valid_issue() {
+        let report = validate_event(&event(), "A subject", MARKDOWN);
+
+        assert_eq!(report, ValidationReport::default());
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_subject_length() {
+        let subject = "x".repeat(MAX_SUBJECT_LENGTH + 1);
+
+        let report = validate_event(&event(), &subject, MARKDOWN);
+
+        assert_eq!(rules(&report.errors), vec!["subject-length"]);
+        assert!(validate_event(&event(), &"é".repeat(MAX_SUBJECT_LENGTH), MARKDOWN).is_empty());
+    }
+
+    #[test]
+    fn test_leftover_todo_and_template_syntax() {
+        let markdown = "Hello, {{ subscriber.metadata.first_name }}\n\n\
+                        TODO: WRITE INTRO\n\n\
+                        TODO: check this link\n\n\
+                        A {{ link.title }} and a {% endif %}\n";
+
+        let report = validate_event(&event(), "A subject", markdown);
+
+        assert_eq!(
+            report.errors,
+            vec![
+                ValidationFinding {
+                    rule: "leftover-todo",
+                    message: "The intro is still a placeholder (line 3)".to_string()
+                },
+                ValidationFinding {
+                    rule: "leftover-todo",
+                    message: "Line 5 has a leftover TODO".to_string()
+                },
+                ValidationFinding {
+                    rule: "template-syntax",
+                    message: "Line 7 has raw template syntax: A {{ link.title }} and a {% endif %}"
+                        .to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_todo_in_a_link_title_is_a_warning() {
+        let mut event = event();
+        event.data.links[1].title = "Build a TODO app in Rust".to_string();
+        let markdown = "Hello, {{ subscriber.metadata.first_name }}\n\n\
+                        A fine intro\n\n\
+                        [**Build a TODO app in Rust**](https://example.com/todo) — How to\n";
+
+        let report = validate_event(&event, "A subject", markdown);
+
+        assert!(!report.has_errors());
+        assert_eq!(
+            report.warnings,
+            vec![ValidationFinding {
+                rule: "leftover-todo",
+                message: "Line 5 mentions a TODO".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_broken_urls() {
+        let mut event = event();
+        event.data.links[0].image = Some("not a url".to_string());
+        event.data.links[1].campaign_urls.title = "/relative".to_string();
+        event.data.book.cover_picture = String::new();
+        event.data.book.links.uk = "amazon.co.uk/dp/1".to_string();
+
+        let report = validate_event(&event, "A subject", MARKDOWN);
+
+        assert_eq!(
+            rules(&report.errors),
+            vec!["link-url", "image-url", "image-url"]
+        );
+        assert_eq!(rules(&report.warnings), vec!["link-url"]);
+        assert!(report.errors[1].message.contains("not a url"));
+    }
+
+    #[test]
+    fn test_missing_link_text_and_quote() {
+        let mut event = event();
+        event.data.links[0].image = None;
+        event.data.links[2].title = " ".to_string();
+        event.data.links[3].description.clear();
+        event.data.links[12].description.clear();
+        event.data.quote.author.clear();
+
+        let report = validate_event(&event, "A subject", MARKDOWN);
+
+        assert_eq!(
+            rules(&report.errors),
+            vec!["image-url", "link-text", "quote"]
+        );
+        assert_eq!(rules(&report.warnings), vec!["link-text"]);
+        assert_eq!(
+            report.review_notes(),
+            vec![
+                format!(
+                    "Error: The primary link \"{}\" has no image (image-url)",
+                    event.data.links[0].title
+                ),
+                format!(
+                    "Error: A secondary link has no title: {} (link-text)",
+                    event.data.links[2].url
+                ),
+                format!(
+                    "Error: The quote #{} has no text or no author (quote)",
+                    event.data.quote.id
+                ),
+                format!(
+                    "Warning: The secondary link \"{}\" has no description (link-text)",
+                    event.data.links[3].title
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_rendered_links_are_checked() {
+        let mut event = event();
+        event.data.links[13].url = "broken".to_string();
+        let layout = LinkLayout {
+            extra_links: Some(0),
+            ..LinkLayout::default()
+        };
+        let links = lay_out(&event.data.links, &layout).unwrap();
+
+        assert!(validate_sections(&event, &links, "A subject", MARKDOWN).is_empty());
+    }
+
+    #[test]
+    fn test_link_health() {
+        let mut event = event();
+        event.data.links[0].campaign_urls.title = "https://gone.example/post".to_string();
+        event.data.links[1].campaign_urls.title = "https://moved.example".to_string();
+        event.data.links[2].campaign_urls.description = "https://down.example".to_string();
+        event.data.book.cover_picture = "https://slow.example/cover.jpg".to_string();
+        event.data.links[13].campaign_urls.title = "https://dropped.example".to_string();
+        let layout = LinkLayout {
+            extra_links: Some(0),
+            ..LinkLayout::default()
+        };
+        let links = lay_out(&event.data.links, &layout).unwrap();
+        let check = |url: &str, health, status| UrlCheck {
+            url: url.to_string(),
+            health,
+            status,
+            location: None,
+        };
+        let link_health = [
+            check(
+                "https://gone.example/post",
+                LinkHealth::ClientError,
+                Some(404),
+            ),
+            // Only a prefix of a rendered URL
+            check("https://gone.example", LinkHealth::ClientError, Some(410)),
+            check("https://moved.example", LinkHealth::Redirect, Some(301)),
+            check("https://down.example", LinkHealth::ServerError, Some(502)),
+            check("https://slow.example/cover.jpg", LinkHealth::Timeout, None),
+            check(
+                "https://dropped.example",
+                LinkHealth::ClientError,
+                Some(404),
+            ),
+        ];
+
+        let report = validate_with_health(&event, &links, "A subject", MARKDOWN, &link_health);
+
+        assert_eq!(
+            report.errors,
+            vec![ValidationFinding {
+                rule: "link-health",
+                message: "https://gone.example/post is gone (404)".to_string()
+            }]
+        );
+        assert_eq!(
+            report
+                .warnings
+                .iter()
+                .map(|finding| finding.message.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://down.example returned a server error (502)",
+                "https://slow.example/cover.jpg timed out"
+            ]
+        );
+    }
+}
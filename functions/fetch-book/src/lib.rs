@@ -0,0 +1,270 @@
+pub mod selection;
+
+use lambda_runtime::{tracing, Error, LambdaEvent};
+use reqwest::Client;
+use selection::BookSelector;
+use serde::Deserialize;
+use shared::http::{self, RetryPolicy};
+use shared::{BookLinks, BookResponse, Event};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Clone)]
+struct BookAuthor {
+    name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Book {
+    slug: String,
+    title: String,
+    subtitle: Option<String>,
+    authors: Vec<BookAuthor>,
+    cover: String,
+    links: HashMap<String, String>,
+    #[serde(rename = "descriptionHtml")]
+    description_html: String,
+}
+
+impl From<Book> for BookResponse {
+    fn from(book: Book) -> Self {
+        let authors = match book.authors.len() {
+            0 => "Unknown".to_string(),
+            1 => book.authors.first().unwrap().name.clone(),
+            _ => {
+                let author_names = book
+                    .authors
+                    .iter()
+                    .map(|a| a.name.clone())
+                    .collect::<Vec<_>>();
+                format!(
+                    "{}, and {}",
+                    author_names[..author_names.len() - 1].join(", "),
+                    author_names.last().unwrap()
+                )
+            }
+        };
+
+        let mut links = BookLinks::default();
+        for (key, value) in book.links.iter() {
+            match key.as_str() {
+                "amazon_us" => links.us = value.clone(),
+                "amazon_uk" => links.uk = value.clone(),
+                x => {
+                    links.other.insert(x.to_string(), value.clone());
+                }
+            }
+        }
+
+        let title = match book.subtitle {
+            Some(subtitle) => format!("{}: {}", book.title, subtitle),
+            None => book.title.clone(),
+        };
+
+        BookResponse {
+            id: book.slug,
+            title,
+            author: authors,
+            links,
+            cover_picture: book.cover.clone(),
+            description: book.description_html.clone(),
+        }
+    }
+}
+
+pub static BASE_URL: &str = "https://fullStackbulletin.github.io/fullstack-books";
+
+async fn fetch_book(
+    client: &Client,
+    base_url: &str,
+    book_id: &str,
+    retry_policy: &RetryPolicy,
+) -> Result<Book, Error> {
+    Ok(http::send(
+        client.get(format!("{}/books/{}.json", base_url, book_id)),
+        retry_policy,
+    )
+    .await?
+    .error_for_status()?
+    .json()
+    .await?)
+}
+
+pub async fn function_handler(
+    client: &Client,
+    base_url: &str,
+    selector: &BookSelector<'_>,
+    event: LambdaEvent<Event>,
+) -> Result<BookResponse, Error> {
+    let retry_policy = RetryPolicy::default();
+
+    // The book picked by the editor, whatever its links
+    if let Some(book_id) = &event.payload.config.book_id {
+        tracing::info!("Using the book of the config: {}", book_id);
+        return Ok(fetch_book(client, base_url, book_id, &retry_policy)
+            .await?
+            .into());
+    }
+
+    // Get the list of available book ids
+    let book_ids: Vec<String> = http::send(
+        client.get(format!("{}/books/ids.json", base_url)),
+        &retry_policy,
+    )
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+
+    // Try the books in the order of the selector, until one has all the required links
+    let issue_number = event.payload.next_issue.number;
+    for book_id in selector.candidates(issue_number, &book_ids)? {
+        let book = fetch_book(client, base_url, book_id, &retry_policy).await?;
+        if selector.is_available(&book.links) {
+            return Ok(book.into());
+        }
+        tracing::info!("Skipping {}, it lacks some of the required links", book_id);
+    }
+
+    Err(format!(
+        "No book of the catalogue is available for issue #{}",
+        issue_number
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use lambda_runtime::Context;
+    use serde_json::json;
+
+    fn event(book_id: Option<&str>) -> LambdaEvent<Event> {
+        let event = serde_json::from_value(json!({
+            "NextIssue": { "number": 435 },
+            "config": { "bookId": book_id }
+        }))
+        .unwrap();
+        LambdaEvent::new(event, Context::default())
+    }
+
+    fn mock_book<'a>(server: &'a MockServer, slug: &str, uk_link: &str) -> httpmock::Mock<'a> {
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/books/{}.json", slug));
+            then.status(200).json_body(json!({
+                "slug": slug,
+                "title": slug,
+                "subtitle": null,
+                "authors": [],
+                "cover": "https://example.com/cover.jpg",
+                "links": {
+                    "amazon_us": "https://www.amazon.com/dp/1",
+                    "amazon_uk": uk_link
+                },
+                "descriptionHtml": "<p>A book</p>"
+            }));
+        })
+    }
+
+    #[test]
+    fn test_book_response_from_catalogue_book() {
+        let book: Book = serde_json::from_value(json!({
+            "slug": "refactoring",
+            "title": "Refactoring",
+            "subtitle": "Improving the Design of Existing Code",
+            "authors": [{ "name": "Martin Fowler" }, { "name": "Kent Beck" }],
+            "cover": "https://example.com/refactoring.jpg",
+            "links": {
+                "amazon_us": "https://www.amazon.com/dp/0134757599",
+                "amazon_uk": "https://www.amazon.co.uk/dp/0134757599",
+                "free": "https://example.com/refactoring"
+            },
+            "descriptionHtml": "<p>Refactoring!</p>"
+        }))
+        .unwrap();
+
+        let response: BookResponse = book.into();
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "id": "refactoring",
+                "title": "Refactoring: Improving the Design of Existing Code",
+                "author": "Martin Fowler, and Kent Beck",
+                "links": {
+                    "us": "https://www.amazon.com/dp/0134757599",
+                    "uk": "https://www.amazon.co.uk/dp/0134757599",
+                    "free": "https://example.com/refactoring"
+                },
+                "coverPicture": "https://example.com/refactoring.jpg",
+                "description": "<p>Refactoring!</p>"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_skips_books_without_required_links() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/books/ids.json");
+            then.status(200).json_body(json!(["a", "b", "c"]));
+        });
+        let book_ids = ["a", "b", "c"].map(String::from);
+        let selector = BookSelector::default();
+        let order = selector.candidates(435, &book_ids).unwrap();
+        let skipped = mock_book(&server, order[0], "");
+        let picked = mock_book(&server, order[1], "https://www.amazon.co.uk/dp/1");
+        let untouched = mock_book(&server, order[2], "https://www.amazon.co.uk/dp/1");
+
+        let book = function_handler(&Client::new(), &server.base_url(), &selector, event(None))
+            .await
+            .unwrap();
+
+        assert_eq!(book.id, order[1]);
+        skipped.assert();
+        picked.assert();
+        untouched.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_book_id_from_config() {
+        let server = MockServer::start();
+        let ids = server.mock(|when, then| {
+            when.method(GET).path("/books/ids.json");
+            then.status(200).json_body(json!(["a", "b"]));
+        });
+        mock_book(&server, "refactoring", "");
+
+        let book = function_handler(
+            &Client::new(),
+            &server.base_url(),
+            &BookSelector::default(),
+            event(Some("refactoring")),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(book.id, "refactoring");
+        ids.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_fails_when_no_book_is_available() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/books/ids.json");
+            then.status(200).json_body(json!(["a"]));
+        });
+        mock_book(&server, "a", "");
+
+        let result = function_handler(
+            &Client::new(),
+            &server.base_url(),
+            &BookSelector::default(),
+            event(None),
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! How the book of an issue is picked among the books of the catalogue.
+//!
+//! Every book gets a random rank for every issue, derived from the issue number, the book id
+//! and a seed only: the same issue gets the same book on every run, and adding a book to the
+//! catalogue doesn't shuffle the picks of the other books.
+
+use shared::history::{IssueHistory, IssueHistoryError};
+use std::collections::HashMap;
+
+/// About half a year of issues
+pub const DEFAULT_AVOID_LAST: usize = 26;
+
+/// The links a book needs to be picked: the Amazon US and UK stores that the newsletter
+/// links to
+pub const DEFAULT_REQUIRED_LINKS: [&str; 2] = ["amazon_us", "amazon_uk"];
+
+pub struct BookSelector<'a> {
+    seed: u64,
+    weights: HashMap<String, f64>,
+    required_links: Vec<String>,
+    history: Option<&'a (dyn IssueHistory + Sync)>,
+    avoid_last: usize,
+}
+
+impl Default for BookSelector<'_> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<'a> BookSelector<'a> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            weights: HashMap::new(),
+            required_links: DEFAULT_REQUIRED_LINKS.map(str::to_string).to_vec(),
+            history: None,
+            avoid_last: 0,
+        }
+    }
+
+    /// Make some books more (or less) likely to be picked. Books have a weight of 1 by
+    /// default, and a weight of 0 only picks a book when no other book can be.
+    pub fn with_weights(mut self, weights: HashMap<String, f64>) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Only pick the books that have all of these links (keys of the catalogue `links`)
+    pub fn requiring_links(mut self, links: Vec<String>) -> Self {
+        self.required_links = links;
+        self
+    }
+
+    /// Leave out the books of the last `avoid_last` issues of the history, unless no other
+    /// book can be picked. An issue that is already in the history keeps its book.
+    pub fn with_history(
+        mut self,
+        history: &'a (dyn IssueHistory + Sync),
+        avoid_last: usize,
+    ) -> Self {
+        self.history = Some(history);
+        self.avoid_last = avoid_last;
+        self
+    }
+
+    /// The books of the catalogue in the order they should be tried for the issue: the book
+    /// it already has, if any, then the books of the recent issues last
+    pub fn candidates<'b>(
+        &self,
+        issue_number: u32,
+        book_ids: &'b [String],
+    ) -> Result<Vec<&'b str>, IssueHistoryError> {
+        let (recorded, recent) = match self.history {
+            Some(history) => (
+                history.issue(issue_number)?.map(|issue| issue.book_id),
+                history
+                    .recent(issue_number, self.avoid_last)?
+                    .into_iter()
+                    .map(|issue| issue.book_id)
+                    .collect(),
+            ),
+            None => (None, Vec::new()),
+        };
+
+        let mut ranked: Vec<(bool, f64, &str)> = book_ids
+            .iter()
+            .map(|book_id| {
+                let is_recent = recent.contains(book_id);
+                (
+                    is_recent,
+                    self.rank(issue_number, book_id),
+                    book_id.as_str(),
+                )
+            })
+            .collect();
+        // Books that weren't in the recent issues first, then by descending rank
+        ranked.sort_by(|left, right| {
+            left.0
+                .cmp(&right.0)
+                .then(right.1.total_cmp(&left.1))
+                .then(left.2.cmp(right.2))
+        });
+
+        let mut candidates: Vec<&str> = ranked.into_iter().map(|(_, _, id)| id).collect();
+        if let Some(position) = recorded.and_then(|recorded| {
+            candidates
+                .iter()
+                .position(|candidate| *candidate == recorded)
+        }) {
+            let book_id = candidates.remove(position);
+            candidates.insert(0, book_id);
+        }
+        Ok(candidates)
+    }
+
+    /// Whether a book has all the required links
+    pub fn is_available(&self, links: &HashMap<String, String>) -> bool {
+        self.required_links
+            .iter()
+            .all(|key| links.get(key).is_some_and(|link| !link.trim().is_empty()))
+    }
+
+    /// A weighted random key (Efraimidis-Spirakis): sorting the books by descending key
+    /// picks each one with a probability proportional to its weight
+    fn rank(&self, issue_number: u32, book_id: &str) -> f64 {
+        let weight = self.weights.get(book_id).copied().unwrap_or(1.0);
+        if weight <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        let hash = mix(fnv1a(book_id) ^ mix(self.seed ^ u64::from(issue_number)));
+        // A uniform value in (0, 1]
+        let uniform = ((hash >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        uniform.ln() / weight
+    }
+}
+
+/// FNV-1a, a hash whose values are fixed, unlike the ones of the `Hash` implementations
+/// of the standard library
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// The SplitMix64 finalizer
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::history::{IssueRecord, MemoryIssueHistory};
+
+    fn catalogue(size: usize) -> Vec<String> {
+        (0..size).map(|index| format!("book-{}", index)).collect()
+    }
+
+    fn record(number: u32, book_id: &str) -> IssueRecord {
+        IssueRecord {
+            number,
+            date: "2025-08-18".to_string(),
+            subject: format!("Issue #{}", number),
+            quote_id: 0,
+            book_id: book_id.to_string(),
+            sponsor: None,
+            links: Vec::new(),
+            sponsor_links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_selection_is_deterministic_and_stable() {
+        let book_ids = catalogue(20);
+        let selector = BookSelector::new(1);
+
+        let first = selector.candidates(435, &book_ids).unwrap();
+        assert_eq!(selector.candidates(435, &book_ids).unwrap(), first);
+        assert_eq!(first.len(), 20);
+        assert_ne!(selector.candidates(436, &book_ids).unwrap(), first);
+        assert_ne!(
+            BookSelector::new(2).candidates(435, &book_ids).unwrap(),
+            first
+        );
+
+        // A new book either comes first or leaves the pick untouched
+        let mut grown = book_ids.clone();
+        grown.push("book-new".to_string());
+        let pick = selector.candidates(435, &grown).unwrap()[0];
+        assert!(pick == first[0] || pick == "book-new");
+    }
+
+    #[test]
+    fn test_weights() {
+        let book_ids = catalogue(2);
+        let selector = BookSelector::new(0).with_weights(HashMap::from([
+            ("book-0".to_string(), 9.0),
+            ("book-1".to_string(), 1.0),
+        ]));
+        let picks = (0..1000)
+            .filter(|issue_number| {
+                selector.candidates(*issue_number, &book_ids).unwrap()[0] == "book-0"
+            })
+            .count();
+        assert!((850..950).contains(&picks), "{}", picks);
+
+        let never = BookSelector::new(0).with_weights(HashMap::from([("book-0".to_string(), 0.0)]));
+        for issue_number in 0..100 {
+            assert_eq!(
+                never.candidates(issue_number, &book_ids).unwrap(),
+                vec!["book-1", "book-0"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_history_avoids_recent_books() {
+        let book_ids = catalogue(3);
+        let history = MemoryIssueHistory::new();
+        history.record(record(433, "book-0")).unwrap();
+        history.record(record(434, "book-1")).unwrap();
+        let selector = BookSelector::new(0).with_history(&history, 2);
+
+        assert_eq!(selector.candidates(435, &book_ids).unwrap()[0], "book-2");
+        let candidates = selector.candidates(435, &book_ids).unwrap();
+        let mut last_two = candidates[1..].to_vec();
+        last_two.sort();
+        assert_eq!(last_two, vec!["book-0", "book-1"]);
+
+        // A re-run keeps the book of the issue
+        history.record(record(435, "book-1")).unwrap();
+        assert_eq!(selector.candidates(435, &book_ids).unwrap()[0], "book-1");
+    }
+
+    #[test]
+    fn test_required_links() {
+        let selector = BookSelector::default();
+        let both = HashMap::from([
+            (
+                "amazon_us".to_string(),
+                "https://www.amazon.com/dp/1".to_string(),
+            ),
+            (
+                "amazon_uk".to_string(),
+                "https://www.amazon.co.uk/dp/1".to_string(),
+            ),
+        ]);
+        let us_only = HashMap::from([
+            (
+                "amazon_us".to_string(),
+                "https://www.amazon.com/dp/1".to_string(),
+            ),
+            ("amazon_uk".to_string(), "".to_string()),
+        ]);
+
+        assert!(selector.is_available(&both));
+        assert!(!selector.is_available(&us_only));
+        assert!(BookSelector::default()
+            .requiring_links(vec!["amazon_us".to_string()])
+            .is_available(&us_only));
+    }
+}
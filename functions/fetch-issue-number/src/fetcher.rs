@@ -2,7 +2,9 @@ use nom::{
     bytes::complete::{tag, take_until, take_while},
     IResult,
 };
+use reqwest::Client;
 use scraper::selector;
+use shared::http::{self, RetryPolicy};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -32,7 +34,9 @@ fn parse_number_from_title(title: &str) -> IResult<&str, u32> {
 }
 
 pub async fn fetch_last_issue_number(url: &str) -> Result<u32, ScrapeError> {
-    let resp = reqwest::get(url).await?;
+    let resp = http::send(Client::new().get(url), &RetryPolicy::default())
+        .await?
+        .error_for_status()?;
     let body = resp.text().await?;
     let document = scraper::Html::parse_document(&body);
     // safe to unwrap because we are hardcoding the selector
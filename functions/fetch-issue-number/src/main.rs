@@ -1,6 +1,6 @@
+use fetch_issue_number::fetcher;
 use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
 use std::env;
-mod fetcher;
 
 async fn function_handler(
     _event: LambdaEvent<serde_json::Value>,
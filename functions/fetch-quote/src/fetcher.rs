@@ -1,24 +1,50 @@
+use crate::history::QuoteHistoryError;
 use crate::models::{InputQuote, Quote, Stats};
+use crate::selection::QuoteSelector;
+use reqwest::Client;
+use shared::http::{self, RetryPolicy};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum FetchQuoteError {
     #[error("Failed to fetch quote")]
     FailedRequest(#[from] reqwest::Error),
+    #[error("The quotes dataset is empty")]
+    NoQuotes,
+    #[error(transparent)]
+    History(#[from] QuoteHistoryError),
 }
 
-pub async fn fetch_quote(base_url: &str, _issue_number: u32) -> Result<Quote, FetchQuoteError> {
+pub async fn fetch_quote(
+    base_url: &str,
+    issue_number: u32,
+    selector: &QuoteSelector<'_>,
+) -> Result<Quote, FetchQuoteError> {
+    let client = Client::new();
+    let retry_policy = RetryPolicy::default();
+
     // Extract some useful information from the request
-    let stats: Stats = reqwest::get(format!("{}/quotes/stats.json", base_url))
+    let stats: Stats = http::send(
+        client.get(format!("{}/quotes/stats.json", base_url)),
+        &retry_policy,
+    )
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+
+    if stats.total == 0 {
+        return Err(FetchQuoteError::NoQuotes);
+    }
+    let index = selector.select(issue_number, stats.total)?;
+    let quote_url = format!("{}/quotes/{}.json", base_url, index);
+    let input_quote: InputQuote = http::send(client.get(&quote_url), &retry_policy)
         .await?
+        .error_for_status()?
         .json()
         .await?;
-
-    // Generate a random quote index in the range [0, stats.total)
-    let random_index = rand::random_range(0..stats.total);
-    let quote_url = format!("{}/quotes/{}.json", base_url, random_index);
-    let input_quote: InputQuote = reqwest::get(&quote_url).await?.json().await?;
     let output_quote: Quote = input_quote.into();
+    selector.record(issue_number, output_quote.id)?;
 
     Ok(output_quote)
 }
@@ -26,7 +52,9 @@ pub async fn fetch_quote(base_url: &str, _issue_number: u32) -> Result<Quote, Fe
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::history::{MemoryQuoteHistory, QuoteHistory};
     use httpmock::prelude::*;
+    use shared::test_support::first_calls;
 
     #[tokio::test]
     async fn test_fetch_quote_random() {
@@ -126,7 +154,7 @@ mod tests {
             .await;
 
         // Test that the function successfully fetches a random quote
-        let response = fetch_quote(&server.base_url(), 0).await;
+        let response = fetch_quote(&server.base_url(), 0, &QuoteSelector::default()).await;
 
         assert!(
             response.is_ok(),
@@ -139,4 +167,102 @@ mod tests {
         assert!(quote.id < 3, "Quote ID {} is out of range [0, 3)", quote.id);
         assert_eq!(quote.author, "Test Author");
     }
+
+    #[tokio::test]
+    async fn test_fetch_quote_retries_server_errors() {
+        let server = MockServer::start();
+
+        let unavailable = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/quotes/stats.json")
+                    .matches(first_calls!(1));
+                then.status(503);
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/quotes/stats.json");
+                then.status(200).json_body(serde_json::json!({
+                    "total": 1,
+                    "all": "https://fullStackbulletin.github.io/tech-quotes/quotes/all.json",
+                    "first": "https://fullStackbulletin.github.io/tech-quotes/quotes/0.json",
+                    "last": "https://fullStackbulletin.github.io/tech-quotes/quotes/0.json",
+                    "urlPrefix": "https://fullStackbulletin.github.io/tech-quotes/quotes"
+                }));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/quotes/0.json");
+                then.status(200).json_body(serde_json::json!({
+                    "id": 0,
+                    "text": "Test quote 0",
+                    "author": {
+                        "id": "test-author",
+                        "name": "Test Author",
+                        "description": "Author",
+                        "url": "https://fullStackbulletin.github.io/tech-quotes/authors/test.json"
+                    },
+                    "url": "https://fullStackbulletin.github.io/tech-quotes/quotes/0.json"
+                }));
+            })
+            .await;
+
+        let quote = fetch_quote(&server.base_url(), 0, &QuoteSelector::default())
+            .await
+            .unwrap();
+
+        unavailable.assert_hits_async(1).await;
+        assert_eq!(quote.text, "Test quote 0");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_quote_records_history() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/quotes/stats.json");
+            then.status(200).json_body(serde_json::json!({
+                "total": 3,
+                "all": "https://fullStackbulletin.github.io/tech-quotes/quotes/all.json",
+                "first": "https://fullStackbulletin.github.io/tech-quotes/quotes/0.json",
+                "last": "https://fullStackbulletin.github.io/tech-quotes/quotes/2.json",
+                "urlPrefix": "https://fullStackbulletin.github.io/tech-quotes/quotes"
+            }));
+        });
+        for id in 0..3 {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/quotes/{}.json", id));
+                then.status(200).json_body(serde_json::json!({
+                    "id": id,
+                    "text": format!("Test quote {}", id),
+                    "author": {
+                        "id": "test-author",
+                        "name": "Test Author",
+                        "description": "Author",
+                        "url": "https://fullStackbulletin.github.io/tech-quotes/authors/test.json"
+                    },
+                    "url": format!("https://fullStackbulletin.github.io/tech-quotes/quotes/{}.json", id)
+                }));
+            });
+        }
+        let history = MemoryQuoteHistory::new();
+        history.record(434, 1).unwrap();
+        let selector = QuoteSelector::HistoryAware {
+            history: &history,
+            avoid_last: 1,
+            seed: 0,
+        };
+
+        let quote = fetch_quote(&server.base_url(), 435, &selector)
+            .await
+            .unwrap();
+        let rerun = fetch_quote(&server.base_url(), 435, &selector)
+            .await
+            .unwrap();
+
+        assert_ne!(quote.id, 1);
+        assert_eq!(rerun, quote);
+        assert_eq!(history.quote_for(435).unwrap(), Some(quote.id));
+    }
 }
@@ -0,0 +1,188 @@
+//! Which quote went out with which issue, so that quotes don't repeat too soon and a
+//! re-run of an issue gets the same quote.
+
+use shared::history::{IssueHistory, IssueHistoryError};
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum QuoteHistoryError {
+    #[error("Failed to access the quote history: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid quote history: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Issues(#[from] IssueHistoryError),
+}
+
+pub trait QuoteHistory {
+    /// The quote of an issue, if it was already picked
+    fn quote_for(&self, issue_number: u32) -> Result<Option<u32>, QuoteHistoryError>;
+
+    /// The quotes of the issues before `issue_number`, most recent first
+    fn recent(&self, issue_number: u32, limit: usize) -> Result<Vec<u32>, QuoteHistoryError>;
+
+    fn record(&self, issue_number: u32, quote_id: u32) -> Result<(), QuoteHistoryError>;
+}
+
+fn recent_in(entries: &BTreeMap<u32, u32>, issue_number: u32, limit: usize) -> Vec<u32> {
+    entries
+        .range(..issue_number)
+        .rev()
+        .take(limit)
+        .map(|(_, quote_id)| *quote_id)
+        .collect()
+}
+
+/// A history kept in memory, useful in tests
+#[derive(Debug, Default)]
+pub struct MemoryQuoteHistory {
+    entries: Mutex<BTreeMap<u32, u32>>,
+}
+
+impl MemoryQuoteHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl QuoteHistory for MemoryQuoteHistory {
+    fn quote_for(&self, issue_number: u32) -> Result<Option<u32>, QuoteHistoryError> {
+        Ok(self.entries.lock().unwrap().get(&issue_number).copied())
+    }
+
+    fn recent(&self, issue_number: u32, limit: usize) -> Result<Vec<u32>, QuoteHistoryError> {
+        Ok(recent_in(
+            &self.entries.lock().unwrap(),
+            issue_number,
+            limit,
+        ))
+    }
+
+    fn record(&self, issue_number: u32, quote_id: u32) -> Result<(), QuoteHistoryError> {
+        self.entries.lock().unwrap().insert(issue_number, quote_id);
+        Ok(())
+    }
+}
+
+/// A history stored in a JSON file mapping issue numbers to quote ids, for local runs.
+/// A missing file is an empty history.
+#[derive(Debug, Clone)]
+pub struct FileQuoteHistory {
+    path: PathBuf,
+}
+
+impl FileQuoteHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn load(&self) -> Result<BTreeMap<u32, u32>, QuoteHistoryError> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl QuoteHistory for FileQuoteHistory {
+    fn quote_for(&self, issue_number: u32) -> Result<Option<u32>, QuoteHistoryError> {
+        Ok(self.load()?.get(&issue_number).copied())
+    }
+
+    fn recent(&self, issue_number: u32, limit: usize) -> Result<Vec<u32>, QuoteHistoryError> {
+        Ok(recent_in(&self.load()?, issue_number, limit))
+    }
+
+    fn record(&self, issue_number: u32, quote_id: u32) -> Result<(), QuoteHistoryError> {
+        let mut entries = self.load()?;
+        entries.insert(issue_number, quote_id);
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(())
+    }
+}
+
+/// The quotes of the published issues, from the issue history. Recording does nothing, as
+/// create-issue records the whole issue once it's published.
+pub struct IssueQuoteHistory<H>(pub H);
+
+impl<H: IssueHistory> QuoteHistory for IssueQuoteHistory<H> {
+    fn quote_for(&self, issue_number: u32) -> Result<Option<u32>, QuoteHistoryError> {
+        Ok(self.0.issue(issue_number)?.map(|issue| issue.quote_id))
+    }
+
+    fn recent(&self, issue_number: u32, limit: usize) -> Result<Vec<u32>, QuoteHistoryError> {
+        Ok(self
+            .0
+            .recent(issue_number, limit)?
+            .into_iter()
+            .map(|issue| issue.quote_id)
+            .collect())
+    }
+
+    fn record(&self, _issue_number: u32, _quote_id: u32) -> Result<(), QuoteHistoryError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::history::{IssueRecord, MemoryIssueHistory};
+
+    #[test]
+    fn test_file_history() {
+        let path = std::env::temp_dir().join(format!(
+            "fetch-quote-history-{}/quotes.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let history = FileQuoteHistory::new(&path);
+
+        assert_eq!(history.quote_for(435).unwrap(), None);
+        history.record(433, 7).unwrap();
+        history.record(434, 12).unwrap();
+        history.record(436, 3).unwrap();
+
+        let reopened = FileQuoteHistory::new(&path);
+        assert_eq!(reopened.quote_for(434).unwrap(), Some(12));
+        assert_eq!(reopened.recent(435, 5).unwrap(), vec![12, 7]);
+        assert_eq!(reopened.recent(435, 1).unwrap(), vec![12]);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            history.quote_for(435),
+            Err(QuoteHistoryError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_issue_quote_history() {
+        let issues = MemoryIssueHistory::new();
+        for (number, quote_id) in [(433, 7), (434, 12)] {
+            issues
+                .record(IssueRecord {
+                    number,
+                    date: "2025-08-18".to_string(),
+                    subject: format!("Issue #{}", number),
+                    quote_id,
+                    book_id: "building-microservices".to_string(),
+                    sponsor: None,
+                    links: Vec::new(),
+                    sponsor_links: Vec::new(),
+                })
+                .unwrap();
+        }
+        let history = IssueQuoteHistory(&issues);
+
+        assert_eq!(history.quote_for(434).unwrap(), Some(12));
+        assert_eq!(history.quote_for(435).unwrap(), None);
+        assert_eq!(history.recent(435, 5).unwrap(), vec![12, 7]);
+        history.record(435, 3).unwrap();
+        assert_eq!(history.quote_for(435).unwrap(), None);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod fetcher;
+pub mod history;
+pub mod models;
+pub mod selection;
@@ -1,16 +1,35 @@
+use fetch_quote::{
+    fetcher::fetch_quote,
+    history::IssueQuoteHistory,
+    models::Quote,
+    selection::{QuoteSelector, DEFAULT_AVOID_LAST},
+};
 use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
+use shared::{history::FileIssueHistory, Event};
 use std::env;
 
-mod fetcher;
-mod models;
-use fetcher::fetch_quote;
-use models::Quote;
-use shared::Event;
-
 async fn function_handler(event: LambdaEvent<Event>) -> Result<Quote, Error> {
     let base_url = env::var("BASE_URL")
         .unwrap_or("https://fullstackbulletin.github.io/tech-quotes".to_string());
-    let quote = fetch_quote(&base_url, event.payload.next_issue.number).await?;
+    // The same seed for every run, so that a re-run of an issue gets the same quote
+    let seed = env::var("QUOTE_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_default();
+    // With an issue history (a file shared with create-issue, e.g. on EFS), the quotes of the
+    // last issues are avoided
+    let history = env::var("ISSUE_HISTORY_FILE")
+        .ok()
+        .map(|path| IssueQuoteHistory(FileIssueHistory::new(path)));
+    let selector = match &history {
+        Some(history) => QuoteSelector::HistoryAware {
+            history,
+            avoid_last: DEFAULT_AVOID_LAST,
+            seed,
+        },
+        None => QuoteSelector::Permutation { seed },
+    };
+    let quote = fetch_quote(&base_url, event.payload.next_issue.number, &selector).await?;
     Ok(quote)
 }
 
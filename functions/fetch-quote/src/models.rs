@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+pub use shared::Quote;
 
 #[derive(Deserialize)]
 pub struct Stats {
@@ -27,17 +28,6 @@ pub struct InputQuote {
     pub url: String,
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct Quote {
-    pub id: u32,
-    pub text: String,
-    pub author: String,
-    #[serde(rename = "authorDescription")]
-    pub author_description: String,
-    #[serde(rename = "authorUrl", skip_serializing_if = "Option::is_none")]
-    pub author_url: Option<String>,
-}
-
 impl From<InputQuote> for Quote {
     fn from(input: InputQuote) -> Self {
         Quote {
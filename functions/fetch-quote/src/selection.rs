@@ -0,0 +1,172 @@
+//! How the quote of an issue is picked among the quotes of the dataset, numbered from 0.
+//! Both strategies are deterministic: an issue gets the same quote on every run, as long as
+//! the dataset (and the history) don't change.
+
+use crate::history::{QuoteHistory, QuoteHistoryError};
+
+/// About a year of issues
+pub const DEFAULT_AVOID_LAST: usize = 52;
+
+pub enum QuoteSelector<'a> {
+    /// Walk a shuffled order of all the quotes, one per issue, with a new order after
+    /// every full walk
+    Permutation { seed: u64 },
+    /// Pick one of the quotes that the last `avoid_last` issues didn't use, and record it
+    HistoryAware {
+        history: &'a (dyn QuoteHistory + Sync),
+        avoid_last: usize,
+        seed: u64,
+    },
+}
+
+impl Default for QuoteSelector<'_> {
+    fn default() -> Self {
+        QuoteSelector::Permutation { seed: 0 }
+    }
+}
+
+impl QuoteSelector<'_> {
+    /// The quote of the issue among `total` quotes (at least one)
+    pub fn select(&self, issue_number: u32, total: u32) -> Result<u32, QuoteHistoryError> {
+        match self {
+            QuoteSelector::Permutation { seed } => {
+                let cycle = issue_number / total;
+                let order = permutation(*seed ^ mix(u64::from(cycle)), total);
+                Ok(order[(issue_number % total) as usize])
+            }
+            QuoteSelector::HistoryAware {
+                history,
+                avoid_last,
+                seed,
+            } => {
+                // Re-runs of an issue get the quote it already has
+                if let Some(quote_id) = history
+                    .quote_for(issue_number)?
+                    .filter(|quote_id| *quote_id < total)
+                {
+                    return Ok(quote_id);
+                }
+                // Leave at least one quote to pick
+                let recent = history.recent(issue_number, (*avoid_last).min(total as usize - 1))?;
+                let candidates: Vec<u32> = (0..total).filter(|id| !recent.contains(id)).collect();
+                let mut rng = SplitMix64(*seed ^ mix(u64::from(issue_number)));
+                Ok(candidates[rng.below(candidates.len() as u32) as usize])
+            }
+        }
+    }
+
+    /// Remember the quote of the issue, for the strategies that need to
+    pub fn record(&self, issue_number: u32, quote_id: u32) -> Result<(), QuoteHistoryError> {
+        match self {
+            QuoteSelector::Permutation { .. } => Ok(()),
+            QuoteSelector::HistoryAware { history, .. } => history.record(issue_number, quote_id),
+        }
+    }
+}
+
+/// A Fisher-Yates shuffle of `0..total`
+fn permutation(seed: u64, total: u32) -> Vec<u32> {
+    let mut order: Vec<u32> = (0..total).collect();
+    let mut rng = SplitMix64(seed);
+    for index in (1..order.len()).rev() {
+        let other = rng.below(index as u32 + 1) as usize;
+        order.swap(index, other);
+    }
+    order
+}
+
+fn mix(value: u64) -> u64 {
+    SplitMix64(value).next()
+}
+
+/// SplitMix64, a tiny generator whose sequence is fixed, unlike the ones of `rand` that can
+/// change between releases (and would change the quotes of past issues)
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u32) -> u32 {
+        (self.next() % u64::from(bound)) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::MemoryQuoteHistory;
+
+    #[test]
+    fn test_permutation_walks_every_quote_before_repeating() {
+        let selector = QuoteSelector::Permutation { seed: 42 };
+
+        for cycle in 0..3 {
+            let mut quotes: Vec<u32> = (cycle * 10..(cycle + 1) * 10)
+                .map(|issue_number| selector.select(issue_number, 10).unwrap())
+                .collect();
+            quotes.sort();
+            assert_eq!(quotes, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_permutation_is_deterministic() {
+        let selector = QuoteSelector::Permutation { seed: 42 };
+        let first: Vec<u32> = (0..20).map(|n| selector.select(n, 10).unwrap()).collect();
+        let again: Vec<u32> = (0..20).map(|n| selector.select(n, 10).unwrap()).collect();
+        let other_seed: Vec<u32> = (0..20)
+            .map(|n| {
+                QuoteSelector::Permutation { seed: 7 }
+                    .select(n, 10)
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(first, again);
+        assert_ne!(first, other_seed);
+        // Every walk has its own order
+        assert_ne!(first[..10], first[10..]);
+        assert_eq!(QuoteSelector::default().select(435, 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_history_aware_avoids_recent_quotes() {
+        let history = MemoryQuoteHistory::new();
+        let selector = QuoteSelector::HistoryAware {
+            history: &history,
+            avoid_last: 4,
+            seed: 1,
+        };
+
+        for issue_number in 1..=20 {
+            let quote_id = selector.select(issue_number, 5).unwrap();
+            let recent = history.recent(issue_number, 4).unwrap();
+            assert!(!recent.contains(&quote_id), "issue {}", issue_number);
+            selector.record(issue_number, quote_id).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_history_aware_reruns_get_the_same_quote() {
+        let history = MemoryQuoteHistory::new();
+        history.record(434, 2).unwrap();
+        history.record(435, 1).unwrap();
+        let selector = QuoteSelector::HistoryAware {
+            history: &history,
+            avoid_last: DEFAULT_AVOID_LAST,
+            seed: 0,
+        };
+
+        assert_eq!(selector.select(435, 10).unwrap(), 1);
+        // A quote that's no longer in the dataset is replaced
+        assert_eq!(selector.select(435, 1).unwrap(), 0);
+        // When every quote is recent, only the most recent ones are avoided
+        assert_eq!(selector.select(436, 2).unwrap(), 0);
+    }
+}
@@ -0,0 +1,597 @@
+//! A small client of the Airtable REST API: typed list queries (formula, fields, sort and
+//! view), the `offset` pagination of the list endpoint and Airtable's rate limit.
+
+use chrono::NaiveDate;
+use reqwest::{Client, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use shared::http::{self, RetryPolicy};
+use std::fmt;
+use std::time::Duration;
+use thiserror::Error;
+
+pub static AIRTABLE_BASE_URL: &str = "https://api.airtable.com/v0";
+
+/// The most records Airtable returns in a page
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Error)]
+pub enum AirtableError {
+    #[error("Invalid Airtable URL: {0}")]
+    Url(String),
+    #[error("Airtable request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Airtable returned {status}: {message}")]
+    Api { status: u16, message: String },
+}
+
+/// An Airtable formula, e.g. for `filterByFormula`. Field names and strings are escaped, so
+/// that the values of an event can't change the meaning of a formula.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formula {
+    text: String,
+    /// Comparisons are wrapped in parentheses when compared in turn
+    comparison: bool,
+}
+
+impl Formula {
+    fn new(text: String) -> Self {
+        Self {
+            text,
+            comparison: false,
+        }
+    }
+
+    /// A field, e.g. `{Issue}`
+    pub fn field(name: &str) -> Self {
+        Self::new(format!(
+            "{{{}}}",
+            name.replace('\\', "\\\\").replace('}', "\\}")
+        ))
+    }
+
+    /// A string literal, e.g. `"mid-banner"`
+    pub fn string(value: &str) -> Self {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        Self::new(format!("\"{}\"", escaped))
+    }
+
+    pub fn number(value: impl Into<f64>) -> Self {
+        Self::new(value.into().to_string())
+    }
+
+    /// A day, e.g. `DATETIME_PARSE("2025-08-25")`
+    pub fn date(date: NaiveDate) -> Self {
+        Self::function("DATETIME_PARSE", [Self::string(&date.to_string())])
+    }
+
+    pub fn boolean(value: bool) -> Self {
+        Self::new(if value { "TRUE()" } else { "FALSE()" }.to_string())
+    }
+
+    /// A call of an Airtable function, e.g. `IS_SAME({Date}, "2025-08-25", "day")`
+    pub fn function(name: &str, arguments: impl IntoIterator<Item = Formula>) -> Self {
+        let arguments: Vec<String> = arguments.into_iter().map(|formula| formula.text).collect();
+        Self::new(format!("{}({})", name, arguments.join(", ")))
+    }
+
+    pub fn and(formulas: impl IntoIterator<Item = Formula>) -> Self {
+        Self::function("AND", formulas)
+    }
+
+    pub fn or(formulas: impl IntoIterator<Item = Formula>) -> Self {
+        Self::function("OR", formulas)
+    }
+
+    fn compare(self, operator: &str, other: Formula) -> Self {
+        let operand = |formula: Formula| match formula.comparison {
+            true => format!("({})", formula.text),
+            false => formula.text,
+        };
+        Self {
+            text: format!("{}{}{}", operand(self), operator, operand(other)),
+            comparison: true,
+        }
+    }
+
+    pub fn eq(self, other: Formula) -> Self {
+        self.compare("=", other)
+    }
+
+    pub fn ne(self, other: Formula) -> Self {
+        self.compare("!=", other)
+    }
+
+    pub fn lt(self, other: Formula) -> Self {
+        self.compare("<", other)
+    }
+
+    pub fn le(self, other: Formula) -> Self {
+        self.compare("<=", other)
+    }
+
+    pub fn gt(self, other: Formula) -> Self {
+        self.compare(">", other)
+    }
+
+    pub fn ge(self, other: Formula) -> Self {
+        self.compare(">=", other)
+    }
+}
+
+impl std::ops::Not for Formula {
+    type Output = Formula;
+
+    fn not(self) -> Formula {
+        Formula::function("NOT", [self])
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Asc => "asc",
+            Direction::Desc => "desc",
+        }
+    }
+}
+
+/// The parameters of a list request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    filter: Option<Formula>,
+    fields: Vec<String>,
+    sort: Vec<(String, Direction)>,
+    view: Option<String>,
+    page_size: Option<u32>,
+    max_records: Option<u32>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only list the records for which the formula is true
+    pub fn filter(mut self, formula: Formula) -> Self {
+        self.filter = Some(formula);
+        self
+    }
+
+    /// Only return these fields of the records (all of them by default)
+    pub fn fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sort by a field, after the fields of the previous calls
+    pub fn sort(mut self, field: impl Into<String>, direction: Direction) -> Self {
+        self.sort.push((field.into(), direction));
+        self
+    }
+
+    /// List the records of a view, in its order and with its filters
+    pub fn view(mut self, view: impl Into<String>) -> Self {
+        self.view = Some(view.into());
+        self
+    }
+
+    /// Records per page, at most (and by default) 100
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size.min(MAX_PAGE_SIZE));
+        self
+    }
+
+    /// Records in total, over all the pages
+    pub fn max_records(mut self, max_records: u32) -> Self {
+        self.max_records = Some(max_records);
+        self
+    }
+
+    fn append_to(&self, url: &mut Url, offset: Option<&str>) {
+        let mut pairs = url.query_pairs_mut();
+        if let Some(filter) = &self.filter {
+            pairs.append_pair("filterByFormula", &filter.text);
+        }
+        for field in &self.fields {
+            pairs.append_pair("fields[]", field);
+        }
+        for (index, (field, direction)) in self.sort.iter().enumerate() {
+            pairs.append_pair(&format!("sort[{}][field]", index), field);
+            pairs.append_pair(&format!("sort[{}][direction]", index), direction.as_str());
+        }
+        if let Some(view) = &self.view {
+            pairs.append_pair("view", view);
+        }
+        if let Some(page_size) = self.page_size {
+            pairs.append_pair("pageSize", &page_size.to_string());
+        }
+        if let Some(max_records) = self.max_records {
+            pairs.append_pair("maxRecords", &max_records.to_string());
+        }
+        if let Some(offset) = offset {
+            pairs.append_pair("offset", offset);
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Record<T> {
+    pub id: String,
+    #[serde(default)]
+    pub created_time: String,
+    pub fields: T,
+}
+
+#[derive(Deserialize)]
+struct Page<T> {
+    records: Vec<Record<T>>,
+    /// Present when there are more records
+    offset: Option<String>,
+}
+
+/// The error of a failed request: either `{"error": {"type", "message"}}` or
+/// `{"error": "TYPE"}`
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorDetail {
+    Typed {
+        #[serde(rename = "type")]
+        kind: String,
+        message: Option<String>,
+    },
+    Plain(String),
+}
+
+impl ErrorDetail {
+    fn message(self) -> String {
+        match self {
+            ErrorDetail::Typed {
+                kind,
+                message: Some(message),
+            } => format!("{} ({})", message, kind),
+            ErrorDetail::Typed {
+                kind,
+                message: None,
+            } => kind,
+            ErrorDetail::Plain(kind) => kind,
+        }
+    }
+}
+
+/// Airtable allows 5 requests per second per base, and rejects every request of the next 30
+/// seconds with a 429 once the limit is exceeded
+pub fn rate_limit_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 2,
+        initial_delay: Duration::from_millis(200),
+        max_delay: Duration::from_secs(5),
+        rate_limit_delay: Some(Duration::from_secs(30)),
+        max_retry_after: Duration::from_secs(30),
+    }
+}
+
+pub struct AirtableClient {
+    client: Client,
+    base_url: String,
+    base_id: String,
+    api_key: String,
+    retry_policy: RetryPolicy,
+}
+
+impl AirtableClient {
+    pub fn new(
+        client: Client,
+        base_url: impl Into<String>,
+        base_id: impl Into<String>,
+        api_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.into(),
+            base_id: base_id.into(),
+            api_key: api_key.into(),
+            retry_policy: rate_limit_policy(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Every record of a table matching the query, following the pages
+    pub async fn list<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        query: &Query,
+    ) -> Result<Vec<Record<T>>, AirtableError> {
+        let mut records = Vec::new();
+        let mut offset = None;
+        loop {
+            let page: Page<T> = self.page(table, query, offset.as_deref()).await?;
+            records.extend(page.records);
+            match page.offset {
+                Some(next) => offset = Some(next),
+                None => return Ok(records),
+            }
+        }
+    }
+
+    async fn page<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        query: &Query,
+        offset: Option<&str>,
+    ) -> Result<Page<T>, AirtableError> {
+        let mut url = Url::parse(&format!("{}/{}/", self.base_url, self.base_id))
+            .and_then(|url| url.join(table))
+            .map_err(|e| AirtableError::Url(e.to_string()))?;
+        query.append_to(&mut url, offset);
+
+        let request = self.client.get(url).bearer_auth(&self.api_key);
+        let response = http::send(request, &self.retry_policy).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<ErrorResponse>(&body)
+                .map(|error| error.error.message())
+                .unwrap_or(body);
+            return Err(AirtableError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+    use shared::test_support::first_calls;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Fields {
+        #[serde(rename = "Customer")]
+        customer: String,
+    }
+
+    fn airtable(server: &MockServer) -> AirtableClient {
+        AirtableClient::new(Client::new(), server.url("/v0"), "app123", "key")
+            .with_retry_policy(rate_limit_policy().without_delays())
+    }
+
+    fn without_offset(request: &HttpMockRequest) -> bool {
+        !request
+            .query_params
+            .iter()
+            .flatten()
+            .any(|(name, _)| name == "offset")
+    }
+
+    #[test]
+    fn test_formulas() {
+        let issue = Formula::field("Issue").eq(Formula::number(435));
+        assert_eq!(issue.to_string(), "{Issue}=435");
+        assert_eq!(
+            Formula::and([
+                issue,
+                Formula::field("Slot").ne(Formula::string("mid-banner")),
+                !Formula::field("Paid").eq(Formula::boolean(true)),
+            ])
+            .to_string(),
+            r#"AND({Issue}=435, {Slot}!="mid-banner", NOT({Paid}=TRUE()))"#
+        );
+        assert_eq!(
+            Formula::function(
+                "IS_SAME",
+                [
+                    Formula::field("Date"),
+                    Formula::string("2025-08-25"),
+                    Formula::string("day")
+                ]
+            )
+            .to_string(),
+            r#"IS_SAME({Date}, "2025-08-25", "day")"#
+        );
+        assert_eq!(
+            Formula::field("A")
+                .le(Formula::field("B"))
+                .eq(Formula::boolean(false))
+                .to_string(),
+            "({A}<={B})=FALSE()"
+        );
+    }
+
+    #[test]
+    fn test_formulas_are_escaped() {
+        // A customer can't turn the filter into `TRUE()`
+        assert_eq!(
+            Formula::field("Customer")
+                .eq(Formula::string(r#"ACME", TRUE(), ""#))
+                .to_string(),
+            r#"{Customer}="ACME\", TRUE(), \"""#
+        );
+        assert_eq!(
+            Formula::string("C:\\ACME\nInc").to_string(),
+            r#""C:\\ACME\nInc""#
+        );
+        assert_eq!(
+            Formula::field(r"Weird} {name\").to_string(),
+            r"{Weird\} {name\\}"
+        );
+    }
+
+    #[test]
+    fn test_query_parameters() {
+        let mut url = Url::parse("https://api.airtable.com/v0/app123/Sponsors").unwrap();
+        Query::new()
+            .filter(Formula::field("Issue").eq(Formula::number(435)))
+            .fields(["Customer", "Slot"])
+            .sort("Date", Direction::Asc)
+            .sort("Customer", Direction::Desc)
+            .view("Paid")
+            .page_size(500)
+            .max_records(10)
+            .append_to(&mut url, Some("itr/rec"));
+
+        assert_eq!(
+            url.query_pairs()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>(),
+            vec![
+                "filterByFormula={Issue}=435",
+                "fields[]=Customer",
+                "fields[]=Slot",
+                "sort[0][field]=Date",
+                "sort[0][direction]=asc",
+                "sort[1][field]=Customer",
+                "sort[1][direction]=desc",
+                "view=Paid",
+                "pageSize=100",
+                "maxRecords=10",
+                "offset=itr/rec",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_follows_the_pages() {
+        let server = MockServer::start();
+        let first = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v0/app123/Sponsors")
+                .query_param("filterByFormula", "{Issue}=435")
+                .query_param("fields[]", "Customer")
+                .header("Authorization", "Bearer key")
+                .matches(without_offset);
+            then.status(200).json_body(json!({
+                "records": [
+                    { "id": "rec1", "createdTime": "2025-08-01T10:00:00.000Z", "fields": { "Customer": "ACME" } }
+                ],
+                "offset": "itr1/rec1"
+            }));
+        });
+        let second = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v0/app123/Sponsors")
+                .query_param("filterByFormula", "{Issue}=435")
+                .query_param("offset", "itr1/rec1");
+            then.status(200).json_body(json!({
+                "records": [
+                    { "id": "rec2", "createdTime": "2025-08-02T10:00:00.000Z", "fields": { "Customer": "Globex" } }
+                ]
+            }));
+        });
+        let query = Query::new()
+            .filter(Formula::field("Issue").eq(Formula::number(435)))
+            .fields(["Customer"]);
+
+        let records: Vec<Record<Fields>> =
+            airtable(&server).list("Sponsors", &query).await.unwrap();
+
+        first.assert_hits(1);
+        second.assert_hits(1);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record.id.as_str(), record.fields.customer.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("rec1", "ACME"), ("rec2", "Globex")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_waits_for_the_rate_limit() {
+        let server = MockServer::start();
+        let rate_limited = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v0/app123/Sponsors")
+                .matches(first_calls!(1));
+            then.status(429).json_body(json!({
+                "errors": [{ "error": "RATE_LIMIT_REACHED" }]
+            }));
+        });
+        let success = server.mock(|when, then| {
+            when.method(GET).path("/v0/app123/Sponsors");
+            then.status(200).json_body(json!({ "records": [] }));
+        });
+
+        let records: Vec<Record<Fields>> = airtable(&server)
+            .list("Sponsors", &Query::new())
+            .await
+            .unwrap();
+
+        assert!(records.is_empty());
+        rate_limited.assert_hits(1);
+        success.assert_hits(1);
+        assert_eq!(
+            rate_limit_policy().rate_limit_delay,
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_reports_airtable_errors() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/v0/app123/Sponsors");
+            then.status(422).json_body(json!({
+                "error": {
+                    "type": "INVALID_FILTER_BY_FORMULA",
+                    "message": "The formula for filtering records is invalid"
+                }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/v0/app123/Missing");
+            then.status(404).json_body(json!({ "error": "NOT_FOUND" }));
+        });
+
+        let error = airtable(&server)
+            .list::<Fields>("Sponsors", &Query::new())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Airtable returned 422: The formula for filtering records is invalid \
+             (INVALID_FILTER_BY_FORMULA)"
+        );
+
+        let error = airtable(&server)
+            .list::<Fields>("Missing", &Query::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AirtableError::Api { status: 404, ref message } if message == "NOT_FOUND"
+        ));
+    }
+}
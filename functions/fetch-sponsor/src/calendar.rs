@@ -0,0 +1,262 @@
+//! The booking calendar of the sponsors. A booking (a record of the `Sponsors` table) is for
+//! the day an issue is sent (`Date`), or for an issue number (`Issue`) when it has no date:
+//! matching by date keeps the bookings on the right issue when the numbers shift, e.g. after
+//! a skipped week.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use lambda_runtime::tracing;
+use shared::{BookingReport, DoubleBooking, UnmatchedBooking};
+
+use crate::airtable::Formula;
+use crate::Sponsor;
+
+/// How many weeks after the issue the report looks for gaps and double bookings
+pub const LOOKAHEAD_WEEKS: u32 = 8;
+
+pub struct Calendar {
+    issue_number: u32,
+    send_date: NaiveDate,
+}
+
+impl Calendar {
+    pub fn new(issue_number: u32, send_date: NaiveDate) -> Self {
+        Self {
+            issue_number,
+            send_date,
+        }
+    }
+
+    /// The last day of the report
+    fn end_date(&self) -> NaiveDate {
+        self.send_date + Duration::weeks(LOOKAHEAD_WEEKS.into())
+    }
+
+    /// The bookings to fetch: the ones of the next weeks, and the ones of the issue number
+    pub fn formula(&self) -> Formula {
+        let date = Formula::field("Date");
+        Formula::or([
+            Formula::and([
+                Formula::function(
+                    "IS_AFTER",
+                    [
+                        date.clone(),
+                        Formula::date(self.send_date.pred_opt().unwrap()),
+                    ],
+                ),
+                Formula::function(
+                    "IS_BEFORE",
+                    [date, Formula::date(self.end_date().succ_opt().unwrap())],
+                ),
+            ]),
+            Formula::field("Issue").eq(Formula::number(self.issue_number)),
+        ])
+    }
+
+    /// The day a booking is for: its date, or the send date of the issue when it only has
+    /// the issue number
+    fn date_of(&self, booking: &Sponsor) -> Option<Result<NaiveDate, String>> {
+        match booking.date.as_deref().map(str::trim) {
+            Some(date) if !date.is_empty() => Some(
+                // Date fields are `YYYY-MM-DD`, date and time fields are RFC 3339
+                NaiveDate::parse_from_str(date.get(..10).unwrap_or(date), "%Y-%m-%d")
+                    .map_err(|_| date.to_string()),
+            ),
+            _ => (booking.issue == Some(self.issue_number)).then_some(Ok(self.send_date)),
+        }
+    }
+
+    /// The bookings of the issue, in the order of Airtable
+    pub fn bookings_of_issue(&self, bookings: Vec<Sponsor>) -> Vec<Sponsor> {
+        bookings
+            .into_iter()
+            .filter(|booking| {
+                let matched = self.date_of(booking) == Some(Ok(self.send_date));
+                if matched
+                    && booking
+                        .issue
+                        .is_some_and(|issue| issue != self.issue_number)
+                {
+                    tracing::warn!(
+                        "The sponsor {} is booked for #{} on {}, which is the day of #{}",
+                        booking.customer(),
+                        booking.issue.unwrap_or_default(),
+                        self.send_date,
+                        self.issue_number
+                    );
+                }
+                matched
+            })
+            .collect()
+    }
+
+    /// Double bookings and gaps of the next weeks, and the paid bookings that can't match an
+    /// issue: the ones that aren't on a send day, and the ones of the issue number whose day
+    /// has passed
+    pub fn report(&self, bookings: &[Sponsor]) -> BookingReport {
+        let mut report = BookingReport {
+            send_date: self.send_date.to_string(),
+            ..BookingReport::default()
+        };
+        let mut booked: Vec<(NaiveDate, &str, &Sponsor)> = Vec::new();
+
+        for booking in bookings {
+            let unmatched = |reason: String| UnmatchedBooking {
+                customer: booking.customer().to_string(),
+                date: booking.date.clone(),
+                issue: booking.issue,
+                reason,
+            };
+            let reason = match self.date_of(booking) {
+                None => continue,
+                Some(Err(date)) => Some(format!("{} is not a date", date)),
+                Some(Ok(date)) if date.weekday() != Weekday::Mon => Some(format!(
+                    "{} is a {}, issues are sent on Mondays",
+                    date,
+                    date.format("%A")
+                )),
+                Some(Ok(date)) if date < self.send_date => Some(format!(
+                    "{} has passed, #{} is sent on {}",
+                    date, self.issue_number, self.send_date
+                )),
+                Some(Ok(date)) => {
+                    booked.push((date, booking.slot(), booking));
+                    None
+                }
+            };
+            if let (Some(reason), true) = (reason, booking.paid.unwrap_or_default()) {
+                report.unmatched.push(unmatched(reason));
+            }
+        }
+
+        let mut date = self.send_date;
+        while date <= self.end_date() {
+            for slot in ["top-banner", "mid-banner"] {
+                let customers: Vec<String> = booked
+                    .iter()
+                    .filter(|(day, booked_slot, _)| *day == date && *booked_slot == slot)
+                    .map(|(_, _, booking)| booking.customer().to_string())
+                    .collect();
+                if customers.len() > 1 {
+                    report.double_bookings.push(DoubleBooking {
+                        date: date.to_string(),
+                        slot: slot.to_string(),
+                        customers,
+                    });
+                } else if customers.is_empty() && slot == "top-banner" {
+                    report.gaps.push(date.to_string());
+                }
+            }
+            date += Duration::weeks(1);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar() -> Calendar {
+        Calendar::new(435, NaiveDate::from_ymd_opt(2025, 8, 25).unwrap())
+    }
+
+    fn booking(customer: &str, date: Option<&str>, issue: Option<u32>) -> Sponsor {
+        Sponsor {
+            customer: Some(customer.to_string()),
+            date: date.map(str::to_string),
+            issue,
+            paid: Some(true),
+            ..Sponsor::default()
+        }
+    }
+
+    fn customers(bookings: &[Sponsor]) -> Vec<&str> {
+        bookings.iter().map(Sponsor::customer).collect()
+    }
+
+    #[test]
+    fn test_formula() {
+        assert_eq!(
+            calendar().formula().to_string(),
+            "OR(AND(IS_AFTER({Date}, DATETIME_PARSE(\"2025-08-24\")), \
+             IS_BEFORE({Date}, DATETIME_PARSE(\"2025-10-21\"))), {Issue}=435)"
+        );
+    }
+
+    #[test]
+    fn test_bookings_are_matched_by_date_then_by_number() {
+        let bookings = vec![
+            // Booked as #436 before the numbers shifted: the date wins
+            booking("ACME", Some("2025-08-25"), Some(436)),
+            booking("Globex", None, Some(435)),
+            booking("Initech", Some("2025-09-01"), Some(435)),
+            booking("Hooli", Some("2025-09-01T00:00:00.000Z"), None),
+            booking("Umbrella", None, Some(436)),
+        ];
+
+        assert_eq!(
+            customers(&calendar().bookings_of_issue(bookings)),
+            vec!["ACME", "Globex"]
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let mut mid_banner = booking("Stark", Some("2025-09-08"), None);
+        mid_banner.slot = Some("mid-banner".to_string());
+        let mut unpaid = booking("Wayne", Some("2025-09-10"), None);
+        unpaid.paid = None;
+        let bookings = vec![
+            booking("ACME", Some("2025-08-25"), None),
+            booking("Globex", None, Some(435)),
+            booking("Initech", Some("2025-09-08"), None),
+            mid_banner,
+            booking("Hooli", Some("2025-09-09"), None),
+            booking("Umbrella", Some("2025-08-18"), Some(435)),
+            booking("Cyberdyne", Some("someday"), None),
+            unpaid,
+            booking("Tyrell", Some("2025-10-20"), None),
+        ];
+
+        let report = calendar().report(&bookings);
+
+        assert_eq!(report.send_date, "2025-08-25");
+        assert_eq!(
+            report.double_bookings,
+            vec![DoubleBooking {
+                date: "2025-08-25".to_string(),
+                slot: "top-banner".to_string(),
+                customers: vec!["ACME".to_string(), "Globex".to_string()],
+            }]
+        );
+        assert_eq!(
+            report.gaps,
+            vec![
+                "2025-09-01",
+                "2025-09-15",
+                "2025-09-22",
+                "2025-09-29",
+                "2025-10-06",
+                "2025-10-13"
+            ]
+        );
+        assert_eq!(
+            report
+                .unmatched
+                .iter()
+                .map(|booking| (booking.customer.as_str(), booking.reason.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "Hooli",
+                    "2025-09-09 is a Tuesday, issues are sent on Mondays"
+                ),
+                (
+                    "Umbrella",
+                    "2025-08-18 has passed, #435 is sent on 2025-08-25"
+                ),
+                ("Cyberdyne", "someday is not a date"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+pub mod airtable;
+mod calendar;
+
+use airtable::{AirtableClient, Formula, Query};
+use calendar::Calendar;
+use lambda_runtime::{tracing, Error, LambdaEvent};
+use serde::Deserialize;
+use shared::datetime_utils::get_next_monday_from;
+use shared::{Event, SponsorPlacement, SponsorResponse, SponsorSlot};
+
+pub use airtable::AIRTABLE_BASE_URL;
+
+/// The fields of the `Sponsors` table that make a sponsor
+const SPONSOR_FIELDS: [&str; 7] = [
+    "BannerHTML",
+    "SponsoredArticleHTML",
+    "Customer",
+    "Slot",
+    "Date",
+    "Issue",
+    "Paid",
+];
+
+#[derive(Deserialize, Clone, Debug, Default)]
+struct Sponsor {
+    #[serde(rename = "BannerHTML")]
+    banner_html: Option<String>,
+    #[serde(rename = "SponsoredArticleHTML")]
+    sponsored_article_html: Option<String>,
+    #[serde(rename = "Customer")]
+    customer: Option<String>,
+    /// `top-banner` (the default), `mid-banner` or `classified`
+    #[serde(rename = "Slot")]
+    slot: Option<String>,
+    /// The day of the issue the sponsor booked
+    #[serde(rename = "Date")]
+    date: Option<String>,
+    /// The number of the issue the sponsor booked, for the bookings without a date
+    #[serde(rename = "Issue")]
+    issue: Option<u32>,
+    #[serde(rename = "Paid")]
+    paid: Option<bool>,
+}
+
+impl Sponsor {
+    fn customer(&self) -> &str {
+        self.customer.as_deref().unwrap_or_default()
+    }
+
+    fn slot(&self) -> &str {
+        match self.slot.as_deref().map(str::trim) {
+            None | Some("") => "top-banner",
+            Some(slot) => slot,
+        }
+    }
+}
+
+impl From<Sponsor> for SponsorResponse {
+    fn from(sponsor: Sponsor) -> Self {
+        SponsorResponse {
+            banner_html: sponsor.banner_html.unwrap_or_default(),
+            sponsored_article_html: sponsor.sponsored_article_html.unwrap_or_default(),
+            customer: sponsor.customer.unwrap_or_default(),
+            placements: Vec::new(),
+            bookings: None,
+        }
+    }
+}
+
+/// Put every sponsor of the issue in its slot: one top banner and one mid banner (the first
+/// ones, in the order of Airtable), and any number of classifieds. The banner HTML of a
+/// record is the content of the other slots.
+fn into_response(sponsors: Vec<Sponsor>) -> SponsorResponse {
+    let mut top: Option<SponsorResponse> = None;
+    let mut placements: Vec<SponsorPlacement> = Vec::new();
+    for sponsor in sponsors {
+        let customer = sponsor.customer().to_string();
+        let slot = match sponsor.slot() {
+            "top-banner" => None,
+            "mid-banner" => Some(SponsorSlot::MidBanner),
+            "classified" => Some(SponsorSlot::Classified),
+            slot => {
+                tracing::warn!("Ignoring the sponsor {}: unknown slot {}", customer, slot);
+                continue;
+            }
+        };
+        match slot {
+            None if top.is_some() => {
+                tracing::warn!("Ignoring the sponsor {}: the top banner is taken", customer);
+            }
+            None => top = Some(sponsor.into()),
+            Some(SponsorSlot::MidBanner)
+                if placements
+                    .iter()
+                    .any(|placement| placement.slot == SponsorSlot::MidBanner) =>
+            {
+                tracing::warn!("Ignoring the sponsor {}: the mid banner is taken", customer);
+            }
+            Some(slot) => placements.push(SponsorPlacement {
+                slot,
+                customer,
+                html: sponsor.banner_html.unwrap_or_default(),
+            }),
+        }
+    }
+
+    SponsorResponse {
+        placements,
+        ..top.unwrap_or_default()
+    }
+}
+
+pub struct Handler {
+    pub airtable_base_url: String,
+    /// The ID of the Airtable base (`app…`) with the `Sponsors` table
+    pub airtable_table_id: String,
+    pub airtable_api_key: String,
+    pub client: reqwest::Client,
+}
+
+impl Handler {
+    pub async fn handle(&self, event: LambdaEvent<Event>) -> Result<SponsorResponse, Error> {
+        let airtable = AirtableClient::new(
+            self.client.clone(),
+            &self.airtable_base_url,
+            &self.airtable_table_id,
+            &self.airtable_api_key,
+        );
+        let issue_number = event.payload.next_issue.number;
+
+        // The bookings are matched by the day the issue is sent, which follows from the time
+        // of the scheduled event like in create-issue
+        let calendar = match event.payload.config.time.as_deref() {
+            Some(time) => Some(Calendar::new(
+                issue_number,
+                get_next_monday_from(time)?.date_naive(),
+            )),
+            None => {
+                tracing::warn!("No event time, the sponsors are matched by issue number");
+                None
+            }
+        };
+        let filter = match &calendar {
+            Some(calendar) => calendar.formula(),
+            None => Formula::field("Issue").eq(Formula::number(issue_number)),
+        };
+        let query = Query::new().filter(filter).fields(SPONSOR_FIELDS);
+        let bookings: Vec<Sponsor> = airtable
+            .list::<Sponsor>("Sponsors", &query)
+            .await?
+            .into_iter()
+            .map(|record| record.fields)
+            .collect();
+
+        let Some(calendar) = calendar else {
+            return Ok(into_response(bookings));
+        };
+        let report = calendar.report(&bookings);
+        for double_booking in &report.double_bookings {
+            tracing::warn!(
+                "The {} of {} is booked by {}",
+                double_booking.slot,
+                double_booking.date,
+                double_booking.customers.join(", ")
+            );
+        }
+        for unmatched in &report.unmatched {
+            tracing::warn!(
+                "The paid booking of {} matches no issue: {}",
+                unmatched.customer,
+                unmatched.reason
+            );
+        }
+        if !report.gaps.is_empty() {
+            tracing::info!("No top banner sponsor on {}", report.gaps.join(", "));
+        }
+        Ok(SponsorResponse {
+            bookings: Some(report),
+            ..into_response(calendar.bookings_of_issue(bookings))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    fn sponsors(records: serde_json::Value) -> Vec<Sponsor> {
+        records
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|record| serde_json::from_value(record["fields"].clone()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_single_sponsor() {
+        let response = into_response(sponsors(json!([{
+            "fields": {
+                "BannerHTML": "<a>banner</a>",
+                "SponsoredArticleHTML": "<p>article</p>",
+                "Customer": "ACME"
+            }
+        }])));
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "banner_html": "<a>banner</a>",
+                "sponsored_article_html": "<p>article</p>",
+                "customer": "ACME"
+            })
+        );
+        assert_eq!(into_response(Vec::new()), SponsorResponse::default());
+    }
+
+    #[test]
+    fn test_sponsors_by_slot() {
+        let response = into_response(sponsors(json!([
+            { "fields": { "Customer": "Initech", "BannerHTML": "<a>ad 1</a>", "Slot": "classified" } },
+            { "fields": { "Customer": "ACME", "BannerHTML": "<a>mid</a>", "Slot": "mid-banner" } },
+            { "fields": { "Customer": "Globex", "BannerHTML": "<a>top</a>", "Slot": "top-banner" } },
+            { "fields": { "Customer": "Umbrella", "BannerHTML": "<a>mid 2</a>", "Slot": "mid-banner" } },
+            { "fields": { "Customer": "Hooli", "BannerHTML": "<a>top 2</a>" } },
+            { "fields": { "Customer": "Stark", "BannerHTML": "<a>ad 2</a>", "Slot": "classified" } },
+            { "fields": { "Customer": "Wayne", "BannerHTML": "<a>?</a>", "Slot": "footer" } }
+        ])));
+
+        assert_eq!(response.customer, "Globex");
+        assert_eq!(response.banner_html, "<a>top</a>");
+        assert_eq!(
+            response.placements,
+            vec![
+                SponsorPlacement {
+                    slot: SponsorSlot::Classified,
+                    customer: "Initech".to_string(),
+                    html: "<a>ad 1</a>".to_string(),
+                },
+                SponsorPlacement {
+                    slot: SponsorSlot::MidBanner,
+                    customer: "ACME".to_string(),
+                    html: "<a>mid</a>".to_string(),
+                },
+                SponsorPlacement {
+                    slot: SponsorSlot::Classified,
+                    customer: "Stark".to_string(),
+                    html: "<a>ad 2</a>".to_string(),
+                },
+            ]
+        );
+    }
+
+    fn handler(server: &MockServer) -> Handler {
+        Handler {
+            airtable_base_url: server.url("/v0"),
+            airtable_table_id: "app123".to_string(),
+            airtable_api_key: "key".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn event(config: serde_json::Value) -> LambdaEvent<Event> {
+        LambdaEvent::new(
+            serde_json::from_value(json!({ "NextIssue": { "number": 435 }, "config": config }))
+                .unwrap(),
+            lambda_runtime::Context::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sponsors_are_matched_by_send_date() {
+        let server = MockServer::start();
+        let sponsors = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v0/app123/Sponsors")
+                .query_param(
+                    "filterByFormula",
+                    "OR(AND(IS_AFTER({Date}, DATETIME_PARSE(\"2025-08-24\")), \
+                     IS_BEFORE({Date}, DATETIME_PARSE(\"2025-10-21\"))), {Issue}=435)",
+                )
+                .query_param("fields[]", "Date");
+            then.status(200).json_body(json!({
+                "records": [
+                    { "id": "rec1", "fields": { "Customer": "ACME", "BannerHTML": "<a>top</a>", "Date": "2025-08-25", "Issue": 436, "Paid": true } },
+                    { "id": "rec2", "fields": { "Customer": "Globex", "BannerHTML": "<a>next</a>", "Date": "2025-09-01", "Paid": true } },
+                    { "id": "rec3", "fields": { "Customer": "Initech", "BannerHTML": "<a>ad</a>", "Issue": 435, "Slot": "classified" } }
+                ]
+            }));
+        });
+
+        // Thursday before the issue
+        let response = handler(&server)
+            .handle(event(json!({ "time": "2025-08-21T17:00:00Z" })))
+            .await
+            .unwrap();
+
+        sponsors.assert();
+        assert_eq!(response.customer, "ACME");
+        assert_eq!(response.placements.len(), 1);
+        assert_eq!(response.placements[0].customer, "Initech");
+        let bookings = response.bookings.unwrap();
+        assert_eq!(bookings.send_date, "2025-08-25");
+        assert_eq!(bookings.gaps.len(), 7);
+        assert!(bookings.double_bookings.is_empty());
+        assert!(bookings.unmatched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sponsors_are_matched_by_number_without_a_time() {
+        let server = MockServer::start();
+        let sponsors = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v0/app123/Sponsors")
+                .query_param("filterByFormula", "{Issue}=435");
+            then.status(200).json_body(json!({
+                "records": [{ "id": "rec1", "fields": { "Customer": "ACME", "Issue": 435 } }]
+            }));
+        });
+
+        let response = handler(&server).handle(event(json!({}))).await.unwrap();
+
+        sponsors.assert();
+        assert_eq!(response.customer, "ACME");
+        assert_eq!(response.bookings, None);
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use shared::history::{FileIssueHistory, IssueHistory, IssueRecord};
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub struct HistoryOptions {
+    /// JSON file of the published issues (the `--issue-history` of the runs)
+    #[arg(long, env = "ISSUE_HISTORY")]
+    pub file: PathBuf,
+
+    #[command(subcommand)]
+    pub command: HistoryCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommand {
+    /// Lists the published issues, most recent first
+    List {
+        /// How many issues to list
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Prints everything that went out with an issue, as JSON
+    Show { number: u32 },
+}
+
+/// One line per issue: number, date, quote, book, sponsor and subject
+fn summary(issue: &IssueRecord) -> String {
+    format!(
+        "#{}  {}  quote {}  book {}  sponsor {}  {} links  {}",
+        issue.number,
+        issue.date,
+        issue.quote_id,
+        issue.book_id,
+        issue.sponsor.as_deref().unwrap_or("-"),
+        issue.links.len(),
+        issue.subject
+    )
+}
+
+pub fn run_history(opts: &HistoryOptions) -> Result<()> {
+    let history = FileIssueHistory::new(&opts.file);
+    match opts.command {
+        HistoryCommand::List { limit } => {
+            for issue in history.issues()?.iter().rev().take(limit) {
+                println!("{}", summary(issue));
+            }
+        }
+        HistoryCommand::Show { number } => match history.issue(number)? {
+            Some(issue) => println!("{}", serde_json::to_string_pretty(&issue)?),
+            None => bail!("Issue #{} is not in {}", number, opts.file.display()),
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+mod history;
+mod pipeline;
+
+use anyhow::bail;
+use clap::{Args, Parser, Subcommand};
+use create_issue::lint::lint_templates;
+use history::{run_history, HistoryOptions};
+use pipeline::{run_pipeline, RunOptions};
+use std::path::PathBuf;
+
+/// Runs the FullStack Bulletin automation locally, without AWS
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Replays the create-issue state machine in-process
+    Run(Box<RunOptions>),
+    /// Renders every newsletter template with sample events and reports their problems
+    LintTemplates(LintOptions),
+    /// Lists and shows the issues recorded in an issue history
+    History(HistoryOptions),
+}
+
+#[derive(Args, Debug)]
+struct LintOptions {
+    /// Directory of newsletter templates overriding the embedded ones
+    #[arg(long, env = "TEMPLATES_DIR")]
+    templates_dir: Option<PathBuf>,
+
+    /// Fails on warnings too (e.g. unused context keys)
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Run(opts) => {
+            let output = run_pipeline(&opts).await?;
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        Command::LintTemplates(opts) => {
+            let report = lint_templates(opts.templates_dir.as_deref())?;
+            for finding in &report.findings {
+                println!("{}", finding);
+            }
+            let errors = report.errors().count();
+            let warnings = report.warnings().count();
+            println!("{} error(s), {} warning(s)", errors, warnings);
+            if errors > 0 || (opts.deny_warnings && warnings > 0) {
+                bail!("The templates have problems");
+            }
+        }
+        Command::History(opts) => run_history(&opts)?,
+    }
+
+    Ok(())
+}
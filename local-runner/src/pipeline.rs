@@ -0,0 +1,576 @@
+use anyhow::{anyhow, bail, Context as _, Result};
+use clap::Args;
+use create_issue::{
+    buttondown::ButtonDownClient,
+    event_handler::{function_handler, HandlerConfig},
+    link_health::{LinkCache, LinkCheckOptions, LinkChecker, DEFAULT_CACHE_FILE},
+    template::TemplateRenderer,
+};
+use fetch_book::selection::{BookSelector, DEFAULT_AVOID_LAST as BOOK_AVOID_LAST};
+use fetch_issue_number::fetcher::fetch_last_issue_number;
+use fetch_quote::{
+    fetcher::fetch_quote,
+    history::{FileQuoteHistory, IssueQuoteHistory, QuoteHistory},
+    selection::{QuoteSelector, DEFAULT_AVOID_LAST},
+};
+use fetch_sponsor::Handler as SponsorHandler;
+use lambda_runtime::{Context, LambdaEvent};
+use reqwest::Client;
+use serde_json::{json, Value};
+use shared::history::{FileIssueHistory, IssueHistory};
+use std::{path::PathBuf, time::Duration};
+
+/// Options for a local run of the create-issue state machine.
+///
+/// Every base URL can be overridden so that the whole run can target local mock servers.
+#[derive(Args, Debug, Clone)]
+pub struct RunOptions {
+    /// JSON file used as the execution input (defaults to a synthetic scheduled event)
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+
+    /// Forces `dryRun: true` in the config passed to create-issue
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// ButtonDown archive page scraped to find the last issue number
+    #[arg(
+        long,
+        env = "ARCHIVE_URL",
+        default_value = "https://buttondown.com/fullstackbulletin/archive/"
+    )]
+    pub archive_url: String,
+
+    /// Base URL of the tech-quotes dataset
+    #[arg(
+        long,
+        env = "QUOTES_BASE_URL",
+        default_value = "https://fullstackbulletin.github.io/tech-quotes"
+    )]
+    pub quotes_base_url: String,
+
+    /// JSON file of the quotes used by past issues: when set, the quote is picked among the
+    /// ones the last 52 issues didn't use, and recorded there
+    #[arg(long, env = "QUOTE_HISTORY")]
+    pub quote_history: Option<PathBuf>,
+
+    /// JSON file of the published issues: create-issue records the issue there, fetch-book
+    /// avoids the books of the last 26 issues, and it replaces the quote history when that's
+    /// not set
+    #[arg(long, env = "ISSUE_HISTORY")]
+    pub issue_history: Option<PathBuf>,
+
+    /// Base URL of the fullstack-books dataset
+    #[arg(long, env = "BOOKS_BASE_URL", default_value = fetch_book::BASE_URL)]
+    pub books_base_url: String,
+
+    /// Base URL of the Airtable API
+    #[arg(long, env = "AIRTABLE_BASE_URL", default_value = fetch_sponsor::AIRTABLE_BASE_URL)]
+    pub airtable_base_url: String,
+
+    #[arg(long, env = "AIRTABLE_TABLE_ID", default_value = "")]
+    pub airtable_table_id: String,
+
+    #[arg(
+        long,
+        env = "AIRTABLE_APIKEY",
+        default_value = "",
+        hide_env_values = true
+    )]
+    pub airtable_api_key: String,
+
+    /// JSON file containing the links (the output of the fetch-links function)
+    #[arg(
+        long,
+        conflicts_with = "links_url",
+        required_unless_present = "links_url"
+    )]
+    pub links_file: Option<PathBuf>,
+
+    /// URL that receives the branch input as a POST and responds with the links
+    #[arg(long, env = "LINKS_URL")]
+    pub links_url: Option<String>,
+
+    /// Base URL of the ButtonDown API
+    #[arg(
+        long,
+        env = "BUTTONDOWN_BASE_URL",
+        default_value = "https://api.buttondown.com/v1"
+    )]
+    pub buttondown_base_url: String,
+
+    /// ButtonDown API key (only required when not running in dry-run mode)
+    #[arg(long, env = "BUTTONDOWN_API_KEY", hide_env_values = true)]
+    pub buttondown_api_key: Option<String>,
+
+    #[arg(long, env = "DRAFT_SUBSCRIBER_ID", default_value = "")]
+    pub draft_subscriber_id: String,
+
+    #[arg(long, env = "DRAFT_RECIPIENT_EMAIL", default_value = "")]
+    pub draft_recipient_email: String,
+
+    /// Directory of newsletter templates overriding the embedded ones
+    #[arg(long, env = "TEMPLATES_DIR")]
+    pub templates_dir: Option<PathBuf>,
+
+    /// Check every URL of the issue before creating it
+    #[arg(long)]
+    pub check_links: bool,
+
+    /// File where the link checks are kept between runs (for a day)
+    #[arg(long, env = "LINK_CACHE", default_value = DEFAULT_CACHE_FILE)]
+    pub link_cache: PathBuf,
+}
+
+const LINK_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Mimics the intrinsic `States.JsonMerge(left, right, false)`: a shallow merge where
+/// the keys of `right` win over the keys of `left`
+pub fn json_merge(left: Value, right: Value) -> Value {
+    match (left, right) {
+        (Value::Object(mut left), Value::Object(right)) => {
+            left.extend(right);
+            Value::Object(left)
+        }
+        (left, Value::Null) => left,
+        (_, right) => right,
+    }
+}
+
+/// Builds an input equivalent to the one that EventBridge sends on the weekly schedule
+fn default_execution_input() -> Value {
+    json!({
+        "detail-type": "Scheduled Event",
+        "resources": [],
+        "id": "local-runner",
+        "source": "local-runner",
+        "time": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "detail": {},
+        "region": "local",
+        "version": "0",
+        "account": "000000000000"
+    })
+}
+
+fn load_execution_input(opts: &RunOptions) -> Result<Value> {
+    match &opts.input {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read input file {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse input file {}", path.display()))
+        }
+        None => Ok(default_execution_input()),
+    }
+}
+
+/// Returns a copy of the branch input with the task result stored at `key`,
+/// which is what `ResultPath: $.<key>` does for every branch of `Fetch Data`
+fn with_result(input: &Value, key: &str, result: Value) -> Value {
+    let mut output = input.clone();
+    output[key] = result;
+    output
+}
+
+async fn fetch_quote_branch(opts: &RunOptions, input: &Value) -> Result<Value> {
+    let issue_number = input["NextIssue"]["number"]
+        .as_u64()
+        .ok_or_else(|| anyhow!("Missing NextIssue.number"))? as u32;
+    let history: Option<Box<dyn QuoteHistory + Sync>> =
+        match (&opts.quote_history, &opts.issue_history) {
+            (Some(path), _) => Some(Box::new(FileQuoteHistory::new(path))),
+            (None, Some(path)) => Some(Box::new(IssueQuoteHistory(FileIssueHistory::new(path)))),
+            (None, None) => None,
+        };
+    let selector = match &history {
+        Some(history) => QuoteSelector::HistoryAware {
+            history: history.as_ref(),
+            avoid_last: DEFAULT_AVOID_LAST,
+            seed: 0,
+        },
+        None => QuoteSelector::default(),
+    };
+    let quote = fetch_quote(&opts.quotes_base_url, issue_number, &selector)
+        .await
+        .context("Fetch Quote failed")?;
+    Ok(with_result(input, "Quote", serde_json::to_value(quote)?))
+}
+
+async fn fetch_book_branch(opts: &RunOptions, client: &Client, input: &Value) -> Result<Value> {
+    let event = LambdaEvent::new(serde_json::from_value(input.clone())?, Context::default());
+    let history = opts.issue_history.as_ref().map(FileIssueHistory::new);
+    let selector = match &history {
+        Some(history) => BookSelector::default().with_history(history, BOOK_AVOID_LAST),
+        None => BookSelector::default(),
+    };
+    let book = fetch_book::function_handler(client, &opts.books_base_url, &selector, event)
+        .await
+        .map_err(|e| anyhow!("Fetch Book failed: {}", e))?;
+    Ok(with_result(input, "Book", serde_json::to_value(book)?))
+}
+
+async fn fetch_sponsor_branch(opts: &RunOptions, client: &Client, input: &Value) -> Result<Value> {
+    let handler = SponsorHandler {
+        airtable_base_url: opts.airtable_base_url.clone(),
+        airtable_table_id: opts.airtable_table_id.clone(),
+        airtable_api_key: opts.airtable_api_key.clone(),
+        client: client.clone(),
+    };
+    let event = LambdaEvent::new(serde_json::from_value(input.clone())?, Context::default());
+    let sponsor = handler
+        .handle(event)
+        .await
+        .map_err(|e| anyhow!("Fetch Sponsor failed: {}", e))?;
+    Ok(with_result(
+        input,
+        "Sponsor",
+        serde_json::to_value(sponsor)?,
+    ))
+}
+
+async fn fetch_links_branch(opts: &RunOptions, client: &Client, input: &Value) -> Result<Value> {
+    let links: Value = match (&opts.links_file, &opts.links_url) {
+        (Some(path), _) => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read links file {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse links file {}", path.display()))?
+        }
+        (None, Some(url)) => client
+            .post(url)
+            .json(input)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Fetch Links failed")?
+            .json()
+            .await
+            .context("Failed to parse links response")?,
+        (None, None) => bail!("Either a links file or a links URL is required"),
+    };
+    Ok(with_result(input, "Links", links))
+}
+
+async fn create_issue(opts: &RunOptions, client: &Client, state: Value) -> Result<Value> {
+    let dry_run = state["config"]["dryRun"].as_bool().unwrap_or(false);
+    let buttondown_api_key = match (&opts.buttondown_api_key, dry_run) {
+        (Some(key), _) => key.clone(),
+        (None, true) => String::new(),
+        (None, false) => bail!("A ButtonDown API key is required unless running in dry-run mode"),
+    };
+
+    let handler_config = HandlerConfig {
+        publisher: ButtonDownClient::new(
+            buttondown_api_key,
+            client.clone(),
+            opts.buttondown_base_url.clone(),
+        ),
+        template_renderer: match &opts.templates_dir {
+            Some(templates_dir) => TemplateRenderer::from_dir(templates_dir)?,
+            None => TemplateRenderer::new()?,
+        },
+        draft_subscriber_id: opts.draft_subscriber_id.clone(),
+        draft_recipient_email: opts.draft_recipient_email.clone(),
+        link_checker: if opts.check_links {
+            Some(
+                LinkChecker::new(&LinkCheckOptions::default())?
+                    .with_cache(LinkCache::open(&opts.link_cache, LINK_CACHE_TTL)),
+            )
+        } else {
+            None
+        },
+        issue_history: opts.issue_history.as_ref().map(|path| {
+            Box::new(FileIssueHistory::new(path)) as Box<dyn IssueHistory + Send + Sync>
+        }),
+    };
+
+    let event = serde_json::from_value(state).context("Invalid create-issue event")?;
+    function_handler(LambdaEvent::new(event, Context::default()), &handler_config)
+        .await
+        .map_err(|e| {
+            anyhow!(
+                "Create Issue failed ({}): {}",
+                e.error_type,
+                e.error_message
+            )
+        })
+}
+
+/// Runs the same sequence of steps as `statemachine/create_issue.asl.yaml`, in-process
+pub async fn run_pipeline(opts: &RunOptions) -> Result<Value> {
+    let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+
+    // Define Defaults + Apply Defaults
+    let defaults = json!({ "dryRun": false });
+    let mut state = json!({
+        "config": json_merge(defaults, load_execution_input(opts)?)
+    });
+    if opts.dry_run {
+        state["config"]["dryRun"] = json!(true);
+    }
+
+    // Fetch Issue Number
+    let last_issue_number = fetch_last_issue_number(&opts.archive_url)
+        .await
+        .context("Fetch Issue Number failed")?;
+    state["NextIssue"] = json!({ "number": last_issue_number + 1 });
+    tracing::info!("Next issue: #{}", last_issue_number + 1);
+
+    // Fetch Data
+    let branches = tokio::try_join!(
+        fetch_quote_branch(opts, &state),
+        fetch_book_branch(opts, &client, &state),
+        fetch_sponsor_branch(opts, &client, &state),
+        fetch_links_branch(opts, &client, &state),
+    )?;
+    let results = [branches.0, branches.1, branches.2, branches.3];
+    state["data"] = json!({
+        "Quote": results[0]["Quote"],
+        "Book": results[1]["Book"],
+        "Sponsor": results[2]["Sponsor"],
+        "Links": results[3]["Links"],
+    });
+    tracing::info!("Fetched issue data");
+
+    // Create Issue
+    create_issue(opts, &client, state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    fn options_for(server: &MockServer) -> RunOptions {
+        RunOptions {
+            input: None,
+            dry_run: false,
+            archive_url: server.url("/archive/"),
+            quotes_base_url: server.url("/tech-quotes"),
+            quote_history: None,
+            issue_history: None,
+            books_base_url: server.url("/fullstack-books"),
+            airtable_base_url: server.url("/airtable"),
+            airtable_table_id: "tbl".to_string(),
+            airtable_api_key: "airtable-key".to_string(),
+            links_file: None,
+            links_url: Some(server.url("/links")),
+            buttondown_base_url: server.url("/buttondown"),
+            buttondown_api_key: Some("buttondown-key".to_string()),
+            draft_subscriber_id: "subscriber".to_string(),
+            draft_recipient_email: "editor@example.com".to_string(),
+            templates_dir: None,
+            check_links: false,
+            link_cache: PathBuf::from(DEFAULT_CACHE_FILE),
+        }
+    }
+
+    fn mock_data_sources(server: &MockServer) {
+        server.mock(|when, then| {
+            when.method(GET).path("/archive/");
+            then.status(200).body(
+                r#"<html><body><div class="email">🤓 #434: Putting the "You" in CPU</div></body></html>"#,
+            );
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/tech-quotes/quotes/stats.json");
+            then.status(200).json_body(json!({
+                "total": 1,
+                "all": "",
+                "first": "",
+                "last": "",
+                "urlPrefix": ""
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/tech-quotes/quotes/0.json");
+            then.status(200).json_body(json!({
+                "id": 0,
+                "text": "Talk is cheap. Show me the code.",
+                "author": {
+                    "id": "linus-torvalds",
+                    "name": "Linus Torvalds",
+                    "description": "Software engineer",
+                    "url": ""
+                },
+                "url": ""
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/fullstack-books/books/ids.json");
+            then.status(200)
+                .json_body(json!(["building-microservices"]));
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/fullstack-books/books/building-microservices.json");
+            then.status(200).json_body(json!({
+                "slug": "building-microservices",
+                "title": "Building Microservices",
+                "subtitle": null,
+                "authors": [{ "name": "Sam Newman" }],
+                "cover": "https://example.com/cover.jpg",
+                "links": {
+                    "amazon_us": "https://www.amazon.com/dp/1492034029",
+                    "amazon_uk": "https://www.amazon.co.uk/dp/1492034029"
+                },
+                "descriptionHtml": "<p>Microservices!</p>"
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/airtable/tbl/Sponsors")
+                // The formula depends on the day of the run
+                .query_param_exists("filterByFormula")
+                .header("Authorization", "Bearer airtable-key");
+            then.status(200).json_body(json!({ "records": [] }));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/links")
+                .json_body_partial(r#"{ "NextIssue": { "number": 435 } }"#);
+            then.status(200).json_body(json!([{
+                "title": "An Interactive Guide to SVG Paths",
+                "url": "https://joshwcomeau.com/svg/interactive-guide-to-paths",
+                "description": "Vector graphics!",
+                "image": "https://example.com/svg.png",
+                "score": 100,
+                "originalImage": "",
+                "campaignUrls": {
+                    "title": "https://joshwcomeau.com/svg/interactive-guide-to-paths?utm_content=title",
+                    "image": "https://joshwcomeau.com/svg/interactive-guide-to-paths?utm_content=image",
+                    "description": "https://joshwcomeau.com/svg/interactive-guide-to-paths?utm_content=description"
+                }
+            }]));
+        });
+    }
+
+    #[test]
+    fn test_json_merge_prefers_right_keys() {
+        let merged = json_merge(
+            json!({ "dryRun": false, "keep": 1 }),
+            json!({ "dryRun": true, "time": "2025-08-22T17:00:00Z" }),
+        );
+        assert_eq!(
+            merged,
+            json!({ "dryRun": true, "keep": 1, "time": "2025-08-22T17:00:00Z" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_pipeline() {
+        let server = MockServer::start();
+        mock_data_sources(&server);
+
+        let mut opts = options_for(&server);
+        opts.dry_run = true;
+        opts.buttondown_api_key = None;
+
+        let output = run_pipeline(&opts).await.unwrap();
+
+        assert_eq!(output["dryRun"], json!(true));
+        assert!(output["subjectLine"]
+            .as_str()
+            .unwrap()
+            .ends_with("An Interactive Guide to SVG Paths — FullStack Bulletin #435"));
+        let rendered = output["renderedContent"].as_str().unwrap();
+        assert!(rendered.contains("Talk is cheap. Show me the code."));
+        assert!(rendered.contains("Building Microservices"));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_creates_campaign() {
+        let server = MockServer::start();
+        mock_data_sources(&server);
+
+        let list_emails = server.mock(|when, then| {
+            when.method(GET).path("/buttondown/emails");
+            then.status(200)
+                .json_body(json!({ "results": [], "next": null, "count": 0 }));
+        });
+        let create_email = server.mock(|when, then| {
+            when.method(POST)
+                .path("/buttondown/emails")
+                .header("Authorization", "Token buttondown-key")
+                .json_body_partial(r#"{ "slug": "435-an-interactive-guide-to-svg-paths" }"#);
+            then.status(201)
+                .json_body(json!({ "id": "email-1", "status": "scheduled" }));
+        });
+        let send_draft = server.mock(|when, then| {
+            when.method(POST)
+                .path("/buttondown/emails/email-1/send-draft");
+            then.status(200).json_body(json!({}));
+        });
+        let get_email = server.mock(|when, then| {
+            when.method(GET).path("/buttondown/emails/email-1");
+            then.status(200)
+                .json_body(json!({ "id": "email-1", "status": "scheduled" }));
+        });
+
+        let output = run_pipeline(&options_for(&server)).await.unwrap();
+
+        list_emails.assert();
+        create_email.assert();
+        send_draft.assert();
+        get_email.assert();
+        assert_eq!(output["emailId"], json!("email-1"));
+        assert_eq!(output["emailStatus"], json!("scheduled"));
+        assert_eq!(output["emailAction"], json!("created"));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_records_the_issue_history() {
+        let server = MockServer::start();
+        mock_data_sources(&server);
+        server.mock(|when, then| {
+            when.method(GET).path("/buttondown/emails");
+            then.status(200)
+                .json_body(json!({ "results": [], "next": null, "count": 0 }));
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/buttondown/emails");
+            then.status(201)
+                .json_body(json!({ "id": "email-1", "status": "scheduled" }));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/buttondown/emails/email-1/send-draft");
+            then.status(200).json_body(json!({}));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/buttondown/emails/email-1");
+            then.status(200)
+                .json_body(json!({ "id": "email-1", "status": "scheduled" }));
+        });
+        let path = std::env::temp_dir().join(format!(
+            "local-runner-issue-history-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let mut opts = options_for(&server);
+        opts.issue_history = Some(path.clone());
+        run_pipeline(&opts).await.unwrap();
+
+        let issue = FileIssueHistory::new(&path).issue(435).unwrap().unwrap();
+        assert_eq!(issue.quote_id, 0);
+        assert_eq!(issue.book_id, "building-microservices");
+        assert_eq!(
+            issue.links,
+            vec!["https://joshwcomeau.com/svg/interactive-guide-to-paths"]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_real_run_requires_api_key() {
+        let server = MockServer::start();
+        mock_data_sources(&server);
+
+        let mut opts = options_for(&server);
+        opts.buttondown_api_key = None;
+
+        let result = run_pipeline(&opts).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use shared::schema::{schemas, SCHEMAS_DIR};
+use std::path::PathBuf;
+
+/// Writes the JSON Schema of every Lambda payload to the given directory
+/// (defaults to the `schemas` directory at the root of the repository)
+fn main() -> std::io::Result<()> {
+    let dir = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(SCHEMAS_DIR));
+    std::fs::create_dir_all(&dir)?;
+
+    for (file_name, schema) in schemas() {
+        let path = dir.join(file_name);
+        let content = serde_json::to_string_pretty(&schema)?;
+        std::fs::write(&path, content + "\n")?;
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+//! The schedule of the newsletter: an issue goes out on Monday at 17:00 UTC.
+
 use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
 
 /// Calculate next Monday at 17:00 UTC from a given reference time string
@@ -28,7 +30,7 @@ pub fn get_next_monday_from(reference_time: &str) -> Result<DateTime<Utc>, chron
     // Otherwise, find the next Monday
     let mut target = parsed_time + Duration::days(1);
     while target.weekday() != Weekday::Mon {
-        target = target + Duration::days(1);
+        target += Duration::days(1);
     }
 
     // Set to 17:00:00 UTC
@@ -0,0 +1,231 @@
+//! What went out with every published issue, so that the functions can avoid repeating a
+//! quote, a book or a link too soon.
+//!
+//! create-issue appends an [`IssueRecord`] after publishing an issue; the fetchers and the
+//! link layout read the most recent ones.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum IssueHistoryError {
+    #[error("Failed to access the issue history: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid issue history: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The content of a published issue
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueRecord {
+    pub number: u32,
+    /// The day the issue is scheduled for (`YYYY-MM-DD`)
+    pub date: String,
+    pub subject: String,
+    pub quote_id: u32,
+    pub book_id: String,
+    /// The customer of the sponsor, if the issue had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sponsor: Option<String>,
+    /// The URLs of the links, in the order of the issue
+    pub links: Vec<String>,
+    /// The links of the sponsors, with the tracking parameters they went out with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sponsor_links: Vec<SponsorLink>,
+}
+
+/// A link of a sponsor block, as the sponsor wrote it and as it went out
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SponsorLink {
+    pub customer: String,
+    /// The block of the link (`top-banner`, `sponsored-article`, `mid-banner` or
+    /// `classified`), which is also its `utm_content`
+    pub block: String,
+    pub url: String,
+    pub tracked_url: String,
+}
+
+pub trait IssueHistory {
+    /// Every recorded issue, by issue number
+    fn issues(&self) -> Result<Vec<IssueRecord>, IssueHistoryError>;
+
+    /// Record an issue, replacing the record of a previous run of the same issue
+    fn record(&self, issue: IssueRecord) -> Result<(), IssueHistoryError>;
+
+    fn issue(&self, number: u32) -> Result<Option<IssueRecord>, IssueHistoryError> {
+        Ok(self
+            .issues()?
+            .into_iter()
+            .find(|issue| issue.number == number))
+    }
+
+    /// The issues before `number`, most recent first
+    fn recent(&self, number: u32, limit: usize) -> Result<Vec<IssueRecord>, IssueHistoryError> {
+        Ok(self
+            .issues()?
+            .into_iter()
+            .rev()
+            .filter(|issue| issue.number < number)
+            .take(limit)
+            .collect())
+    }
+}
+
+impl<H: IssueHistory + ?Sized> IssueHistory for &H {
+    fn issues(&self) -> Result<Vec<IssueRecord>, IssueHistoryError> {
+        (**self).issues()
+    }
+
+    fn record(&self, issue: IssueRecord) -> Result<(), IssueHistoryError> {
+        (**self).record(issue)
+    }
+}
+
+/// A history shared between its users (e.g. a fetcher and create-issue)
+impl<H: IssueHistory + ?Sized> IssueHistory for Arc<H> {
+    fn issues(&self) -> Result<Vec<IssueRecord>, IssueHistoryError> {
+        (**self).issues()
+    }
+
+    fn record(&self, issue: IssueRecord) -> Result<(), IssueHistoryError> {
+        (**self).record(issue)
+    }
+}
+
+/// A history kept in memory, useful in tests
+#[derive(Debug, Default)]
+pub struct MemoryIssueHistory {
+    issues: Mutex<BTreeMap<u32, IssueRecord>>,
+}
+
+impl MemoryIssueHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IssueHistory for MemoryIssueHistory {
+    fn issues(&self) -> Result<Vec<IssueRecord>, IssueHistoryError> {
+        Ok(self.issues.lock().unwrap().values().cloned().collect())
+    }
+
+    fn record(&self, issue: IssueRecord) -> Result<(), IssueHistoryError> {
+        self.issues.lock().unwrap().insert(issue.number, issue);
+        Ok(())
+    }
+}
+
+/// A history stored in a JSON file, as an array of [`IssueRecord`]s sorted by issue number.
+/// A missing file is an empty history.
+#[derive(Debug, Clone)]
+pub struct FileIssueHistory {
+    path: PathBuf,
+}
+
+impl FileIssueHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl IssueHistory for FileIssueHistory {
+    fn issues(&self) -> Result<Vec<IssueRecord>, IssueHistoryError> {
+        let mut issues: Vec<IssueRecord> = match std::fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error.into()),
+        };
+        issues.sort_by_key(|issue| issue.number);
+        Ok(issues)
+    }
+
+    fn record(&self, issue: IssueRecord) -> Result<(), IssueHistoryError> {
+        let mut issues = self.issues()?;
+        match issues.binary_search_by_key(&issue.number, |recorded| recorded.number) {
+            Ok(index) => issues[index] = issue,
+            Err(index) => issues.insert(index, issue),
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&issues)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(number: u32, quote_id: u32) -> IssueRecord {
+        IssueRecord {
+            number,
+            date: "2025-08-25".to_string(),
+            subject: format!("Issue #{}", number),
+            quote_id,
+            book_id: "building-microservices".to_string(),
+            sponsor: None,
+            links: vec![format!("https://example.com/{}", number)],
+            sponsor_links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_file_history() {
+        let path = std::env::temp_dir().join(format!(
+            "shared-issue-history-{}/issues.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let history = FileIssueHistory::new(&path);
+
+        assert_eq!(history.issues().unwrap(), Vec::new());
+        history.record(issue(434, 12)).unwrap();
+        history.record(issue(432, 7)).unwrap();
+        history.record(issue(433, 3)).unwrap();
+        // A re-run replaces the record of the issue
+        history.record(issue(434, 5)).unwrap();
+
+        let reopened = FileIssueHistory::new(&path);
+        let numbers: Vec<u32> = reopened
+            .issues()
+            .unwrap()
+            .iter()
+            .map(|i| i.number)
+            .collect();
+        assert_eq!(numbers, vec![432, 433, 434]);
+        assert_eq!(reopened.issue(434).unwrap(), Some(issue(434, 5)));
+        assert_eq!(reopened.issue(435).unwrap(), None);
+        let recent: Vec<u32> = reopened
+            .recent(434, 5)
+            .unwrap()
+            .iter()
+            .map(|i| i.number)
+            .collect();
+        assert_eq!(recent, vec![433, 432]);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(history.issues(), Err(IssueHistoryError::Json(_))));
+    }
+
+    #[test]
+    fn test_record_format() {
+        let mut record = issue(435, 1);
+        record.sponsor = Some("Acme".to_string());
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!(value["quoteId"], 1);
+        assert_eq!(value["bookId"], "building-microservices");
+        assert_eq!(value["sponsor"], "Acme");
+
+        let history = MemoryIssueHistory::new();
+        history.record(record.clone()).unwrap();
+        assert_eq!(history.recent(436, 1).unwrap(), vec![record]);
+    }
+}
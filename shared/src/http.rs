@@ -0,0 +1,345 @@
+//! Retries for the HTTP calls of the fetcher functions.
+//!
+//! The `Retry` blocks of the state machine re-run a whole Lambda, which is slow and only
+//! covers Lambda service errors. This retries transient failures of a single request
+//! in-process instead: only idempotent `GET`s are retried, on 5xx and 429 responses and
+//! on connection errors, with a bounded exponential backoff that honours `Retry-After`:
+//! the requested wait is waited in full, unless it's longer than the policy allows, in
+//! which case the response is returned without retrying.
+
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, Method, RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retries)
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every following one
+    pub initial_delay: Duration,
+    /// Upper bound of the backoff
+    pub max_delay: Duration,
+    /// Delay after a 429 without `Retry-After`, for the APIs that document a fixed one
+    /// (e.g. Airtable's 30 seconds), instead of the backoff
+    pub rate_limit_delay: Option<Duration>,
+    /// Longest wait the server can ask for, with `Retry-After` or the `rate_limit_delay`:
+    /// the response asking for a longer one is returned without retrying
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            rate_limit_delay: None,
+            max_retry_after: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The same number of retries, without waiting between them unless the server asks to
+    /// with `Retry-After`
+    pub fn without_delays(self) -> Self {
+        Self {
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            rate_limit_delay: self.rate_limit_delay.map(|_| Duration::ZERO),
+            ..self
+        }
+    }
+
+    /// Exponential backoff with "equal jitter": half of the delay is fixed, the other half random
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let half = delay / 2;
+        half + half.mul_f64(rand::rng().random::<f64>())
+    }
+
+    /// The delay before a retry, at least the one the server asked for, or `None` when the
+    /// server asked for more than `max_retry_after`
+    fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        match retry_after {
+            Some(retry_after) if retry_after > self.max_retry_after => None,
+            Some(retry_after) => Some(retry_after.max(self.backoff(retry))),
+            None => Some(self.backoff(retry)),
+        }
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// `Retry-After` in seconds (HTTP dates are ignored and fall back to the backoff)
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Send a request, retrying it according to `policy` if it's a `GET`.
+///
+/// Once the retries are exhausted the last response is returned as is, so callers still
+/// decide how to handle an error status (e.g. with `error_for_status`).
+pub async fn send(request: RequestBuilder, policy: &RetryPolicy) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+
+    if request.method() != Method::GET {
+        return client.execute(request).await;
+    }
+
+    let mut retry = 0;
+    loop {
+        // GET requests have no streaming body, so they can always be cloned
+        let attempt = request.try_clone().expect("GET requests can be cloned");
+        let result = client.execute(attempt).await;
+
+        if retry >= policy.max_retries {
+            return result;
+        }
+        let delay = match &result {
+            Ok(response) if is_retryable_status(response.status()) => {
+                let rate_limit_delay = policy
+                    .rate_limit_delay
+                    .filter(|_| response.status() == StatusCode::TOO_MANY_REQUESTS);
+                policy.delay(retry, retry_after(response).or(rate_limit_delay))
+            }
+            Err(error) if error.is_connect() => policy.delay(retry, None),
+            _ => return result,
+        };
+        let Some(delay) = delay else {
+            tracing::warn!(
+                "GET {} was rate limited for longer than {:?}, not retrying",
+                request.url(),
+                policy.max_retry_after
+            );
+            return result;
+        };
+
+        tracing::warn!(
+            "GET {} failed ({}), retrying in {:?}",
+            request.url(),
+            match &result {
+                Ok(response) => response.status().to_string(),
+                Err(error) => error.to_string(),
+            },
+            delay
+        );
+        tokio::time::sleep(delay).await;
+        retry += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::first_calls;
+    use httpmock::prelude::*;
+    use reqwest::Client;
+    use std::time::Instant;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::default().without_delays()
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors_until_success() {
+        let server = MockServer::start();
+        let failures = server.mock(|when, then| {
+            when.method(GET).path("/books").matches(first_calls!(2));
+            then.status(503);
+        });
+        let success = server.mock(|when, then| {
+            when.method(GET).path("/books");
+            then.status(200).body("ok");
+        });
+
+        let response = send(Client::new().get(server.url("/books")), &policy())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "ok");
+        failures.assert_hits(2);
+        success.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_honours_retry_after() {
+        let server = MockServer::start();
+        let rate_limited = server.mock(|when, then| {
+            when.method(GET).path("/quotes").matches(first_calls!(1));
+            then.status(429).header("Retry-After", "1");
+        });
+        let success = server.mock(|when, then| {
+            when.method(GET).path("/quotes");
+            then.status(200);
+        });
+        // The full `Retry-After` is waited, even beyond the bound of the backoff
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(100),
+            ..RetryPolicy::default()
+        };
+
+        let started = Instant::now();
+        let response = send(Client::new().get(server.url("/quotes")), &policy)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        rate_limited.assert_hits(1);
+        success.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_when_retry_after_exceeds_the_budget() {
+        let server = MockServer::start();
+        let rate_limited = server.mock(|when, then| {
+            when.method(GET).path("/links");
+            then.status(429).header("Retry-After", "30");
+        });
+        let policy = RetryPolicy {
+            max_delay: Duration::from_secs(5),
+            max_retry_after: Duration::from_secs(10),
+            ..RetryPolicy::default()
+        };
+
+        let started = Instant::now();
+        let response = send(Client::new().get(server.url("/links")), &policy)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 429);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        rate_limited.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_waits_the_rate_limit_delay() {
+        let server = MockServer::start();
+        let rate_limited = server.mock(|when, then| {
+            when.method(GET).path("/sponsors").matches(first_calls!(1));
+            then.status(429);
+        });
+        let success = server.mock(|when, then| {
+            when.method(GET).path("/sponsors");
+            then.status(200);
+        });
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(1),
+            rate_limit_delay: Some(Duration::from_millis(500)),
+            ..RetryPolicy::default()
+        };
+
+        let started = Instant::now();
+        let response = send(Client::new().get(server.url("/sponsors")), &policy)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert!(started.elapsed() >= Duration::from_millis(500));
+        rate_limited.assert_hits(1);
+        success.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_returns_last_response_when_retries_are_exhausted() {
+        let server = MockServer::start();
+        let failures = server.mock(|when, then| {
+            when.method(GET).path("/sponsors");
+            then.status(500);
+        });
+
+        let response = send(Client::new().get(server.url("/sponsors")), &policy())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 500);
+        failures.assert_hits(4);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_client_errors() {
+        let server = MockServer::start();
+        let not_found = server.mock(|when, then| {
+            when.method(GET).path("/missing");
+            then.status(404);
+        });
+
+        let response = send(Client::new().get(server.url("/missing")), &policy())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 404);
+        not_found.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_other_methods() {
+        let server = MockServer::start();
+        let failures = server.mock(|when, then| {
+            when.method(POST).path("/emails");
+            then.status(503);
+        });
+
+        let response = send(Client::new().post(server.url("/emails")), &policy())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 503);
+        failures.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_retries_connection_errors() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            ..policy()
+        };
+
+        let error = send(Client::new().get("http://127.0.0.1:1/books"), &policy)
+            .await
+            .unwrap_err();
+
+        assert!(error.is_connect());
+    }
+
+    #[test]
+    fn test_delays_are_bounded() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            rate_limit_delay: None,
+            max_retry_after: Duration::from_secs(30),
+        };
+
+        for retry in 0..10 {
+            let expected = (Duration::from_millis(100) * 2u32.pow(retry)).min(policy.max_delay);
+            let delay = policy.delay(retry, None).unwrap();
+            assert!(delay >= expected / 2 && delay <= expected, "{:?}", delay);
+        }
+        // The wait asked by the server isn't capped by the backoff bound, only refused
+        // beyond `max_retry_after`
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(30))),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(31))), None);
+    }
+}
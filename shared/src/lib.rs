@@ -1,12 +1,16 @@
-use serde::Deserialize;
+//! Types and helpers shared by the Lambda functions of the create-issue state machine.
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct Issue {
-    pub number: u32,
-}
+pub mod datetime_utils;
+pub mod history;
+pub mod http;
+mod model;
+pub mod schema;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct Event {
-    #[serde(rename = "NextIssue")]
-    pub next_issue: Issue,
-}
+pub use model::{
+    Book, BookLinks, BookResponse, BookingReport, CampaignUrls, Config, CreateIssueEvent, Data,
+    DoubleBooking, EditorialOverrides, Event, ExistingEmailMode, FetchConfig, InjectedLink, Issue,
+    Link, LinkAnnotation, LinkGrouping, LinkLayout, Quote, Sponsor, SponsorPlacement,
+    SponsorResponse, SponsorSlot, UnmatchedBooking,
+};
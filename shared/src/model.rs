@@ -0,0 +1,550 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub number: u32,
+}
+
+/// Input of the fetch-* functions: the state machine input after `Fetch Issue Number`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Event {
+    #[serde(rename = "NextIssue")]
+    pub next_issue: Issue,
+    /// The execution input, of which the fetchers only read their overrides
+    #[serde(default)]
+    pub config: FetchConfig,
+}
+
+/// The fields of the execution input that change what the fetch-* functions pick
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct FetchConfig {
+    /// The book of the issue, instead of the one fetch-book would pick
+    #[serde(rename = "bookId", default, skip_serializing_if = "Option::is_none")]
+    pub book_id: Option<String>,
+    /// The time of the scheduled event, from which the send date of the issue follows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+}
+
+/// Input of the create-issue function: the state machine input after `Fetch Data`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct CreateIssueEvent {
+    pub config: Config,
+    #[serde(rename = "NextIssue")]
+    pub next_issue: Issue,
+    pub data: Data,
+}
+
+/// The scheduled EventBridge event merged with the defaults of the state machine
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Config {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    #[serde(rename = "detail-type")]
+    pub detail_type: String,
+    pub resources: Vec<String>,
+    pub id: String,
+    pub source: String,
+    pub time: String,
+    pub detail: HashMap<String, serde_json::Value>,
+    pub region: String,
+    pub version: String,
+    pub account: String,
+    /// What to do when an email already exists for the issue (defaults to `skip`)
+    #[serde(
+        rename = "onExistingEmail",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub on_existing_email: Option<ExistingEmailMode>,
+    /// The create-issue template to render (defaults to `newsletter`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// How the links are distributed between the sections of the newsletter
+    #[serde(
+        rename = "linkLayout",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub link_layout: Option<LinkLayout>,
+    /// Editorial changes to the links of this issue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<EditorialOverrides>,
+    /// The intro of the issue, replacing the one create-issue generates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro: Option<String>,
+}
+
+/// How create-issue reacts to an email that was already created for the same issue,
+/// e.g. when the state machine is re-run
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExistingEmailMode {
+    /// Update the subject, body and publish date of the existing email
+    Update,
+    /// Leave the existing email untouched
+    #[default]
+    Skip,
+    /// Fail the execution
+    Fail,
+}
+
+/// How create-issue distributes the links, in the order of fetch-links, between the
+/// sections of the newsletter. The first link is always the primary one.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct LinkLayout {
+    /// Links listed with their description after the primary one
+    #[serde(rename = "secondaryLinks")]
+    pub secondary_links: usize,
+    /// Links listed by title only at the end (all the remaining ones when missing)
+    #[serde(rename = "extraLinks", skip_serializing_if = "Option::is_none")]
+    pub extra_links: Option<usize>,
+    /// Keep the links of the same group next to each other within each section
+    #[serde(rename = "groupBy", skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<LinkGrouping>,
+    /// The most links from the same domain in the whole issue (at least 1)
+    #[serde(rename = "maxPerDomain", skip_serializing_if = "Option::is_none")]
+    pub max_per_domain: Option<usize>,
+    /// Leave out the links that were already in one of this many previous issues (needs an
+    /// issue history)
+    #[serde(rename = "avoidRecentIssues", skip_serializing_if = "Option::is_none")]
+    pub avoid_recent_issues: Option<usize>,
+}
+
+impl Default for LinkLayout {
+    fn default() -> Self {
+        Self {
+            secondary_links: 6,
+            extra_links: None,
+            group_by: None,
+            max_per_domain: None,
+            avoid_recent_issues: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkGrouping {
+    /// By domain, ignoring `www.`
+    Domain,
+    /// By kind of content: repository, video or article
+    Kind,
+}
+
+/// Per-issue editorial changes to the links, applied before the [`LinkLayout`].
+/// Links are identified by their URL.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct EditorialOverrides {
+    /// The link to use as the primary one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<String>,
+    /// Links to leave out of the issue
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Links to move to the top, in this order (the primary link still comes first)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    /// New titles and descriptions, by URL
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, LinkAnnotation>,
+    /// Hand-written links to add to the issue
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inject: Vec<InjectedLink>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkAnnotation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct InjectedLink {
+    pub url: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Where to insert the link among the others (0 makes it the primary one),
+    /// after all of them when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+/// The outputs of the `Fetch Data` branches, as selected by its `ResultSelector`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Data {
+    #[serde(rename = "Quote")]
+    pub quote: Quote,
+    #[serde(rename = "Book")]
+    pub book: Book,
+    #[serde(rename = "Sponsor")]
+    pub sponsor: Sponsor,
+    #[serde(rename = "Links")]
+    pub links: Vec<Link>,
+}
+
+/// Output of the fetch-quote function
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub id: u32,
+    pub text: String,
+    pub author: String,
+    #[serde(rename = "authorDescription")]
+    pub author_description: String,
+    #[serde(rename = "authorUrl", skip_serializing_if = "Option::is_none")]
+    pub author_url: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Book {
+    pub id: String,
+    pub title: String,
+    pub author: String,
+    pub links: BookLinks,
+    #[serde(rename = "coverPicture")]
+    pub cover_picture: String,
+    pub description: String,
+}
+
+/// Output of the fetch-book function
+pub type BookResponse = Book;
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct BookLinks {
+    pub us: String,
+    pub uk: String,
+    /// Any other store or download link (e.g. `free`)
+    #[serde(flatten)]
+    pub other: BTreeMap<String, String>,
+}
+
+/// The sponsors of an issue: the fields are the ones of the top slot (a banner after the
+/// quote and a sponsored article at the end), the other slots are in `placements`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct Sponsor {
+    pub banner_html: String,
+    pub sponsored_article_html: String,
+    pub customer: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub placements: Vec<SponsorPlacement>,
+    /// What fetch-sponsor noticed in the booking calendar, when the send date is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bookings: Option<BookingReport>,
+}
+
+/// A sponsor of one of the other slots of an issue
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct SponsorPlacement {
+    pub slot: SponsorSlot,
+    pub customer: String,
+    pub html: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SponsorSlot {
+    /// A banner between the secondary links (one per issue)
+    MidBanner,
+    /// An entry of the classifieds list, before the closing
+    Classified,
+}
+
+/// The bookings of the sponsors around an issue
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BookingReport {
+    /// The day the issue is sent (`YYYY-MM-DD`)
+    pub send_date: String,
+    /// Slots booked more than once for the same day
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub double_bookings: Vec<DoubleBooking>,
+    /// The send days of the next weeks without a top banner sponsor
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<String>,
+    /// Paid bookings that no issue is going to have
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmatched: Vec<UnmatchedBooking>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct DoubleBooking {
+    pub date: String,
+    /// `top-banner` or `mid-banner`
+    pub slot: String,
+    /// In the order of Airtable: the first one gets the slot
+    pub customers: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct UnmatchedBooking {
+    pub customer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<u32>,
+    pub reason: String,
+}
+
+/// Output of the fetch-sponsor function
+pub type SponsorResponse = Sponsor;
+
+/// A single entry of the fetch-links output
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Link {
+    pub title: String,
+    pub url: String,
+    pub description: String,
+    pub image: Option<String>,
+    pub score: u32,
+    #[serde(rename = "originalImage")]
+    pub original_image: String,
+    #[serde(rename = "campaignUrls")]
+    pub campaign_urls: CampaignUrls,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct CampaignUrls {
+    pub title: String,
+    pub image: String,
+    pub description: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const ISSUE_435: &str = include_str!("../../functions/create-issue/events/issue-435.json");
+
+    fn sample_book() -> Book {
+        Book {
+            id: "building-microservices".to_string(),
+            title: "Building Microservices".to_string(),
+            author: "Sam Newman".to_string(),
+            links: BookLinks {
+                us: "https://www.amazon.com/dp/1492034029".to_string(),
+                uk: "https://www.amazon.co.uk/dp/1492034029".to_string(),
+                other: BTreeMap::from([(
+                    "free".to_string(),
+                    "https://example.com/free".to_string(),
+                )]),
+            },
+            cover_picture: "https://example.com/cover.jpg".to_string(),
+            description: "<p>Microservices!</p>".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_create_issue_event_round_trip() {
+        let original: Value = serde_json::from_str(ISSUE_435).unwrap();
+
+        let event: CreateIssueEvent = serde_json::from_value(original.clone()).unwrap();
+        let serialized = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(serialized, original);
+        assert_eq!(event.next_issue.number, 435);
+    }
+
+    #[test]
+    fn test_on_existing_email_mode() {
+        let mut original: Value = serde_json::from_str(ISSUE_435).unwrap();
+
+        let event: CreateIssueEvent = serde_json::from_value(original.clone()).unwrap();
+        assert_eq!(event.config.on_existing_email, None);
+
+        original["config"]["onExistingEmail"] = json!("update");
+        let event: CreateIssueEvent = serde_json::from_value(original.clone()).unwrap();
+        assert_eq!(
+            event.config.on_existing_email,
+            Some(ExistingEmailMode::Update)
+        );
+        assert_eq!(serde_json::to_value(&event).unwrap(), original);
+
+        original["config"]["onExistingEmail"] = json!("overwrite");
+        assert!(serde_json::from_value::<CreateIssueEvent>(original).is_err());
+    }
+
+    #[test]
+    fn test_link_layout() {
+        let mut original: Value = serde_json::from_str(ISSUE_435).unwrap();
+
+        original["config"]["linkLayout"] = json!({ "extraLinks": 3, "groupBy": "kind" });
+        let event: CreateIssueEvent = serde_json::from_value(original.clone()).unwrap();
+        assert_eq!(
+            event.config.link_layout,
+            Some(LinkLayout {
+                extra_links: Some(3),
+                group_by: Some(LinkGrouping::Kind),
+                ..LinkLayout::default()
+            })
+        );
+
+        original["config"]["linkLayout"] = json!({ "groupBy": "size" });
+        assert!(serde_json::from_value::<CreateIssueEvent>(original).is_err());
+    }
+
+    #[test]
+    fn test_editorial_overrides() {
+        let mut original: Value = serde_json::from_str(ISSUE_435).unwrap();
+
+        original["config"]["overrides"] = json!({
+            "primary": "https://example.com/pinned",
+            "annotations": { "https://example.com/a": { "title": "A better title" } },
+            "inject": [{ "url": "https://example.com/new", "title": "New", "position": 2 }]
+        });
+        let event: CreateIssueEvent = serde_json::from_value(original.clone()).unwrap();
+        let overrides = event.config.overrides.clone().unwrap();
+        assert_eq!(
+            overrides.primary.as_deref(),
+            Some("https://example.com/pinned")
+        );
+        assert!(overrides.exclude.is_empty());
+        assert_eq!(
+            overrides.annotations["https://example.com/a"],
+            LinkAnnotation {
+                title: Some("A better title".to_string()),
+                description: None
+            }
+        );
+        assert_eq!(overrides.inject[0].description, "");
+        assert_eq!(overrides.inject[0].position, Some(2));
+        assert_eq!(serde_json::to_value(&event).unwrap(), original);
+    }
+
+    #[test]
+    fn test_fetcher_event_ignores_other_fields() {
+        let event: Event = serde_json::from_str(ISSUE_435).unwrap();
+        assert_eq!(event.next_issue, Issue { number: 435 });
+    }
+
+    #[test]
+    fn test_book_wire_format() {
+        let serialized = serde_json::to_value(sample_book()).unwrap();
+
+        assert_eq!(
+            serialized,
+            json!({
+                "id": "building-microservices",
+                "title": "Building Microservices",
+                "author": "Sam Newman",
+                "links": {
+                    "us": "https://www.amazon.com/dp/1492034029",
+                    "uk": "https://www.amazon.co.uk/dp/1492034029",
+                    "free": "https://example.com/free"
+                },
+                "coverPicture": "https://example.com/cover.jpg",
+                "description": "<p>Microservices!</p>"
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<Book>(serialized).unwrap(),
+            sample_book()
+        );
+    }
+
+    #[test]
+    fn test_quote_wire_format() {
+        let quote = Quote {
+            id: 47,
+            text: "Computers are useless. They can only give you answers".to_string(),
+            author: "Pablo Picasso".to_string(),
+            author_description: "Artist".to_string(),
+            author_url: None,
+        };
+
+        let serialized = serde_json::to_value(&quote).unwrap();
+
+        assert_eq!(
+            serialized,
+            json!({
+                "id": 47,
+                "text": "Computers are useless. They can only give you answers",
+                "author": "Pablo Picasso",
+                "authorDescription": "Artist"
+            })
+        );
+        assert_eq!(serde_json::from_value::<Quote>(serialized).unwrap(), quote);
+    }
+
+    #[test]
+    fn test_sponsor_wire_format() {
+        let sponsor = Sponsor {
+            banner_html: "<a>banner</a>".to_string(),
+            sponsored_article_html: "<p>article</p>".to_string(),
+            customer: "ACME".to_string(),
+            placements: Vec::new(),
+            bookings: None,
+        };
+
+        let serialized = serde_json::to_value(&sponsor).unwrap();
+
+        assert_eq!(
+            serialized,
+            json!({
+                "banner_html": "<a>banner</a>",
+                "sponsored_article_html": "<p>article</p>",
+                "customer": "ACME"
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<Sponsor>(serialized).unwrap(),
+            sponsor
+        );
+    }
+
+    #[test]
+    fn test_sponsor_placements_wire_format() {
+        let sponsor: Sponsor = serde_json::from_value(json!({
+            "banner_html": "",
+            "sponsored_article_html": "",
+            "customer": "",
+            "placements": [
+                { "slot": "mid-banner", "customer": "ACME", "html": "<a>mid</a>" },
+                { "slot": "classified", "customer": "Initech", "html": "<a>ad</a>" }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(sponsor.placements[0].slot, SponsorSlot::MidBanner);
+        assert_eq!(sponsor.placements[1].slot, SponsorSlot::Classified);
+        assert_eq!(sponsor.placements[1].customer, "Initech");
+    }
+
+    #[test]
+    fn test_link_wire_format() {
+        let link = json!({
+            "title": "Glass3D generator",
+            "url": "https://glass3d.dev",
+            "description": "A modern 3d glassmorphism generator",
+            "image": null,
+            "score": 33,
+            "originalImage": "https://example.com/original.png",
+            "campaignUrls": {
+                "title": "https://glass3d.dev?utm_content=title",
+                "image": "https://glass3d.dev?utm_content=image",
+                "description": "https://glass3d.dev?utm_content=description"
+            }
+        });
+
+        let deserialized: Link = serde_json::from_value(link.clone()).unwrap();
+
+        assert_eq!(
+            deserialized.original_image,
+            "https://example.com/original.png"
+        );
+        assert_eq!(
+            deserialized.campaign_urls.title,
+            "https://glass3d.dev?utm_content=title"
+        );
+        assert_eq!(serde_json::to_value(&deserialized).unwrap(), link);
+    }
+}
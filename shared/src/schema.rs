@@ -0,0 +1,102 @@
+use schemars::{schema_for, JsonSchema};
+use serde_json::Value;
+
+use crate::{
+    Book, BookResponse, CreateIssueEvent, Data, Event, Link, Quote, Sponsor, SponsorResponse,
+};
+
+/// Version of the payload contract, written in every exported document.
+/// Bump it whenever a change to the types is not backwards compatible.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Directory where the exported schemas are checked in
+pub const SCHEMAS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../schemas");
+
+fn document<T: JsonSchema>(title: &str, description: &str) -> Value {
+    let mut schema = schema_for!(T);
+    schema.insert("title".to_string(), title.into());
+    schema.insert("description".to_string(), description.into());
+    schema.insert("version".to_string(), SCHEMA_VERSION.into());
+    schema.to_value()
+}
+
+/// The JSON Schema documents of every Lambda input and output payload, by file name
+pub fn schemas() -> Vec<(&'static str, Value)> {
+    vec![
+        (
+            "event.schema.json",
+            document::<Event>("Event", "Input of the fetch-* functions"),
+        ),
+        (
+            "create-issue-event.schema.json",
+            document::<CreateIssueEvent>("CreateIssueEvent", "Input of the create-issue function"),
+        ),
+        (
+            "data.schema.json",
+            document::<Data>("Data", "The outputs of the `Fetch Data` branches"),
+        ),
+        (
+            "link.schema.json",
+            document::<Link>("Link", "A single entry of the fetch-links output"),
+        ),
+        (
+            "quote.schema.json",
+            document::<Quote>("Quote", "Output of the fetch-quote function"),
+        ),
+        (
+            "book.schema.json",
+            document::<Book>("Book", "The book of the week"),
+        ),
+        (
+            "sponsor.schema.json",
+            document::<Sponsor>("Sponsor", "The sponsor of the issue"),
+        ),
+        (
+            "book-response.schema.json",
+            document::<BookResponse>("BookResponse", "Output of the fetch-book function"),
+        ),
+        (
+            "sponsor-response.schema.json",
+            document::<SponsorResponse>("SponsorResponse", "Output of the fetch-sponsor function"),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_committed_schemas_match_types() {
+        for (file_name, expected) in schemas() {
+            let path = Path::new(SCHEMAS_DIR).join(file_name);
+            let committed: Value = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_else(|| panic!("Cannot read {}", path.display()));
+
+            assert_eq!(
+                committed, expected,
+                "{} is out of date, run `cargo run -p shared --bin export-schemas`",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_schemas_carry_version() {
+        for (file_name, schema) in schemas() {
+            assert_eq!(schema["version"], SCHEMA_VERSION, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn test_schema_uses_wire_names() {
+        let book = document::<Book>("Book", "");
+        let properties = book["properties"].as_object().unwrap();
+
+        assert!(properties.contains_key("coverPicture"));
+        assert!(!properties.contains_key("cover_picture"));
+    }
+}
@@ -0,0 +1,23 @@
+//! Helpers for the tests of the crates using `shared`, behind the `test-support` feature.
+
+pub use httpmock::prelude::HttpMockRequest;
+
+/// An httpmock matcher of the first `n` calls to a mock, for the tests of the retries:
+/// httpmock has no response sequences, so a mock using it stops matching after `n` calls
+/// and the retry falls through to the next mock. Every use has its own counter.
+///
+/// ```ignore
+/// when.method(GET).path("/books").matches(first_calls!(2));
+/// ```
+#[macro_export]
+macro_rules! first_calls {
+    ($n:expr) => {
+        |_: &$crate::test_support::HttpMockRequest| -> bool {
+            static CALLS: ::std::sync::atomic::AtomicUsize =
+                ::std::sync::atomic::AtomicUsize::new(0);
+            CALLS.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst) < $n
+        }
+    };
+}
+
+pub use crate::first_calls;